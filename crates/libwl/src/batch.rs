@@ -0,0 +1,134 @@
+use radius::error::RadiusError;
+use radius::packet::Packet;
+use std::mem;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+// Result of encoding a request, the packet with its calculated Request
+// Authenticator and the encoded packet.
+type Encoded = Result<(Packet, Vec<u8>), RadiusError>;
+
+// Request waiting in the batch to be encoded with the shared `secret`.
+struct Queued {
+    packet: Packet,
+    secret: Vec<u8>,
+    encoded: oneshot::Sender<Encoded>,
+}
+
+/// Requests of a worker waiting to be encoded together.
+///
+/// Requests with hashed Request Authenticators, like Accounting-Request, are
+/// queued by the sessions and encoded at once with `Packet::encode_requests`
+/// when the worker flushes the batch after every turn of its sessions, so the
+/// authenticators of all the requests sent within one turn are calculated
+/// with the multi-buffer MD5.
+#[derive(Default)]
+pub struct Batch {
+    queued: Mutex<Vec<Queued>>,
+}
+
+impl Batch {
+    pub fn new() -> Batch {
+        Batch::default()
+    }
+
+    // Queues the `packet` to be encoded with the shared `secret` on the next
+    // flush and waits for it.
+    pub async fn encode(&self, packet: Packet, secret: &[u8]) -> Encoded {
+        let (sender, receiver) = oneshot::channel();
+        self.queued.lock().unwrap().push(Queued {
+            packet,
+            secret: secret.to_vec(),
+            encoded: sender,
+        });
+
+        // the batch is dropped with the worker only, after all its sessions
+        receiver.await.expect("batch dropped with requests queued")
+    }
+
+    // Encodes all the queued requests, the requests with the same secret
+    // together. If a request of a batch can't be encoded, the requests of the
+    // batch are encoded one by one, so only that request fails.
+    pub fn flush(&self) {
+        let mut queued = mem::take(&mut *self.queued.lock().unwrap());
+
+        while !queued.is_empty() {
+            let secret = queued[0].secret.clone();
+            let (same, others): (Vec<Queued>, Vec<Queued>) =
+                queued.into_iter().partition(|q| q.secret == secret);
+            queued = others;
+
+            let (mut packets, senders): (Vec<Packet>, Vec<_>) =
+                same.into_iter().map(|q| (q.packet, q.encoded)).unzip();
+            match Packet::encode_requests(&mut packets, &secret) {
+                Ok(bufs) => {
+                    for ((packet, buf), sender) in packets.into_iter().zip(bufs).zip(senders) {
+                        let _ = sender.send(Ok((packet, buf)));
+                    }
+                }
+                Err(_) => {
+                    for (mut packet, sender) in packets.into_iter().zip(senders) {
+                        let encoded = packet.encode_request(&secret).map(|buf| (packet, buf));
+                        let _ = sender.send(encoded);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+    use radius::attribute::Attribute;
+    use radius::packet::Code;
+    use tokio::task;
+
+    fn accounting_request(identifier: u8, attributes: usize) -> Packet {
+        let mut packet = Packet::new(Code::AccountingRequest, identifier);
+        for _ in 0..attributes {
+            let mut attr = Attribute::new(25, None);
+            attr.set_value(&[identifier; 253]).unwrap();
+            packet.add_attribute(attr);
+        }
+        packet
+    }
+
+    #[tokio::test]
+    async fn flush_test() {
+        let batch = Batch::new();
+        let packets = [
+            (accounting_request(1, 1), b"secret".as_slice()),
+            (accounting_request(2, 2), b"other".as_slice()),
+            (accounting_request(3, 3), b"secret".as_slice()),
+            // too long to be encoded
+            (accounting_request(4, 17), b"secret".as_slice()),
+        ];
+
+        // the requests are encoded when the batch is flushed only
+        let flush = async {
+            task::yield_now().await;
+            assert_eq!(batch.queued.lock().unwrap().len(), packets.len());
+            batch.flush();
+        };
+        let (first, second, third, fourth, ()) = tokio::join!(
+            batch.encode(packets[0].0.clone(), packets[0].1),
+            batch.encode(packets[1].0.clone(), packets[1].1),
+            batch.encode(packets[2].0.clone(), packets[2].1),
+            batch.encode(packets[3].0.clone(), packets[3].1),
+            flush,
+        );
+
+        // requests encoded together are encoded the same way as one by one
+        for ((packet, secret), encoded) in packets.iter().zip([first, second, third]) {
+            let (encoded, buf) = encoded.unwrap();
+            let mut expected = packet.clone();
+            assert_eq!(buf, expected.encode_request(secret).unwrap());
+            assert_eq!(encoded.authenticator(), expected.authenticator());
+        }
+
+        // other requests of the batch are encoded even if one of them fails
+        assert!(matches!(fourth, Err(RadiusError::PacketTooLong(_))));
+        assert!(batch.queued.lock().unwrap().is_empty());
+    }
+}
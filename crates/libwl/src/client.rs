@@ -0,0 +1,372 @@
+use super::stats::STATS;
+use radius::packet::MAX_PACKET_LENGTH;
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddr, UdpSocket as StdUdpSocket};
+use std::sync::{Arc, Mutex};
+use tokio::net::UdpSocket;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+use tokio::time::{timeout_at, Duration, Instant};
+
+// Number of distinct RADIUS Identifiers, so the max number of requests
+// outstanding on one socket.
+const IDENTIFIERS: usize = 256;
+// Number of received datagrams queued for a request, further datagrams are
+// dropped until the request verifies the queued ones.
+const QUEUED_REPLIES: usize = 8;
+
+/// Outcome of a RADIUS request sent with `PendingRequest::send`.
+#[derive(Debug)]
+pub enum Reply<T, E> {
+    /// The reply accepted by the request.
+    Received(T),
+    /// Only replies rejected by the request were received, the argument
+    /// contains the reason the last of them was rejected for.
+    Rejected(E),
+    /// No reply was received after all the retransmissions.
+    Timeout,
+}
+
+/// Pool of UDP sockets RADIUS requests are sent from.
+///
+/// Every outstanding request is identified by its socket and the RADIUS
+/// Identifier. When all 256 identifiers of the pooled sockets are in use, an
+/// extra socket is bound, so new requests never wait for a free identifier
+/// and never reuse the identifier of an outstanding request.
+///
+/// The pooled sockets are shared by all the workers, so they are served by
+/// the given runtime rather than by the runtime of the worker that binds them.
+#[derive(Debug)]
+pub struct Client {
+    runtime: Handle,
+    sockets: Mutex<Vec<Arc<Socket>>>,
+}
+
+#[derive(Debug)]
+struct Socket {
+    udp: UdpSocket,
+    outstanding: Mutex<Outstanding>,
+}
+
+#[derive(Debug, Default)]
+struct Outstanding {
+    // identifier to try first on the next request, identifiers are rotated
+    // so late replies to released identifiers don't match new requests
+    next: u8,
+    requests: HashMap<u8, Waiting>,
+}
+
+// Outstanding request waiting for a reply from the `server`. All the
+// datagrams from the server with the identifier of the request are passed
+// to it, as a late reply to a previous request with the same identifier or
+// a spoofed datagram could come before the genuine reply.
+#[derive(Debug)]
+struct Waiting {
+    server: SocketAddr,
+    replies: mpsc::Sender<Vec<u8>>,
+}
+
+impl Outstanding {
+    fn allocate(&mut self, server: SocketAddr) -> Option<(u8, mpsc::Receiver<Vec<u8>>)> {
+        if self.requests.len() == IDENTIFIERS {
+            return None;
+        }
+
+        let mut identifier = self.next;
+        while self.requests.contains_key(&identifier) {
+            identifier = identifier.wrapping_add(1);
+        }
+        self.next = identifier.wrapping_add(1);
+
+        let (sender, receiver) = mpsc::channel(QUEUED_REPLIES);
+        let waiting = Waiting {
+            server,
+            replies: sender,
+        };
+        self.requests.insert(identifier, waiting);
+        Some((identifier, receiver))
+    }
+}
+
+impl Client {
+    pub fn new(runtime: Handle) -> Client {
+        Client {
+            runtime,
+            sockets: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Allocates a free identifier for a request to the given `server` on
+    // one of the pooled sockets, binding a new socket if all of them are
+    // busy. New sockets are registered within the runtime of the client and
+    // served by their own receiving tasks on it.
+    pub fn acquire(&self, server: SocketAddr) -> io::Result<PendingRequest> {
+        let mut sockets = self.sockets.lock().unwrap();
+
+        for socket in sockets.iter() {
+            if socket.udp.local_addr()?.is_ipv4() != server.is_ipv4() {
+                continue;
+            }
+            if let Some((identifier, replies)) = socket.outstanding.lock().unwrap().allocate(server)
+            {
+                return Ok(PendingRequest {
+                    socket: socket.clone(),
+                    server,
+                    identifier,
+                    replies,
+                });
+            }
+        }
+
+        let local: SocketAddr = if server.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let udp = StdUdpSocket::bind(local)?;
+        udp.set_nonblocking(true)?;
+        let udp = {
+            let _runtime = self.runtime.enter();
+            UdpSocket::from_std(udp)?
+        };
+        let socket = Arc::new(Socket {
+            udp,
+            outstanding: Mutex::new(Outstanding::default()),
+        });
+        self.runtime.spawn(receive(socket.clone()));
+        sockets.push(socket.clone());
+
+        let (identifier, replies) = socket.outstanding.lock().unwrap().allocate(server).unwrap();
+        Ok(PendingRequest {
+            socket,
+            server,
+            identifier,
+            replies,
+        })
+    }
+}
+
+// Receives replies on the given `socket` and passes them to the requests
+// waiting for them. Replies from other addresses than the request was sent
+// to and replies to unknown identifiers are dropped.
+async fn receive(socket: Arc<Socket>) {
+    let mut buf = [0u8; MAX_PACKET_LENGTH];
+    loop {
+        let (len, from) = match socket.udp.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(_) => {
+                STATS.receive_error();
+                continue;
+            }
+        };
+        if len < 2 {
+            continue;
+        }
+
+        let outstanding = socket.outstanding.lock().unwrap();
+        if let Some(waiting) = outstanding.requests.get(&buf[1]) {
+            if waiting.server == from {
+                let _ = waiting.replies.try_send(buf[..len].to_vec());
+            }
+        }
+    }
+}
+
+/// A request with the allocated socket and Identifier. The identifier is
+/// released when the request is dropped.
+#[derive(Debug)]
+pub struct PendingRequest {
+    socket: Arc<Socket>,
+    server: SocketAddr,
+    identifier: u8,
+    replies: mpsc::Receiver<Vec<u8>>,
+}
+
+impl PendingRequest {
+    pub fn identifier(&self) -> u8 {
+        self.identifier
+    }
+
+    // Sends the encoded request `buf` and waits for the reply accepted by
+    // `accept`, which verifies the received datagrams. Rejected datagrams
+    // don't end the wait, so the genuine reply is accepted if it comes later.
+    // The request is retransmitted unchanged up to `retries` times when no
+    // reply is accepted within `wait`.
+    pub async fn send<T, E, F>(
+        mut self,
+        buf: &[u8],
+        wait: Duration,
+        retries: u32,
+        mut accept: F,
+    ) -> io::Result<Reply<T, E>>
+    where
+        F: FnMut(&[u8]) -> Result<T, E>,
+    {
+        let mut rejected = None;
+
+        for attempt in 0..=retries {
+            if attempt > 0 {
+                STATS.retransmission();
+            }
+            self.socket.udp.send_to(buf, self.server).await?;
+
+            let deadline = Instant::now() + wait;
+            while let Ok(Some(reply)) = timeout_at(deadline, self.replies.recv()).await {
+                match accept(&reply) {
+                    Ok(reply) => return Ok(Reply::Received(reply)),
+                    Err(err) => rejected = Some(err),
+                }
+            }
+        }
+
+        match rejected {
+            Some(err) => Ok(Reply::Rejected(err)),
+            None => Ok(Reply::Timeout),
+        }
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        self.socket.outstanding.lock().unwrap().requests.remove(&self.identifier);
+    }
+}
+
+#[cfg(test)]
+mod client_tests {
+    use super::*;
+
+    const WAIT: Duration = Duration::from_millis(100);
+
+    // Accepts the replies with the code 2 only, so the other ones are
+    // rejected as unverified.
+    fn accept(buf: &[u8]) -> Result<Vec<u8>, &'static str> {
+        match buf[0] {
+            2 => Ok(buf.to_vec()),
+            _ => Err("rejected"),
+        }
+    }
+
+    // Binds the local server which sends the given `replies` to every
+    // received request, with the identifier of the request. Returns the
+    // address of the server and the channel received requests are passed to.
+    async fn bind_server(replies: Vec<Vec<u8>>) -> (SocketAddr, mpsc::UnboundedReceiver<Vec<u8>>) {
+        let udp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = udp.local_addr().unwrap();
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let mut buf = [0u8; MAX_PACKET_LENGTH];
+            loop {
+                let (len, from) = udp.recv_from(&mut buf).await.unwrap();
+                for reply in replies.iter() {
+                    let mut reply = reply.clone();
+                    reply[1] = buf[1];
+                    udp.send_to(&reply, from).await.unwrap();
+                }
+                let _ = sender.send(buf[..len].to_vec());
+            }
+        });
+
+        (addr, receiver)
+    }
+
+    #[test]
+    fn allocate_test() {
+        let server: SocketAddr = ([127, 0, 0, 1], 1812).into();
+        let mut outstanding = Outstanding::default();
+
+        for expected in 0..IDENTIFIERS {
+            let (identifier, _) = outstanding.allocate(server).unwrap();
+            assert_eq!(identifier as usize, expected);
+        }
+        assert!(outstanding.allocate(server).is_none());
+
+        // released identifiers are reused only after the others
+        outstanding.requests.remove(&10);
+        outstanding.requests.remove(&200);
+        assert_eq!(outstanding.allocate(server).unwrap().0, 10);
+        assert_eq!(outstanding.allocate(server).unwrap().0, 200);
+        assert!(outstanding.allocate(server).is_none());
+    }
+
+    #[tokio::test]
+    async fn acquire_test() {
+        let client = Client::new(Handle::current());
+        let server: SocketAddr = ([127, 0, 0, 1], 1812).into();
+
+        let mut requests = (0..IDENTIFIERS)
+            .map(|_| client.acquire(server).unwrap())
+            .collect::<Vec<PendingRequest>>();
+        assert_eq!(client.sockets.lock().unwrap().len(), 1);
+
+        // an extra socket is bound when all the identifiers are in use
+        let extra = client.acquire(server).unwrap();
+        assert_eq!(extra.identifier(), 0);
+        assert_eq!(client.sockets.lock().unwrap().len(), 2);
+
+        // the identifier is released when the request is dropped
+        let released = requests.remove(5);
+        assert_eq!(released.identifier(), 5);
+        drop(released);
+        assert_eq!(client.acquire(server).unwrap().identifier(), 5);
+        assert_eq!(client.sockets.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn send_test() {
+        let client = Client::new(Handle::current());
+        let (server, mut requests) = bind_server(vec![vec![2, 0, 1]]).await;
+
+        let request = client.acquire(server).unwrap();
+        let identifier = request.identifier();
+        match request.send(&[1, identifier, 0], WAIT, 2, accept).await.unwrap() {
+            Reply::Received(reply) => assert_eq!(reply, [2, identifier, 1]),
+            reply => panic!("unexpected {:?}", reply),
+        }
+        assert_eq!(requests.recv().await.unwrap(), [1, identifier, 0]);
+    }
+
+    #[tokio::test]
+    async fn send_timeout_test() {
+        let client = Client::new(Handle::current());
+        let (server, mut requests) = bind_server(Vec::new()).await;
+
+        let request = client.acquire(server).unwrap();
+        let identifier = request.identifier();
+        let reply = request.send(&[1, identifier, 0], WAIT, 2, accept).await.unwrap();
+        assert!(matches!(reply, Reply::Timeout));
+
+        // the request is sent once and retransmitted `retries` times
+        for _ in 0..3 {
+            assert_eq!(requests.recv().await.unwrap(), [1, identifier, 0]);
+        }
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn send_rejected_test() {
+        let client = Client::new(Handle::current());
+
+        // the genuine reply is accepted after the unverified one
+        let (server, _requests) = bind_server(vec![vec![3, 0, 1], vec![2, 0, 2]]).await;
+        let request = client.acquire(server).unwrap();
+        let identifier = request.identifier();
+        match request.send(&[1, identifier, 0], WAIT, 2, accept).await.unwrap() {
+            Reply::Received(reply) => assert_eq!(reply, [2, identifier, 2]),
+            reply => panic!("unexpected {:?}", reply),
+        }
+
+        // the request is retransmitted if only unverified replies are
+        // received and fails with the reason of the last of them
+        let (server, mut requests) = bind_server(vec![vec![3, 0, 1]]).await;
+        let request = client.acquire(server).unwrap();
+        let identifier = request.identifier();
+        let reply = request.send(&[1, identifier, 0], WAIT, 1, accept).await.unwrap();
+        assert!(matches!(reply, Reply::Rejected("rejected")));
+        for _ in 0..2 {
+            assert_eq!(requests.recv().await.unwrap(), [1, identifier, 0]);
+        }
+    }
+}
@@ -1,22 +1,45 @@
-use super::LUA_SCOPE;
-use mlua::Table;
+use mlua::{Lua, Table};
+use num_bigint::BigUint;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
 
 const WORKERS: u8 = 4;
+// Default time to wait for a RADIUS reply before the retransmission
+const RADIUS_TIMEOUT: Duration = Duration::from_secs(3);
+// Default number of retransmissions of a RADIUS request
+const RADIUS_RETRIES: u32 = 2;
 
 #[derive(Debug)]
 pub struct Config {
+    // source of the scenario every worker loads into its Lua context
+    script: String,
     workers: u8,
+    imsi_range: ImsiRange,
+    // number of aborted sessions the run is stopped after
+    max_errors: Option<u64>,
     radius: Option<RadiusConfig>,
 }
 
 #[derive(Debug)]
 pub enum ConfigError {
     LuaError(mlua::prelude::LuaError),
+    InvalidValue(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::LuaError(err) => write!(f, "{}", err),
+            ConfigError::InvalidValue(err) => write!(f, "{}", err),
+        }
+    }
 }
 
 impl Config {
-    pub fn new() -> Result<Config, ConfigError> {
-        let lua = LUA_SCOPE.lock().unwrap();
+    // Reads the configuration from the `workload` table of the given Lua
+    // context the scenario `script` is loaded into.
+    pub fn new(lua: &Lua, script: &str) -> Result<Config, ConfigError> {
         let globals = lua.globals();
 
         let workload = match globals.get::<_, Table>("workload") {
@@ -31,6 +54,15 @@ impl Config {
             _ => WORKERS,
         };
 
+        let imsi_range = workload
+            .get::<_, String>("imsi_range")
+            .map_err(ConfigError::LuaError)?;
+        let imsi_range = ImsiRange::parse(&imsi_range)?;
+
+        let max_errors = workload
+            .get::<_, Option<u64>>("max_errors")
+            .map_err(ConfigError::LuaError)?;
+
         let radius_conf: Result<RadiusConfig, ConfigError> = Self::maybe_radius_conf(&workload);
         match radius_conf {
             Err(e) => {
@@ -40,17 +72,183 @@ impl Config {
         }
 
         let config = Config {
+            script: script.to_string(),
             workers: workers,
+            imsi_range,
+            max_errors,
             radius: Some(radius_conf.unwrap()),
         };
 
         Ok(config)
     }
 
-    fn maybe_radius_conf(_workload: &Table) -> Result<RadiusConfig, ConfigError> {
-        Ok(RadiusConfig {})
+    fn maybe_radius_conf(workload: &Table) -> Result<RadiusConfig, ConfigError> {
+        let mut conf = RadiusConfig::default();
+
+        let radius = match workload.get::<_, Option<Table>>("radius") {
+            Ok(Some(r)) => r,
+            Ok(None) => return Ok(conf),
+            Err(e) => {
+                return Err(ConfigError::LuaError(e));
+            }
+        };
+
+        if let Some(m) = radius
+            .get::<_, Option<bool>>("message_authenticator")
+            .map_err(ConfigError::LuaError)?
+        {
+            conf.message_authenticator = m;
+        }
+
+        if let Some(r) = radius
+            .get::<_, Option<bool>>("require_message_authenticator")
+            .map_err(ConfigError::LuaError)?
+        {
+            conf.require_message_authenticator = r;
+        }
+
+        if let Some(t) = radius.get::<_, Option<f64>>("timeout").map_err(ConfigError::LuaError)? {
+            if !t.is_finite() || t <= 0.0 {
+                return Err(ConfigError::InvalidValue(format!(
+                    "radius.timeout should be a positive number of seconds, got {}",
+                    t
+                )));
+            }
+            conf.timeout = Duration::from_secs_f64(t);
+        }
+
+        if let Some(r) = radius.get::<_, Option<u32>>("retries").map_err(ConfigError::LuaError)? {
+            conf.retries = r;
+        }
+
+        Ok(conf)
+    }
+
+    pub fn script(&self) -> &str {
+        &self.script
+    }
+
+    pub fn workers(&self) -> u8 {
+        self.workers
+    }
+
+    pub fn imsi_range(&self) -> &ImsiRange {
+        &self.imsi_range
+    }
+
+    pub fn max_errors(&self) -> Option<u64> {
+        self.max_errors
+    }
+
+    pub fn radius(&self) -> Option<&RadiusConfig> {
+        self.radius.as_ref()
     }
 }
 
-#[derive(Debug)]
-struct RadiusConfig {}
+/// Range of IMSIs of the subscribers the scenario is run for, both the first
+/// and the last IMSI are included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImsiRange {
+    first: BigUint,
+    last: BigUint,
+    // number of digits of the first IMSI, IMSIs of the range are padded with
+    // leading zeros to it, like IMSIs of the test PLMN 001-01
+    width: usize,
+}
+
+impl ImsiRange {
+    // Parses the range of IMSIs given as `first-last`.
+    pub fn parse(range: &str) -> Result<ImsiRange, ConfigError> {
+        let invalid = || {
+            ConfigError::InvalidValue(format!(
+                "imsi_range should be given as 'first-last' IMSIs, got '{}'",
+                range
+            ))
+        };
+        let imsi = |imsi: &str| {
+            let imsi = imsi.trim();
+            if imsi.is_empty() || !imsi.bytes().all(|c| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            BigUint::from_str(imsi).map_err(|_| invalid())
+        };
+
+        let (first, last) = range.split_once('-').ok_or_else(invalid)?;
+        let width = first.trim().len();
+        let (first, last) = (imsi(first)?, imsi(last)?);
+        if first > last {
+            return Err(invalid());
+        }
+
+        Ok(ImsiRange { first, last, width })
+    }
+
+    // Returns all the IMSIs of the range in order.
+    pub fn imsis(&self) -> impl Iterator<Item = String> + '_ {
+        let mut next = Some(self.first.clone());
+        std::iter::from_fn(move || {
+            let imsi = next.take()?;
+            if imsi < self.last {
+                next = Some(&imsi + 1u32);
+            }
+            Some(format!("{:0>width$}", imsi.to_string(), width = self.width))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RadiusConfig {
+    // add Message-Authenticator to every Access-Request, Status-Server packets
+    // always have it
+    pub message_authenticator: bool,
+    // reject replies to Access-Request and Status-Server without valid
+    // Message-Authenticator
+    pub require_message_authenticator: bool,
+    // time to wait for a reply before the request is retransmitted
+    pub timeout: Duration,
+    // number of retransmissions before the request is considered lost
+    pub retries: u32,
+}
+
+impl Default for RadiusConfig {
+    fn default() -> RadiusConfig {
+        RadiusConfig {
+            message_authenticator: false,
+            require_message_authenticator: false,
+            timeout: RADIUS_TIMEOUT,
+            retries: RADIUS_RETRIES,
+        }
+    }
+}
+
+#[cfg(test)]
+mod conf_tests {
+    use super::*;
+
+    #[test]
+    fn imsi_range_test() {
+        let range = ImsiRange::parse("262019876543200-262019876543202").unwrap();
+        assert_eq!(
+            range.imsis().collect::<Vec<String>>(),
+            ["262019876543200", "262019876543201", "262019876543202"]
+        );
+
+        let range = ImsiRange::parse("262019876543200-262019876543200").unwrap();
+        assert_eq!(range.imsis().collect::<Vec<String>>(), ["262019876543200"]);
+
+        // IMSIs of the test PLMN start with zeros
+        let range = ImsiRange::parse(" 001010000000009 - 001010000000010 ").unwrap();
+        assert_eq!(
+            range.imsis().collect::<Vec<String>>(),
+            ["001010000000009", "001010000000010"]
+        );
+
+        for range in ["", "262019876543200", "2-1", "1-x", "-1", "+1-2", "1_0-20"] {
+            assert!(
+                matches!(ImsiRange::parse(range), Err(ConfigError::InvalidValue(_))),
+                "{}",
+                range
+            );
+        }
+    }
+}
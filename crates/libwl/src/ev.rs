@@ -1,11 +1,23 @@
-use super::conf::Config;
+use super::batch::Batch;
+use super::client::{Client, Reply};
+use super::conf::{Config, ImsiRange, RadiusConfig};
+use super::radius::{reply_to_lua, verify_response};
+use super::session::{run_sessions, Run};
+use super::stats::{Failure, STATS};
+use mlua::prelude::LuaError;
 use mlua::{Function, Lua, Table, Value};
-use num_bigint::BigInt;
-use std::str::FromStr;
-use tokio::time::{sleep, Duration};
-use std::collections::HashMap;
-use radius::attribute::{Attribute, Vendor};
-use std::sync::{Arc, Mutex};
+use radius::attribute::CHAP_PASSWORD_TYPE;
+use radius::dictionary::{AttributeDefinition, Dictionary};
+use radius::error::RadiusError;
+use radius::packet::{Code, Packet};
+use radius::tgpp::{Field, Fields};
+use radius::value::{AttributeValue, DataType};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread;
+use tokio::net::lookup_host;
+use tokio::runtime::Builder;
+use tokio::time::Instant;
 
 #[derive(Debug)]
 pub enum IOEngine {
@@ -15,20 +27,22 @@ pub enum IOEngine {
 
 #[derive(Debug)]
 pub struct Ev {
-    threads: u8,
+    threads: Option<u8>,
     engine: IOEngine,
+    dictionary: Arc<Dictionary>,
 }
 
 impl Ev {
     pub fn new() -> Ev {
         Ev {
-            threads: 4,
+            threads: None,
             engine: IOEngine::WIO,
+            dictionary: Arc::new(Dictionary::new()),
         }
     }
 
     pub fn set_threads(&mut self, threads: u8) -> &mut Self {
-        self.threads = threads;
+        self.threads = Some(threads);
         self
     }
 
@@ -37,78 +51,481 @@ impl Ev {
         self
     }
 
-    #[tokio::main]
-    pub async fn run(&mut self, _config: &Config) -> () {
-        let l = super::LUA_SCOPE.lock().unwrap();
-        let g = l.globals();
+    pub fn set_dictionary(&mut self, dictionary: Arc<Dictionary>) -> &mut Self {
+        self.dictionary = dictionary;
+        self
+    }
+
+    // Runs the scenario for every subscriber and prints the results. Returns
+    // the exit code of whirl, which is non-zero if the run was stopped.
+    //
+    // The scenario runs on the given number of threads or on the number of
+    // `workers` of the workload if the threads are not set. Every worker is
+    // a thread with its own event loop and Lua context running the scenario
+    // for its share of the subscribers, so Lua execution is spread across
+    // the cores. The RADIUS sockets shared by the workers are served by a
+    // separate I/O thread.
+    pub fn run(&mut self, config: &Config) -> i32 {
+        let workers = self.threads.unwrap_or_else(|| config.workers()).max(1) as usize;
+        let io = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("whirl-io")
+            .enable_all()
+            .build();
+        let io = match io {
+            Ok(io) => io,
+            Err(err) => {
+                eprintln!("Error: Can't start the event loop - {}", err);
+                return 1;
+            }
+        };
+        let client = Arc::new(Client::new(io.handle().clone()));
 
-        g.set("radius_send", l.create_async_function(radius_send).unwrap()).unwrap();
+        let shards = shard(config.imsi_range(), workers);
 
-        let workload = g.get::<_, Table>("workload").unwrap();
-        let imsi_range: String = workload.get::<_, String>("imsi_range").unwrap();
-        let imsis = imsi_range.split('-').collect::<Vec<&str>>();
-        let mut imsi_start = BigInt::from_str(imsis[0]).unwrap();
-        let imsi_end = BigInt::from_str(imsis[1]).unwrap();
+        // A Lua context can't be shared between threads, so every worker
+        // owns its context and drives its sessions on its own single-threaded
+        // event loop. The sessions run as Lua coroutines and yield to each
+        // other while they wait for replies.
+        let run = Arc::new(Run::new(config.max_errors()));
+        let radius_conf = config.radius().cloned().unwrap_or_default();
+        let mut handles = Vec::new();
+        for (worker, imsis) in shards.into_iter().filter(|imsis| !imsis.is_empty()).enumerate() {
+            let script = config.script().to_string();
+            let radius_conf = radius_conf.clone();
+            let dictionary = self.dictionary.clone();
+            let client = client.clone();
+            let run = run.clone();
 
-        loop {
-            if imsi_start == imsi_end {
-                break;
+            let handle = thread::Builder::new()
+                .name(format!("whirl-worker-{}", worker))
+                .spawn(move || {
+                    let runtime = Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                        .map_err(LuaError::external)?;
+                    let batch = Arc::new(Batch::new());
+                    let lua = super::new_state(&script)?;
+                    register(&lua, radius_conf, dictionary, client, batch.clone())?;
+                    runtime.block_on(run_sessions(&lua, imsis, &run, &batch))
+                });
+            match handle {
+                Ok(handle) => handles.push(handle),
+                Err(err) => {
+                    eprintln!("Error: Can't start worker {} - {}", worker, err);
+                    return 1;
+                }
             }
-            imsi_start += 1;
-
-            let imsi = imsi_start.to_string();
+        }
 
-            tokio::spawn(async move {
-                let l = super::LUA_SCOPE.lock().unwrap();
-                let g = l.globals();
+        let mut code = 0;
+        for handle in handles {
+            let result = match handle.join() {
+                Ok(result) => result,
+                Err(_) => Err(LuaError::RuntimeError("worker panicked".to_string())),
+            };
+            if let Err(err) = result {
+                eprintln!("Error: Can't run the scenario - {}", err);
+                code = 1;
+            }
+        }
 
-                let state = l.create_table().unwrap();
-                let _ = state.set("3GPP-IMSI", imsi);
+        print!("{}", *STATS);
 
-                let run_cb = g.get::<_, Function>("run").unwrap();
-                run_cb.call::<_, ()>(state).unwrap();
-            });
+        match run.stopped() {
+            Some(reason) => {
+                eprintln!("Error: run stopped - {}", reason);
+                1
+            }
+            None => code,
         }
+    }
+}
 
-        // TODO: remove this as now it is only for testing the event loop
-        sleep(Duration::from_millis(100)).await;
+// Shards the subscribers of the given IMSI `range` across the `workers`
+// round-robin.
+fn shard(range: &ImsiRange, workers: usize) -> Vec<Vec<String>> {
+    let mut shards: Vec<Vec<String>> = vec![Vec::new(); workers];
+    for (subscriber, imsi) in range.imsis().enumerate() {
+        shards[subscriber % workers].push(imsi);
     }
+    shards
+}
+
+// Registers the functions of the scenario API implemented by the event loop
+// within the given Lua context.
+fn register(
+    lua: &Lua,
+    radius_conf: RadiusConfig,
+    dictionary: Arc<Dictionary>,
+    client: Arc<Client>,
+    batch: Arc<Batch>,
+) -> mlua::Result<()> {
+    let radius_send_fn = lua.create_async_function(move |lua, data| {
+        radius_send(
+            lua,
+            data,
+            radius_conf.clone(),
+            dictionary.clone(),
+            client.clone(),
+            batch.clone(),
+        )
+    })?;
+    lua.globals().set("radius_send", radius_send_fn)
 }
 
 // TODO: this function should be moved out of here, probably to libwl:: lua ns
-async fn radius_send<'a>(_lua: &Lua, data: (String, mlua::Table<'a>, String)) -> mlua::Result<()> {
+//
+// Sends the RADIUS request built from the given Lua table to the `server`
+// and returns the result as a table with the `code` of the reply, the
+// `response` attributes by their names and the `latency` in seconds. The
+// result is passed to the callbacks of the packet before it is returned:
+//
+//  * `on_reject` - called on Access-Reject
+//  * `on_response` - called on all other replies
+//  * `on_timeout` - called if no reply is received after all the
+//    retransmissions, the result has no `code` and `response` then
+//
+// The code of the request could be given by the `code` key of the packet as
+// 'Access-Request', 'Accounting-Request' or 'Status-Server'. Otherwise
+// packets with Acct-Status-Type are sent as Accounting-Request and all the
+// other packets as Access-Request.
+//
+// Replies that can't be verified are ignored while the request waits for the
+// genuine reply. If only such replies are received, the request is accounted
+// as a failure of their class and no callbacks are called.
+async fn radius_send<'a>(
+    lua: &'a Lua,
+    data: (String, mlua::Table<'a>, String),
+    conf: RadiusConfig,
+    dictionary: Arc<Dictionary>,
+    client: Arc<Client>,
+    batch: Arc<Batch>,
+) -> mlua::Result<Table<'a>> {
     let (server, packet, secret) = data;
 
+    // Accounting-Request packets are distinguished by the Acct-Status-Type
+    // attribute that must be present in every of them (RFC 2866 5.1).
+    let code = match packet.get::<_, Option<String>>("code")?.as_deref() {
+        Some("Access-Request") => Code::AccessRequest,
+        Some("Accounting-Request") => Code::AccountingRequest,
+        Some("Status-Server") => Code::StatusServer,
+        Some(code) => {
+            return Err(LuaError::RuntimeError(format!(
+                "Unsupported RADIUS packet code '{}'",
+                code
+            )));
+        }
+        None if packet.contains_key("Acct-Status-Type")? => Code::AccountingRequest,
+        None => Code::AccessRequest,
+    };
+    let mut request = Packet::new(code, 0);
+
+    // Message-Authenticator protects Access-Request and Status-Server packets
+    // only, Accounting-Request is already signed by its authenticator. It is
+    // added to every Status-Server packet as RFC 5997 3 requires it.
+    if (conf.message_authenticator && code == Code::AccessRequest) || code == Code::StatusServer {
+        request.add_message_authenticator();
+    }
+
+    // cleartext password the CHAP-Password is calculated from
+    let mut chap_password: Option<Vec<u8>> = None;
+    let mut callbacks = Callbacks::default();
+
     for pair in packet.pairs::<Value, Value>() {
-        let (attr_name, attr_value) = pair.unwrap();
+        let (attr_name, attr_value) = pair?;
         let attr = match attr_name {
-            Value::String(s) => {
-                s.to_str().unwrap().to_string()
-            },
+            Value::String(s) => s.to_str()?.to_string(),
             _ => {
-                // TODO: something should be better than panic here
-                panic!("Wrong attribute");
+                return Err(LuaError::RuntimeError(format!(
+                    "RADIUS attribute name should be a string, got {}",
+                    attr_name.type_name()
+                )));
             }
         };
 
-        let val = match attr_value {
-            Value::String(s) => {
-                s.to_str().unwrap().to_string()
-            },
-            Value::Integer(i) => {
-                i.to_string()
-            },
-            Value::Function(f) => {
-                // TODO: this is on_response cb, so we should fetch and call it properly
-                "".to_string()
+        if attr == "code" {
+            continue;
+        }
+
+        // functions are callbacks called with the result of the request
+        if let Value::Function(f) = attr_value {
+            match attr.as_str() {
+                "on_response" => callbacks.on_response = Some(f),
+                "on_reject" => callbacks.on_reject = Some(f),
+                "on_timeout" => callbacks.on_timeout = Some(f),
+                _ => {
+                    return Err(LuaError::RuntimeError(format!(
+                        "Unknown RADIUS packet callback '{}'",
+                        attr
+                    )));
+                }
             }
-            _ => {
-                // TODO: something should be better than panic here
-                panic!("Wrong attribute");
+            continue;
+        }
+
+        let definition = match dictionary.attribute(&attr) {
+            Some(definition) => definition,
+            None => {
+                return Err(LuaError::RuntimeError(format!(
+                    "Unknown RADIUS attribute '{}'",
+                    attr
+                )));
             }
         };
 
-        // println!("{:?}", radius::RADIUS_DICTIONARIES.lock().unwrap()[&attr]);
+        // CHAP-Password could be given as `{chap='cleartext'}` and is
+        // calculated when all the attributes are added, since the challenge
+        // could be given by the CHAP-Challenge attribute
+        if let Value::Table(t) = &attr_value {
+            if let Some(password) = t.get::<_, Option<mlua::String>>("chap")? {
+                let code = (definition.extended(), definition.vendor(), definition.id());
+                if code != (None, None, CHAP_PASSWORD_TYPE as u32) {
+                    return Err(LuaError::RuntimeError(format!(
+                        "RADIUS attribute '{}' is not CHAP-Password",
+                        attr
+                    )));
+                }
+                chap_password = Some(password.as_bytes().to_vec());
+                continue;
+            }
+        }
+
+        // tagged attributes like Tunnel-Type could be given with their tag
+        let (tag, attr_value) = lua_to_tagged_value(definition, attr_value).map_err(|err| {
+            LuaError::RuntimeError(format!(
+                "Invalid tag of RADIUS attribute '{}' - {}",
+                attr, err
+            ))
+        })?;
+
+        let value = lua_to_attribute_value(definition, attr_value).map_err(|err| {
+            LuaError::RuntimeError(format!(
+                "Invalid value of RADIUS attribute '{}' defined in {} - {:?}",
+                attr,
+                definition
+                    .source()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_else(|| "unknown dictionary".to_string()),
+                err
+            ))
+        })?;
+
+        // attributes like User-Password are hidden with the shared secret
+        // and the Request Authenticator of the packet
+        let attribute = definition
+            .encrypt(&value, secret.as_bytes(), request.authenticator())
+            .and_then(|mut attribute| {
+                if tag.is_some() {
+                    attribute.set_tag(tag)?;
+                }
+                Ok(attribute)
+            })
+            .map_err(|err| {
+                LuaError::RuntimeError(format!(
+                    "Can't encode RADIUS attribute '{}' - {:?}",
+                    attr, err
+                ))
+            })?;
+        request.add_attribute(attribute);
+    }
+
+    if let Some(password) = chap_password {
+        request.add_chap_password(&password);
+    }
+
+    let server = resolve(&server).await?;
+    let pending = client.acquire(server).map_err(|err| {
+        LuaError::RuntimeError(format!("Can't allocate RADIUS socket - {}", err))
+    })?;
+    request.set_identifier(pending.identifier());
+
+    // Accounting-Request authenticator is calculated over the whole packet,
+    // so it is known only after all the attributes are added. Such requests
+    // are encoded together with the others sent within the same turn of the
+    // worker, the authenticators of all of them are hashed at once.
+    let encoded = if request.code().has_hashed_authenticator() {
+        batch.encode(request, secret.as_bytes()).await
+    } else {
+        request.encode_request(secret.as_bytes()).map(|buf| (request, buf))
+    };
+    let (request, buf) = encoded.map_err(|err| {
+        STATS.failure(Failure::InvalidRequest);
+        LuaError::RuntimeError(format!("Can't encode RADIUS packet - {:?}", err))
+    })?;
+
+    STATS.request();
+    let started = Instant::now();
+    let verify = |buf: &[u8]| {
+        verify_response(
+            &request,
+            buf,
+            secret.as_bytes(),
+            conf.require_message_authenticator,
+            dictionary.vendor_formats(),
+        )
+    };
+    let reply = pending.send(&buf, conf.timeout, conf.retries, verify).await.map_err(|err| {
+        LuaError::RuntimeError(format!("Can't send RADIUS packet to {} - {}", server, err))
+    })?;
+
+    let result = lua.create_table()?;
+    result.set("latency", started.elapsed().as_secs_f64())?;
+
+    let callback = match reply {
+        Reply::Timeout => {
+            STATS.failure(Failure::Timeout);
+            callbacks.on_timeout
+        }
+        Reply::Rejected(failure) => {
+            STATS.response();
+            STATS.failure(failure);
+            None
+        }
+        Reply::Received(reply) => {
+            STATS.response();
+            result.set("code", reply.code().to_string())?;
+            result.set("response", reply_to_lua(lua, &reply, &dictionary)?)?;
+            match reply.code() {
+                Code::AccessReject => callbacks.on_reject,
+                _ => callbacks.on_response,
+            }
+        }
+    };
+
+    if let Some(callback) = callback {
+        callback.call_async::<_, ()>(result.clone()).await?;
+    }
+
+    Ok(result)
+}
+
+// Callbacks of a scenario packet.
+#[derive(Default)]
+struct Callbacks<'lua> {
+    on_response: Option<Function<'lua>>,
+    on_reject: Option<Function<'lua>>,
+    on_timeout: Option<Function<'lua>>,
+}
+
+// Resolves the given `server` given as `host:port` to its first address.
+async fn resolve(server: &str) -> mlua::Result<SocketAddr> {
+    lookup_host(server)
+        .await
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| {
+            LuaError::RuntimeError(format!("Can't resolve RADIUS server '{}'", server))
+        })
+}
+
+// Converts the given Lua table to the fields of a structured value, like
+// `{offset='+02:00', dst=1}` for 3GPP-MS-TimeZone.
+fn lua_to_fields(table: &Table) -> mlua::Result<Fields> {
+    table
+        .clone()
+        .pairs::<String, Value>()
+        .map(|pair| {
+            let (name, value) = pair?;
+            let field = match value {
+                Value::Integer(i) if i >= 0 => Field::Integer(i as u64),
+                Value::Number(n) if n >= 0.0 && n.fract() == 0.0 => Field::Integer(n as u64),
+                Value::String(s) => Field::String(s.to_str()?.to_string()),
+                v => {
+                    return Err(LuaError::RuntimeError(format!(
+                        "invalid field '{}' of type {}",
+                        name,
+                        v.type_name()
+                    )));
+                }
+            };
+            Ok((name, field))
+        })
+        .collect()
+}
+
+// Splits the given Lua value of an attribute with the `has_tag` flag given as
+// `{tag=1, value='L2TP'}` into the tag and the value itself. Values of other
+// attributes and values without tags are returned as is.
+fn lua_to_tagged_value<'lua>(
+    definition: &AttributeDefinition,
+    value: Value<'lua>,
+) -> mlua::Result<(Option<u8>, Value<'lua>)> {
+    match value {
+        Value::Table(t) if definition.flags().has_tag && t.contains_key("value")? => {
+            Ok((t.get::<_, Option<u8>>("tag")?, t.get::<_, Value>("value")?))
+        }
+        value => Ok((None, value)),
+    }
+}
+
+// Converts the given Lua value to the value of a RADIUS attribute described
+// by the given `definition`.
+fn lua_to_attribute_value(
+    definition: &AttributeDefinition,
+    value: Value,
+) -> Result<AttributeValue, RadiusError> {
+    match value {
+        Value::String(s) => definition.parse_value(&s.to_string_lossy()),
+        Value::Integer(i) => definition.integer_value(i as i128),
+        Value::Number(n) if n.fract() == 0.0 => definition.integer_value(n as i128),
+        Value::Table(t) => match definition.base_type() {
+            DataType::Array(element) => t
+                .sequence_values::<Value>()
+                .map(|v| match v {
+                    Ok(Value::String(s)) => AttributeValue::parse(&element, &s.to_string_lossy()),
+                    Ok(Value::Integer(i)) => AttributeValue::from_integer(&element, i as i128),
+                    Ok(v) => Err(RadiusError::InvalidAttributeValue(
+                        *element.clone(),
+                        v.type_name().to_string(),
+                    )),
+                    Err(err) => Err(RadiusError::InvalidAttributeValue(
+                        *element.clone(),
+                        err.to_string(),
+                    )),
+                })
+                .collect::<Result<Vec<AttributeValue>, RadiusError>>()
+                .map(AttributeValue::Array),
+            data_type @ (DataType::UserLocationInfo
+            | DataType::MsTimeZone
+            | DataType::PacketFilter) => {
+                let fields = lua_to_fields(&t).map_err(|err| {
+                    RadiusError::InvalidAttributeValue(data_type.clone(), err.to_string())
+                })?;
+                AttributeValue::from_fields(&data_type, &fields)
+            }
+            data_type => Err(RadiusError::InvalidAttributeValue(
+                data_type,
+                "table".to_string(),
+            )),
+        },
+        v => Err(RadiusError::InvalidAttributeValue(
+            definition.data_type().clone(),
+            v.type_name().to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod ev_tests {
+    use super::*;
+
+    #[test]
+    fn shard_test() {
+        let range = ImsiRange::parse("001010000000001-001010000000005").unwrap();
+        assert_eq!(
+            shard(&range, 2),
+            [
+                vec!["001010000000001", "001010000000003", "001010000000005"],
+                vec!["001010000000002", "001010000000004"],
+            ]
+        );
+
+        // workers beyond the number of subscribers get no subscribers
+        let range = ImsiRange::parse("262019876543200-262019876543200").unwrap();
+        assert_eq!(
+            shard(&range, 3),
+            [vec!["262019876543200"], vec![], vec![]] as [Vec<&str>; 3]
+        );
     }
-    Ok(())
 }
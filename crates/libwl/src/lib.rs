@@ -1,23 +1,34 @@
+pub mod batch;
+pub mod client;
 pub mod conf;
 pub mod ev;
 pub mod radius;
+pub mod session;
+pub mod stats;
 
 use conf::{Config, ConfigError};
-use lazy_static::lazy_static;
 use mlua::prelude::*;
 use std::env;
 use std::path::PathBuf;
-use std::sync::Mutex;
-
-// Global LUA context.
-lazy_static! {
-    pub static ref LUA_SCOPE: Mutex<mlua::Lua> = {
-        let lua = unsafe { Lua::unsafe_new() };
-        Mutex::new(lua)
-    };
-}
 
+// Loads the scenario `script` and reads its configuration. The Lua context
+// the configuration is read from is dropped then, as every worker runs the
+// scenario within its own context (see `new_state`).
 pub fn load(script: &str) -> Result<Config, ConfigError> {
+    let lua = new_state(script).map_err(ConfigError::LuaError)?;
+
+    // Try to load configuration from the scenario file
+    conf::Config::new(&lua, script)
+}
+
+// Creates a new Lua context with the scenario `script` loaded.
+pub fn new_state(script: &str) -> LuaResult<Lua> {
+    let lua = unsafe { Lua::unsafe_new() };
+    init_state(&lua, script)?;
+    Ok(lua)
+}
+
+fn init_state(lua: &Lua, script: &str) -> LuaResult<()> {
     let (dylib_path, dylib_ext, separator);
     dylib_path = env::var("LD_LIBRARY_PATH").unwrap();
     dylib_ext = "so";
@@ -35,26 +46,29 @@ pub fn load(script: &str) -> Result<Config, ConfigError> {
         .join(";");
 
     // load whirl modules
-    let _ = LUA_SCOPE
-        .lock()
-        .unwrap()
-        .load(&format!("package.cpath = \"{}\"", cpath))
-        .exec()
-        .unwrap();
+    lua.load(&format!("package.cpath = \"{}\"", cpath)).exec()?;
 
-    // load the scenario script
-    let _ = LUA_SCOPE.lock().unwrap().load(script).exec().unwrap();
+    // `require('libwl')` should return the API of this process rather than
+    // load another copy of libwl with its own state
+    let package = lua.globals().get::<_, LuaTable>("package")?;
+    let loaded = package.get::<_, LuaTable>("loaded")?;
+    loaded.set("libwl", exports(lua)?)?;
 
-    // Try to load configuration from the scenario file
-    let config = conf::Config::new();
-
-    config
+    // load the scenario script
+    lua.load(script).exec()
 }
 
-#[mlua::lua_module]
-fn libwl(lua: &Lua) -> LuaResult<LuaTable> {
+// Builds the table of the `libwl` Lua API.
+pub fn exports(lua: &Lua) -> LuaResult<LuaTable<'_>> {
     let exports = lua.create_table()?;
     // exports.set("imsi_from_range",
     //             lua.create_function(radius::imsi_from_range)?)?;
+    exports.set("stop_with_error", lua.create_function(session::stop_with_error)?)?;
+    exports.set("sleep", lua.create_async_function(session::sleep)?)?;
     Ok(exports)
 }
+
+#[mlua::lua_module]
+fn libwl(lua: &Lua) -> LuaResult<LuaTable> {
+    exports(lua)
+}
@@ -1,5 +1,187 @@
+use super::stats::Failure;
 use mlua::prelude::*;
+use radius::attribute::VendorFormats;
+use radius::dictionary::Dictionary;
+use radius::error::RadiusError;
+use radius::packet::Packet;
+use radius::tgpp::Field;
+use radius::value::AttributeValue;
 
 pub fn imsi_from_range(_: &Lua, range: String) -> LuaResult<String> {
     Ok(range)
 }
+
+// Decodes the reply to the given `request` and verifies its Response
+// Authenticator and Message-Authenticator. With `require_message_authenticator`
+// replies to Access-Request and Status-Server without Message-Authenticator
+// are rejected as well, Accounting-Response is protected by the Response
+// Authenticator and has no Message-Authenticator. Vendor-Specific
+// attributes are decoded according to the vendor `formats` of the loaded
+// dictionaries. Replies that can't be decoded or verified are rejected with
+// the class of the failure they are accounted as if no valid reply comes.
+pub fn verify_response(
+    request: &Packet,
+    buf: &[u8],
+    secret: &[u8],
+    require_message_authenticator: bool,
+    formats: &VendorFormats,
+) -> Result<Packet, Failure> {
+    match Packet::decode_response(
+        buf,
+        request.authenticator(),
+        secret,
+        require_message_authenticator && request.code().has_random_authenticator(),
+        formats,
+    ) {
+        Ok(reply) => Ok(reply),
+        Err(RadiusError::InvalidResponseAuthenticator(_)) => {
+            Err(Failure::InvalidResponseAuthenticator)
+        }
+        Err(RadiusError::InvalidMessageAuthenticator(_)) => {
+            Err(Failure::InvalidMessageAuthenticator)
+        }
+        Err(RadiusError::MissingMessageAuthenticator(_)) => {
+            Err(Failure::MissingMessageAuthenticator)
+        }
+        Err(_) => Err(Failure::MalformedResponse),
+    }
+}
+
+// Converts the decoded value of a RADIUS attribute to a Lua value. Structured
+// 3GPP values like 3GPP-User-Location-Info are converted to tables of their
+// fields, arrays to sequences, addresses and prefixes to strings and dates to
+// RFC 3339 strings.
+pub fn attribute_value_to_lua<'lua>(
+    lua: &'lua Lua,
+    value: &AttributeValue,
+) -> LuaResult<LuaValue<'lua>> {
+    if let Some(fields) = value.fields() {
+        let table = lua.create_table()?;
+        for (name, field) in fields {
+            match field {
+                Field::Integer(n) => table.set(name, n as i64)?,
+                Field::String(s) => table.set(name, s)?,
+            }
+        }
+        return Ok(LuaValue::Table(table));
+    }
+
+    let value = match value {
+        AttributeValue::Integer(n) => LuaValue::Integer(*n as i64),
+        AttributeValue::Integer64(n) => LuaValue::Integer(*n as i64),
+        AttributeValue::Byte(n) => LuaValue::Integer(*n as i64),
+        AttributeValue::Short(n) => LuaValue::Integer(*n as i64),
+        AttributeValue::Signed(n) => LuaValue::Integer(*n as i64),
+        AttributeValue::TimeDelta(n) => LuaValue::Integer(*n as i64),
+        AttributeValue::String(s) => LuaValue::String(lua.create_string(s)?),
+        AttributeValue::Octets(data) => LuaValue::String(lua.create_string(data)?),
+        AttributeValue::Array(values) => {
+            let table = lua.create_table()?;
+            for value in values {
+                table.raw_set(table.raw_len() + 1, attribute_value_to_lua(lua, value)?)?;
+            }
+            LuaValue::Table(table)
+        }
+        value => LuaValue::String(lua.create_string(&value.to_string())?),
+    };
+
+    Ok(value)
+}
+
+// Converts the attributes of the given `reply` to a Lua table keyed by the
+// names of the attributes. Attributes that occur several times are
+// collected into sequences, attributes unknown to the `dictionary` or with
+// values that can't be decoded are skipped. Values of tagged attributes are
+// converted to tables like `{tag=1, value='L2TP'}`, the same way they are
+// given in requests.
+pub fn reply_to_lua<'lua>(
+    lua: &'lua Lua,
+    reply: &Packet,
+    dictionary: &Dictionary,
+) -> LuaResult<LuaTable<'lua>> {
+    let table = lua.create_table()?;
+    let mut repeated: Vec<String> = Vec::new();
+
+    // long values of concat attributes are split into several attributes
+    for attr in &dictionary.join_attributes(reply.attributes()) {
+        let definition = match dictionary.definition_of(attr) {
+            Some(definition) => definition,
+            None => continue,
+        };
+        let value = match definition.decode(attr) {
+            Ok(value) => attribute_value_to_lua(lua, &value)?,
+            Err(_) => continue,
+        };
+        let value = match definition.tag(attr) {
+            Some(tag) => {
+                let tagged = lua.create_table()?;
+                tagged.set("tag", tag)?;
+                tagged.set("value", value)?;
+                LuaValue::Table(tagged)
+            }
+            None => value,
+        };
+
+        let name = definition.name();
+        match table.get::<_, LuaValue>(name)? {
+            LuaValue::Nil => table.set(name, value)?,
+            previous => {
+                if !repeated.iter().any(|r| r == name) {
+                    let values = lua.create_table()?;
+                    values.raw_set(1, previous)?;
+                    table.set(name, values)?;
+                    repeated.push(name.to_string());
+                }
+                let values = table.get::<_, LuaTable>(name)?;
+                values.raw_set(values.raw_len() + 1, value)?;
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+#[cfg(test)]
+mod radius_tests {
+    use super::*;
+    use radius::packet::Code;
+
+    // Encodes the given `request` and the reply to it with the given `code`,
+    // Message-Authenticator is added to the reply if `signed`.
+    fn encode_reply(request: &mut Packet, code: Code, signed: bool) -> Vec<u8> {
+        request.encode_request(b"secret").unwrap();
+        let mut reply = Packet::new(code, request.identifier());
+        if signed {
+            reply.add_message_authenticator();
+        }
+        reply.encode_response(request.authenticator(), b"secret").unwrap()
+    }
+
+    #[test]
+    fn verify_response_test() {
+        let formats = VendorFormats::new();
+
+        // Accounting-Response has no Message-Authenticator even if it is
+        // required for the replies to Access-Request
+        let mut request = Packet::new(Code::AccountingRequest, 1);
+        let buf = encode_reply(&mut request, Code::AccountingResponse, false);
+        let reply = verify_response(&request, &buf, b"secret", true, &formats).unwrap();
+        assert_eq!(reply.code(), Code::AccountingResponse);
+
+        let mut request = Packet::new(Code::AccessRequest, 2);
+        let buf = encode_reply(&mut request, Code::AccessAccept, false);
+        assert_eq!(
+            verify_response(&request, &buf, b"secret", true, &formats).unwrap_err(),
+            Failure::MissingMessageAuthenticator
+        );
+        assert!(verify_response(&request, &buf, b"secret", false, &formats).is_ok());
+
+        let mut request = Packet::new(Code::StatusServer, 3);
+        let buf = encode_reply(&mut request, Code::AccessAccept, true);
+        assert!(verify_response(&request, &buf, b"secret", true, &formats).is_ok());
+        assert_eq!(
+            verify_response(&request, &buf, b"other", true, &formats).unwrap_err(),
+            Failure::InvalidResponseAuthenticator
+        );
+    }
+}
@@ -0,0 +1,397 @@
+use super::batch::Batch;
+use super::stats::STATS;
+use mlua::prelude::*;
+use std::error::Error;
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use tokio::time::{self, Duration};
+
+// Reason of sessions stopped by `libwl.stop_with_error()` without arguments.
+const DEFAULT_REASON: &str = "stopped by scenario";
+// Reason of sessions aborted by errors of the scenario script.
+const SCRIPT_ERROR: &str = "script error";
+
+/// What is stopped by `libwl.stop_with_error()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Only the current subscriber session is aborted.
+    Session,
+    /// The whole run is stopped with a non-zero exit code.
+    Run,
+}
+
+/// Error raised by `libwl.stop_with_error()` to unwind the Lua stack of the
+/// current session.
+#[derive(Debug, Clone)]
+pub struct Stop {
+    pub reason: String,
+    pub scope: Scope,
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl Error for Stop {}
+
+impl Stop {
+    // Returns the stop raised by `libwl.stop_with_error()` if the given Lua
+    // error is caused by it. The stop could be wrapped into several callback
+    // errors as it is raised from callbacks called by Rust functions.
+    pub fn of(err: &LuaError) -> Option<&Stop> {
+        match err {
+            LuaError::CallbackError { cause, .. } => Stop::of(cause),
+            LuaError::ExternalError(err) => err.downcast_ref::<Stop>(),
+            _ => None,
+        }
+    }
+}
+
+// `libwl.stop_with_error([reason [, scope]])` aborts the current subscriber
+// session and records it with the given `reason` in the run results. With the
+// 'run' `scope` the whole run is stopped, 'session' is the default scope.
+pub fn stop_with_error(
+    _: &Lua,
+    (reason, scope): (Option<String>, Option<String>),
+) -> LuaResult<()> {
+    let scope = match scope.as_deref() {
+        None | Some("session") => Scope::Session,
+        Some("run") => Scope::Run,
+        Some(scope) => {
+            return Err(LuaError::RuntimeError(format!(
+                "stop_with_error scope should be 'session' or 'run', got '{}'",
+                scope
+            )));
+        }
+    };
+
+    Err(LuaError::external(Stop {
+        reason: reason.unwrap_or_else(|| DEFAULT_REASON.to_string()),
+        scope,
+    }))
+}
+
+// `libwl.sleep(seconds)` suspends the current subscriber session for the
+// given number of seconds, other sessions are running meanwhile.
+pub async fn sleep(_: &Lua, seconds: f64) -> LuaResult<()> {
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(LuaError::RuntimeError(format!(
+            "sleep time should be a non-negative number of seconds, got {}",
+            seconds
+        )));
+    }
+
+    time::sleep(Duration::from_secs_f64(seconds)).await;
+    Ok(())
+}
+
+/// State of a run shared by all its subscriber sessions.
+#[derive(Debug, Default)]
+pub struct Run {
+    // number of aborted sessions the run is stopped after
+    max_errors: Option<u64>,
+    // reason the run is stopped for, by `libwl.stop_with_error()` with the
+    // 'run' scope or after `max_errors` aborted sessions
+    stopped: Mutex<Option<String>>,
+    // wakers of the tasks of the workers running sessions, woken when the
+    // run is stopped so every worker stops without waiting for its sessions
+    workers: Mutex<Vec<Option<Waker>>>,
+}
+
+impl Run {
+    pub fn new(max_errors: Option<u64>) -> Run {
+        Run {
+            max_errors,
+            stopped: Mutex::new(None),
+            workers: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn stopped(&self) -> Option<String> {
+        self.stopped.lock().unwrap().clone()
+    }
+
+    // Adds a worker running sessions of the run, returns its index the
+    // waker of the worker is updated by.
+    fn join(&self) -> usize {
+        let mut workers = self.workers.lock().unwrap();
+        workers.push(None);
+        workers.len() - 1
+    }
+
+    fn set_waker(&self, worker: usize, waker: &Waker) {
+        let mut workers = self.workers.lock().unwrap();
+        if !workers[worker].as_ref().is_some_and(|w| w.will_wake(waker)) {
+            workers[worker] = Some(waker.clone());
+        }
+    }
+
+    fn stop(&self, reason: String) {
+        let mut stopped = self.stopped.lock().unwrap();
+        if stopped.is_some() {
+            return;
+        }
+        *stopped = Some(reason);
+        drop(stopped);
+
+        for waker in self.workers.lock().unwrap().iter().flatten() {
+            waker.wake_by_ref();
+        }
+    }
+
+    // Accounts the result of the session of the subscriber `imsi`. Sessions
+    // failed with Lua errors are aborted the same way as sessions stopped by
+    // `libwl.stop_with_error()`.
+    fn finish(&self, imsi: &str, result: LuaResult<()>) {
+        let err = match result {
+            Ok(()) => return,
+            Err(err) => err,
+        };
+
+        let (reason, scope) = match Stop::of(&err) {
+            Some(stop) => (stop.reason.clone(), stop.scope),
+            None => {
+                eprintln!("Error: session of {} failed - {}", imsi, err);
+                (SCRIPT_ERROR.to_string(), Scope::Session)
+            }
+        };
+        let aborted = STATS.abort(&reason);
+
+        if scope == Scope::Run {
+            self.stop(reason);
+        } else if self.max_errors.is_some_and(|max| aborted >= max) {
+            self.stop(format!("{} session(s) aborted", aborted));
+        }
+    }
+}
+
+// Session of a subscriber, the call of the scenario `run` function running
+// as a Lua coroutine.
+struct Session<'lua> {
+    imsi: String,
+    future: Pin<Box<dyn Future<Output = LuaResult<()>> + 'lua>>,
+    waker: Waker,
+}
+
+// Indexes of the sessions woken since they were polled last time.
+#[derive(Default)]
+struct Ready {
+    sessions: Mutex<Vec<usize>>,
+    // waker of the task all the sessions are driven by
+    waker: Mutex<Option<Waker>>,
+}
+
+struct SessionWaker {
+    session: usize,
+    ready: Arc<Ready>,
+}
+
+impl Wake for SessionWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready.sessions.lock().unwrap().push(self.session);
+        if let Some(waker) = self.ready.waker.lock().unwrap().as_ref() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+// Runs the scenario `run` function for every subscriber of `imsis` within
+// the given Lua context. Every session is a Lua coroutine, so a session that
+// waits for a RADIUS reply or sleeps yields to the others and all the
+// sessions are in flight concurrently. Only the woken sessions are polled.
+// The requests queued into the `batch` by the sessions are encoded together
+// after every turn. Sessions that are not finished yet are dropped when the
+// run is stopped, even if it is stopped by another worker.
+pub async fn run_sessions(
+    lua: &Lua,
+    imsis: Vec<String>,
+    run: &Run,
+    batch: &Batch,
+) -> LuaResult<()> {
+    let run_cb = lua.globals().get::<_, LuaFunction>("run")?;
+    let ready = Arc::new(Ready::default());
+    let worker = run.join();
+
+    let mut sessions = Vec::with_capacity(imsis.len());
+    for (session, imsi) in imsis.into_iter().enumerate() {
+        let state = lua.create_table()?;
+        state.set("3GPP-IMSI", imsi.as_str())?;
+
+        let waker = Waker::from(Arc::new(SessionWaker {
+            session,
+            ready: ready.clone(),
+        }));
+        waker.wake_by_ref();
+        sessions.push(Some(Session {
+            imsi,
+            future: run_cb.call_async::<_, ()>(state),
+            waker,
+        }));
+    }
+    let mut running = sessions.len();
+
+    poll_fn(|cx| {
+        *ready.waker.lock().unwrap() = Some(cx.waker().clone());
+        run.set_waker(worker, cx.waker());
+
+        let woken = mem::take(&mut *ready.sessions.lock().unwrap());
+        for index in woken {
+            if run.stopped().is_some() {
+                return Poll::Ready(());
+            }
+
+            let session = match sessions[index].as_mut() {
+                Some(session) => session,
+                None => continue,
+            };
+            let mut session_cx = Context::from_waker(&session.waker);
+            if let Poll::Ready(result) = session.future.as_mut().poll(&mut session_cx) {
+                run.finish(&session.imsi, result);
+            } else {
+                continue;
+            }
+            sessions[index] = None;
+            running -= 1;
+        }
+
+        if running == 0 || run.stopped().is_some() {
+            return Poll::Ready(());
+        }
+
+        // sessions waiting for their requests to be encoded are woken
+        batch.flush();
+
+        // sessions woken while the others were polled are polled on the next
+        // turn, so the runtime could serve I/O and timers meanwhile
+        if !ready.sessions.lock().unwrap().is_empty() {
+            cx.waker().wake_by_ref();
+        }
+        Poll::Pending
+    })
+    .await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod session_tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingWaker {
+        woken: AtomicUsize,
+    }
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.woken.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn stop(reason: &str, scope: Scope) -> LuaError {
+        LuaError::external(Stop {
+            reason: reason.to_string(),
+            scope,
+        })
+    }
+
+    #[test]
+    fn stop_with_error_test() {
+        let lua = Lua::new();
+
+        let err = stop_with_error(&lua, (None, None)).unwrap_err();
+        let stop = Stop::of(&err).unwrap();
+        assert_eq!((stop.reason.as_str(), stop.scope), (DEFAULT_REASON, Scope::Session));
+
+        let err = stop_with_error(&lua, (Some("down".to_string()), Some("run".to_string())));
+        let err = err.unwrap_err();
+        let stop = Stop::of(&err).unwrap();
+        assert_eq!((stop.reason.as_str(), stop.scope), ("down", Scope::Run));
+
+        let err = stop_with_error(&lua, (None, Some("worker".to_string()))).unwrap_err();
+        assert!(Stop::of(&err).is_none());
+    }
+
+    #[test]
+    fn finish_test() {
+        let aborted = STATS.aborted();
+        let run = Run::new(Some(aborted + 3));
+        let worker = run.join();
+        let waker = Arc::new(CountingWaker::default());
+        run.set_waker(worker, &Waker::from(waker.clone()));
+
+        // sessions aborted within the session scope don't stop the run until
+        // `max_errors` sessions are aborted, stops raised from callbacks are
+        // wrapped into callback errors
+        run.finish("262019876543200", Ok(()));
+        run.finish("262019876543201", Err(stop("rejected", Scope::Session)));
+        let err = LuaError::CallbackError {
+            traceback: String::new(),
+            cause: Arc::new(stop("no Framed-IP-Address", Scope::Session)),
+        };
+        run.finish("262019876543202", Err(err));
+        assert_eq!(run.stopped(), None);
+        assert_eq!(waker.woken.load(Ordering::Relaxed), 0);
+
+        // sessions failed with script errors are aborted as well, the workers
+        // are woken once the run is stopped
+        let err = LuaError::RuntimeError("attempt to index a nil value".to_string());
+        run.finish("262019876543203", Err(err));
+        let reason = format!("{} session(s) aborted", aborted + 3);
+        assert_eq!(run.stopped(), Some(reason.clone()));
+        assert_eq!(waker.woken.load(Ordering::Relaxed), 1);
+
+        // the reason of the first stop is kept
+        run.finish("262019876543204", Err(stop("server is down", Scope::Run)));
+        assert_eq!(run.stopped(), Some(reason));
+        assert_eq!(waker.woken.load(Ordering::Relaxed), 1);
+
+        // the run scope stops the run at once
+        let run = Run::new(None);
+        run.finish("262019876543205", Err(stop("server is down", Scope::Run)));
+        assert_eq!(run.stopped(), Some("server is down".to_string()));
+    }
+
+    #[tokio::test]
+    async fn run_sessions_test() {
+        let lua = Lua::new();
+        lua.globals().set("libwl", crate::exports(&lua).unwrap()).unwrap();
+        lua.load(
+            r#"
+            finished = {}
+            function run(state)
+                libwl.sleep(0.01)
+                if state['3GPP-IMSI'] == '262019876543201' then
+                    error('no Framed-IP-Address')
+                end
+                table.insert(finished, state['3GPP-IMSI'])
+            end
+            "#,
+        )
+        .exec()
+        .unwrap();
+
+        let imsis = ["262019876543200", "262019876543201", "262019876543202"];
+        let run = Run::new(None);
+        let batch = Batch::new();
+        run_sessions(&lua, imsis.iter().map(|imsi| imsi.to_string()).collect(), &run, &batch)
+            .await
+            .unwrap();
+
+        // the failed session is aborted, the others are finished
+        let finished = lua.globals().get::<_, Vec<String>>("finished").unwrap();
+        assert_eq!(finished, ["262019876543200", "262019876543202"]);
+        assert_eq!(run.stopped(), None);
+    }
+}
@@ -0,0 +1,133 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// Results of the current run.
+lazy_static! {
+    pub static ref STATS: Stats = Stats::new();
+}
+
+/// Class of a failed RADIUS request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Failure {
+    /// The request could not be built from the scenario packet.
+    InvalidRequest,
+    /// The reply could not be decoded.
+    MalformedResponse,
+    /// The Response Authenticator of the reply does not match the request
+    /// and the shared secret.
+    InvalidResponseAuthenticator,
+    /// The Message-Authenticator of the reply does not match the request and
+    /// the shared secret.
+    InvalidMessageAuthenticator,
+    /// The reply has no Message-Authenticator although it is required.
+    MissingMessageAuthenticator,
+    /// No reply was received after all the retransmissions.
+    Timeout,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::InvalidRequest => write!(f, "invalid request"),
+            Failure::MalformedResponse => write!(f, "malformed response"),
+            Failure::InvalidResponseAuthenticator => write!(f, "invalid response authenticator"),
+            Failure::InvalidMessageAuthenticator => write!(f, "invalid message authenticator"),
+            Failure::MissingMessageAuthenticator => write!(f, "missing message authenticator"),
+            Failure::Timeout => write!(f, "timeout"),
+        }
+    }
+}
+
+/// Counters of RADIUS requests and their outcomes collected during a run.
+#[derive(Debug)]
+pub struct Stats {
+    requests: AtomicU64,
+    responses: AtomicU64,
+    retransmissions: AtomicU64,
+    receive_errors: AtomicU64,
+    failures: Mutex<HashMap<Failure, u64>>,
+    aborted: Mutex<HashMap<String, u64>>,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats {
+            requests: AtomicU64::new(0),
+            responses: AtomicU64::new(0),
+            retransmissions: AtomicU64::new(0),
+            receive_errors: AtomicU64::new(0),
+            failures: Mutex::new(HashMap::new()),
+            aborted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn response(&self) {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn retransmission(&self) {
+        self.retransmissions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn receive_error(&self) {
+        self.receive_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn failure(&self, failure: Failure) {
+        *self.failures.lock().unwrap().entry(failure).or_insert(0) += 1;
+    }
+
+    pub fn failures(&self, failure: &Failure) -> u64 {
+        self.failures.lock().unwrap().get(failure).copied().unwrap_or(0)
+    }
+
+    // Records the subscriber session aborted with the given `reason` and
+    // returns the total number of aborted sessions.
+    pub fn abort(&self, reason: &str) -> u64 {
+        let mut aborted = self.aborted.lock().unwrap();
+        *aborted.entry(reason.to_string()).or_insert(0) += 1;
+        aborted.values().sum()
+    }
+
+    pub fn aborted(&self) -> u64 {
+        self.aborted.lock().unwrap().values().sum()
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats::new()
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "requests: {}", self.requests.load(Ordering::Relaxed))?;
+        writeln!(f, "responses: {}", self.responses.load(Ordering::Relaxed))?;
+        writeln!(f, "retransmissions: {}", self.retransmissions.load(Ordering::Relaxed))?;
+        writeln!(f, "receive errors: {}", self.receive_errors.load(Ordering::Relaxed))?;
+
+        let failures = self.failures.lock().unwrap();
+        let mut failures = failures.iter().collect::<Vec<(&Failure, &u64)>>();
+        failures.sort_by_key(|(failure, _)| failure.to_string());
+        for (failure, count) in failures {
+            writeln!(f, "failed ({}): {}", failure, count)?;
+        }
+
+        let aborted = self.aborted.lock().unwrap();
+        let mut aborted = aborted.iter().collect::<Vec<(&String, &u64)>>();
+        aborted.sort();
+        for (reason, count) in aborted {
+            writeln!(f, "aborted ({}): {}", reason, count)?;
+        }
+
+        Ok(())
+    }
+}
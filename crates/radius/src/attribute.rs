@@ -1,6 +1,70 @@
 /// Defines the types and auxilary functions to represent and work with
 /// RADIUS attributes.
-///
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use super::error::RadiusError;
+use super::packet::{HEADER_LENGTH, MAX_PACKET_LENGTH};
+
+/// Type of the Vendor-Specific attribute according to RFC 2865 5.26.
+pub const VENDOR_SPECIFIC_TYPE: u8 = 26;
+
+/// Types of the Extended-Type attributes according to RFC 6929 2.1.
+pub const EXTENDED_TYPES: RangeInclusive<u8> = 241..=244;
+
+/// Types of the Long-Extended-Type attributes according to RFC 6929 2.2.
+pub const LONG_EXTENDED_TYPES: RangeInclusive<u8> = 245..=246;
+
+/// Extended-Type of the Extended-Vendor-Specific attributes according to
+/// RFC 6929 2.4.
+pub const EXTENDED_VENDOR_SPECIFIC_TYPE: u8 = 26;
+
+/// Type of the Message-Authenticator attribute according to RFC 3579 3.2.
+pub const MESSAGE_AUTHENTICATOR_TYPE: u8 = 80;
+
+/// Type of the CHAP-Password attribute according to RFC 2865 5.3.
+pub const CHAP_PASSWORD_TYPE: u8 = 3;
+
+/// Type of the CHAP-Challenge attribute according to RFC 2865 5.40.
+pub const CHAP_CHALLENGE_TYPE: u8 = 60;
+
+/// Size of the `Type` and `Length` fields of a RADIUS attribute.
+const ATTRIBUTE_HEADER_LENGTH: usize = 2;
+
+/// Size of the `Vendor-Id` field of a Vendor-Specific attribute.
+const VENDOR_ID_LENGTH: usize = 4;
+
+/// Maximum value of the `Length` field of a RADIUS attribute.
+const MAX_ATTRIBUTE_LENGTH: usize = 255;
+
+/// The continuation flag of a vendor attribute that is continued by the
+/// next Vendor-Specific attribute.
+const CONTINUATION_FLAG: u8 = 0x80;
+
+/// Size of the `Extended-Type` field of an extended attribute.
+const EXTENDED_TYPE_LENGTH: usize = 1;
+
+/// Size of the `Flags` field of a long extended attribute.
+const FLAGS_LENGTH: usize = 1;
+
+/// The "More" flag of a long extended attribute that is followed by the
+/// next fragment.
+const MORE_FLAG: u8 = 0x80;
+
+/// Size of the `Vendor-Id` and `EVS-Type` fields of an
+/// Extended-Vendor-Specific attribute.
+const EVS_HEADER_LENGTH: usize = VENDOR_ID_LENGTH + 1;
+
+/// Maximum value of the `Tag` field of a tagged attribute according to
+/// RFC 2868 3.1.
+pub const MAX_TAG: u8 = 0x1f;
+
+/// Size of the `Tag` field of a tagged attribute.
+const TAG_LENGTH: usize = 1;
+
 /// According to the RFC 2865 the format of the RADIUS attribute is:
 ///
 ///    0                   1                   2
@@ -30,13 +94,50 @@
 ///  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 ///  |    Attribute-Specific...
 ///  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-
-
-#[derive(Debug)]
+///
+/// RFC 6929 adds the extended attributes of types `241`-`244`:
+///
+///    0                   1                   2                   3
+///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |     Type      |    Length     | Extended-Type |  Value ...
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///
+/// and the long extended attributes of types `245`-`246` which values are
+/// split into fragments chained by the "More" flag:
+///
+///    0                   1                   2                   3
+///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |     Type      |    Length     | Extended-Type |M|  Reserved   |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |  Value ...
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///
+/// The value of an Extended-Vendor-Specific attribute (Extended-Type `26`)
+/// starts with the `Vendor-Id` and the `EVS-Type` of the vendor attribute,
+/// which are represented by the vendor part of an `Attribute`.
+///
+/// The value of an `Attribute` is its value only, the `Type`/`Length`
+/// header, the extended type, the vendor part and the fragmentation of long
+/// extended attributes are added during encoding. Values of attributes
+/// marked with `set_concat` are split into several consecutive attributes of
+/// the same type as well.
+///
+/// RFC 2868 adds the `Tag` field that precedes the values of the tunnel
+/// attributes and groups attributes describing the same tunnel. The tag of
+/// an `Attribute` is set with `set_tag` and is written before the value
+/// during encoding. Decoded attributes never have a tag, since only the
+/// dictionary knows which attributes carry it (see
+/// `AttributeDefinition::tag`).
+#[derive(Debug, Clone)]
 pub struct Attribute {
     attr_type: u8,
-    length: u8,
+    extended_type: Option<u8>,
     vendor: Option<Vendor>,
-    value: [u8; 255],
+    concat: bool,
+    tag: Option<u8>,
+    value: Vec<u8>,
 }
 
 impl Attribute {
@@ -57,18 +158,598 @@ impl Attribute {
     pub fn new(id: u8, vendor: Option<Vendor>) -> Attribute {
         Attribute {
             attr_type: id,
-            length: 0,
-            vendor: vendor,
-            value: [0; 255],
+            extended_type: None,
+            vendor,
+            concat: false,
+            tag: None,
+            value: Vec::new(),
+        }
+    }
+
+    /// Creates new extended `Attribute` according to RFC 6929.
+    ///
+    /// The `id` should be one of `241`-`246`. Extended-Vendor-Specific
+    /// attributes have the `extended_type` equal to `26` and the `vendor`
+    /// with the `EVS-Type` as the vendor type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::attribute::{Attribute, Vendor};
+    ///
+    /// let frag_status = Attribute::new_extended(241, 1, None);
+    /// let evs = Attribute::new_extended(245, 26, Some(Vendor::new(18681, 1)));
+    ///
+    /// assert_eq!(frag_status.extended_type(), Some(1));
+    /// assert!(evs.is_long_extended());
+    /// ```
+    #[inline]
+    pub fn new_extended(id: u8, extended_type: u8, vendor: Option<Vendor>) -> Attribute {
+        Attribute {
+            attr_type: id,
+            extended_type: Some(extended_type),
+            vendor,
+            concat: false,
+            tag: None,
+            value: Vec::new(),
+        }
+    }
+
+    /// Returns the RADIUS attribute type.
+    ///
+    /// For the vendor specific attributes it is always `26`, the type of the
+    /// attribute within vendor space could be obtained via `vendor()`.
+    #[inline]
+    pub fn attr_type(&self) -> u8 {
+        self.attr_type
+    }
+
+    /// Returns the `Extended-Type` of an extended attribute.
+    #[inline]
+    pub fn extended_type(&self) -> Option<u8> {
+        self.extended_type
+    }
+
+    /// Returns `true` if the attribute is a long extended attribute that
+    /// could be fragmented.
+    #[inline]
+    pub fn is_long_extended(&self) -> bool {
+        self.extended_type.is_some() && LONG_EXTENDED_TYPES.contains(&self.attr_type)
+    }
+
+    /// Returns `true` if the value of the attribute is split into several
+    /// consecutive attributes when it does not fit into one.
+    #[inline]
+    pub fn is_concat(&self) -> bool {
+        self.concat
+    }
+
+    /// Sets whether the value of the attribute is split into several
+    /// consecutive attributes when it does not fit into one, like the values
+    /// of EAP-Message attributes (RFC 3579 3.1).
+    ///
+    /// The flag is ignored for extended attributes, long values of those are
+    /// carried by long extended attributes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::attribute::Attribute;
+    ///
+    /// let mut eap_message = Attribute::new(79, None);
+    /// eap_message.set_concat(true);
+    /// eap_message.set_value(&[0; 300]).unwrap();
+    ///
+    /// let mut buf = Vec::new();
+    /// eap_message.encode(&mut buf);
+    /// assert_eq!(buf.len(), 2 + 253 + 2 + 47);
+    /// ```
+    #[inline]
+    pub fn set_concat(&mut self, concat: bool) -> &mut Self {
+        self.concat = concat;
+        self
+    }
+
+    /// Returns the tag of the attribute.
+    #[inline]
+    pub fn tag(&self) -> Option<u8> {
+        self.tag
+    }
+
+    /// Sets the tag of the attribute according to RFC 2868 3.1.
+    ///
+    /// The tag takes the first octet of the attribute value, so the value
+    /// should leave room for it. Returns `RadiusError::InvalidAttributeTag`
+    /// if the tag is out of range `0..31` or the value is too long.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::attribute::Attribute;
+    ///
+    /// let mut tunnel_server_endpoint = Attribute::new(67, None);
+    /// tunnel_server_endpoint.set_value(b"192.0.2.1").unwrap();
+    /// tunnel_server_endpoint.set_tag(Some(1)).unwrap();
+    ///
+    /// let mut buf = Vec::new();
+    /// tunnel_server_endpoint.encode(&mut buf);
+    /// assert_eq!(&buf[..4], &[67, 12, 1, b'1']);
+    /// ```
+    pub fn set_tag(&mut self, tag: Option<u8>) -> Result<&mut Self, RadiusError> {
+        let previous = std::mem::replace(&mut self.tag, tag);
+        if let Some(tag) = tag {
+            if tag > MAX_TAG || self.value.len() > self.max_value_length() {
+                self.tag = previous;
+                return Err(RadiusError::InvalidAttributeTag(self.attr_type, tag));
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Returns the vendor part of the attribute if the attribute is vendor
+    /// specific or Extended-Vendor-Specific.
+    #[inline]
+    pub fn vendor(&self) -> Option<&Vendor> {
+        self.vendor.as_ref()
+    }
+
+    /// Returns the value of the attribute.
+    #[inline]
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    /// Returns the maximum length of a value that could be carried by the
+    /// attribute.
+    ///
+    /// The `Length` field of a RADIUS attribute is one octet long and includes
+    /// the attribute header, so the value of a standard attribute could not be
+    /// longer than `253` octets and the value of a vendor specific attribute
+    /// could not be longer than `247` octets (less for vendors with wider
+    /// fields, see `VendorFormat`). Extended attributes carry up to
+    /// `252` octets (`247` for Extended-Vendor-Specific ones) and the values
+    /// of long extended attributes, of attributes marked with `set_concat` and
+    /// of vendor attributes with the continuation flag are limited by the size
+    /// of a packet only. The tag of a tagged attribute takes one more octet.
+    #[inline]
+    pub fn max_value_length(&self) -> usize {
+        let vendor = match (self.extended_type, &self.vendor) {
+            (None, None) => 0,
+            (None, Some(vendor)) => VENDOR_ID_LENGTH + vendor.format.header_length(),
+            (Some(_), None) => 0,
+            (Some(_), Some(_)) => EVS_HEADER_LENGTH,
+        } + self.tag.map_or(0, |_| TAG_LENGTH);
+
+        match self.extended_type {
+            None if self.concat || self.has_continuation() => MAX_PACKET_LENGTH - HEADER_LENGTH,
+            None => MAX_ATTRIBUTE_LENGTH - ATTRIBUTE_HEADER_LENGTH - vendor,
+            Some(_) if self.is_long_extended() => MAX_PACKET_LENGTH - HEADER_LENGTH - vendor,
+            Some(_) => {
+                MAX_ATTRIBUTE_LENGTH - ATTRIBUTE_HEADER_LENGTH - EXTENDED_TYPE_LENGTH - vendor
+            }
+        }
+    }
+
+    /// Sets the value of the attribute.
+    ///
+    /// Returns `RadiusError::AttributeValueTooLong` if the given `value` does
+    /// not fit into the attribute.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::attribute::Attribute;
+    ///
+    /// let mut attr: Attribute = Attribute::new(1, None);
+    /// attr.set_value(b"user").unwrap();
+    ///
+    /// assert_eq!(attr.value(), b"user");
+    /// ```
+    pub fn set_value(&mut self, value: &[u8]) -> Result<(), RadiusError> {
+        if value.len() > self.max_value_length() {
+            return Err(RadiusError::AttributeValueTooLong(
+                self.attr_type,
+                value.len(),
+            ));
+        }
+
+        self.value = value.to_vec();
+        Ok(())
+    }
+
+    /// Returns the number of octets the attribute occupies on the wire.
+    #[inline]
+    pub fn encoded_length(&self) -> usize {
+        match self.extended_type {
+            None => {
+                let header = self.header_length();
+                let data = self.data().len();
+                let chunks = data.div_ceil(MAX_ATTRIBUTE_LENGTH - header);
+
+                // an empty attribute is still encoded once
+                chunks.max(1) * header + data
+            }
+            Some(_) => {
+                let header = self.extended_header_length();
+                let data = self.extended_data_length();
+                let fragment = MAX_ATTRIBUTE_LENGTH - header;
+
+                // an empty long extended attribute still takes one fragment
+                let fragments = if self.is_long_extended() {
+                    data.div_ceil(fragment).max(1)
+                } else {
+                    1
+                };
+
+                fragments * header + data
+            }
         }
     }
+
+    /// Appends the wire representation of the attribute to the given `buf`.
+    ///
+    /// Every vendor specific attribute is encoded as a separate
+    /// Vendor-Specific attribute carrying exactly one vendor attribute. Long
+    /// extended attributes are split into fragments of at most `255` octets
+    /// and long values of attributes marked with `set_concat` or of vendors
+    /// with the continuation flag into several consecutive attributes.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        let extended_type = match self.extended_type {
+            Some(extended_type) => extended_type,
+            None => {
+                let header = self.header_length();
+                let data = self.data();
+                let chunks: Vec<&[u8]> = match data.is_empty() {
+                    true => vec![&[]],
+                    false => data.chunks(MAX_ATTRIBUTE_LENGTH - header).collect(),
+                };
+
+                let last = chunks.len() - 1;
+                for (i, chunk) in chunks.into_iter().enumerate() {
+                    buf.push(self.attr_type);
+                    buf.push((header + chunk.len()) as u8);
+
+                    if let Some(vendor) = &self.vendor {
+                        buf.extend_from_slice(&vendor.vendor_id.to_be_bytes());
+                        vendor.encode_header(chunk.len(), i != last, buf);
+                    }
+
+                    buf.extend_from_slice(chunk);
+                }
+                return;
+            }
+        };
+
+        // the Vendor-Id and EVS-Type are the beginning of the value of an
+        // Extended-Vendor-Specific attribute, so they are fragmented as well
+        let mut data = Vec::with_capacity(self.extended_data_length());
+        if let Some(vendor) = &self.vendor {
+            data.extend_from_slice(&vendor.vendor_id.to_be_bytes());
+            // the EVS-Type is always one octet long
+            data.push(vendor.vendor_type as u8);
+        }
+        data.extend_from_slice(&self.data());
+
+        if !self.is_long_extended() {
+            buf.push(self.attr_type);
+            buf.push((ATTRIBUTE_HEADER_LENGTH + EXTENDED_TYPE_LENGTH + data.len()) as u8);
+            buf.push(extended_type);
+            buf.extend_from_slice(&data);
+            return;
+        }
+
+        let header = self.extended_header_length();
+        let mut fragments = data.chunks(MAX_ATTRIBUTE_LENGTH - header).peekable();
+        if fragments.peek().is_none() {
+            buf.extend_from_slice(&[self.attr_type, header as u8, extended_type, 0]);
+        }
+
+        while let Some(fragment) = fragments.next() {
+            let flags = if fragments.peek().is_some() { MORE_FLAG } else { 0 };
+            buf.extend_from_slice(&[
+                self.attr_type,
+                (header + fragment.len()) as u8,
+                extended_type,
+                flags,
+            ]);
+            buf.extend_from_slice(fragment);
+        }
+    }
+
+    // Returns the size of the header of every attribute a value of a
+    // standard or vendor specific attribute is encoded into.
+    fn header_length(&self) -> usize {
+        match &self.vendor {
+            None => ATTRIBUTE_HEADER_LENGTH,
+            Some(vendor) => {
+                ATTRIBUTE_HEADER_LENGTH + VENDOR_ID_LENGTH + vendor.format.header_length()
+            }
+        }
+    }
+
+    // Returns true if the attribute is a vendor attribute which value could
+    // be continued by the next Vendor-Specific attribute.
+    fn has_continuation(&self) -> bool {
+        matches!(&self.vendor, Some(vendor) if vendor.format.continuation)
+    }
+
+    // Returns the size of the header of every fragment of an extended
+    // attribute.
+    fn extended_header_length(&self) -> usize {
+        if self.is_long_extended() {
+            ATTRIBUTE_HEADER_LENGTH + EXTENDED_TYPE_LENGTH + FLAGS_LENGTH
+        } else {
+            ATTRIBUTE_HEADER_LENGTH + EXTENDED_TYPE_LENGTH
+        }
+    }
+
+    // Returns the size of the data carried by the fragments of an extended
+    // attribute.
+    fn extended_data_length(&self) -> usize {
+        match self.vendor {
+            None => self.data().len(),
+            Some(_) => EVS_HEADER_LENGTH + self.data().len(),
+        }
+    }
+
+    // Returns the value of the attribute preceded by the tag if the
+    // attribute is tagged.
+    fn data(&self) -> Cow<'_, [u8]> {
+        match self.tag {
+            None => Cow::Borrowed(&self.value),
+            Some(tag) => {
+                let mut data = Vec::with_capacity(TAG_LENGTH + self.value.len());
+                data.push(tag);
+                data.extend_from_slice(&self.value);
+                Cow::Owned(data)
+            }
+        }
+    }
+
+    /// Decodes all RADIUS attributes from the given `buf`.
+    ///
+    /// The `buf` should contain only the attributes part of a RADIUS packet.
+    /// Vendor-Specific attributes are split into the vendor attributes they
+    /// carry, so a single Vendor-Specific attribute may produce more than one
+    /// `Attribute`. The fragments of long extended attributes are reassembled
+    /// into a single `Attribute`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::attribute::Attribute;
+    ///
+    /// let attrs = Attribute::decode(&[1, 6, b'u', b's', b'e', b'r']).unwrap();
+    ///
+    /// assert_eq!(attrs.len(), 1);
+    /// assert_eq!(attrs[0].attr_type(), 1);
+    /// assert_eq!(attrs[0].value(), b"user");
+    /// ```
+    pub fn decode(buf: &[u8]) -> Result<Vec<Attribute>, RadiusError> {
+        Self::decode_with_formats(buf, &VendorFormats::new())
+    }
+
+    /// Decodes all RADIUS attributes from the given `buf` like `decode` does,
+    /// splitting Vendor-Specific attributes of the vendors with the given
+    /// `formats` accordingly. Vendor attributes of other vendors are decoded
+    /// with the default format.
+    ///
+    /// Values of vendor attributes with the continuation flag set are joined
+    /// with the values of the same vendor attributes within the following
+    /// Vendor-Specific attribute.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::attribute::{Attribute, VendorFormat, VendorFormats};
+    ///
+    /// let mut formats = VendorFormats::new();
+    /// formats.insert(429, "4,0".parse::<VendorFormat>().unwrap());
+    ///
+    /// let buf = [26, 12, 0, 0, 0x01, 0xad, 0, 0, 0x90, 0x0f, b'u', b's'];
+    /// let attrs = Attribute::decode_with_formats(&buf, &formats).unwrap();
+    ///
+    /// assert_eq!(attrs[0].vendor().unwrap().vendor_type(), 0x900f);
+    /// assert_eq!(attrs[0].value(), b"us");
+    /// ```
+    pub fn decode_with_formats(
+        buf: &[u8],
+        formats: &VendorFormats,
+    ) -> Result<Vec<Attribute>, RadiusError> {
+        let mut attrs: Vec<Attribute> = Vec::new();
+        let mut continuation = false;
+        let mut pos = 0;
+
+        while pos < buf.len() {
+            if buf.len() - pos < ATTRIBUTE_HEADER_LENGTH {
+                return Err(RadiusError::InvalidAttributeLength(buf[pos], 0));
+            }
+
+            let attr_type = buf[pos];
+            let length = buf[pos + 1] as usize;
+            if length < ATTRIBUTE_HEADER_LENGTH || pos + length > buf.len() {
+                return Err(RadiusError::InvalidAttributeLength(
+                    attr_type,
+                    length as u8,
+                ));
+            }
+
+            let value = &buf[pos + ATTRIBUTE_HEADER_LENGTH..pos + length];
+            // vendor attributes could be continued by the next attribute only
+            let continued = std::mem::take(&mut continuation);
+            if attr_type == VENDOR_SPECIFIC_TYPE {
+                continuation = Self::decode_vendor_specific(value, formats, continued, &mut attrs)?;
+            } else if EXTENDED_TYPES.contains(&attr_type) {
+                let (extended_type, data) = value
+                    .split_first()
+                    .ok_or(RadiusError::InvalidExtendedAttribute(attr_type))?;
+                attrs.push(Self::decode_extended(attr_type, *extended_type, data)?);
+            } else if LONG_EXTENDED_TYPES.contains(&attr_type) {
+                let (attr, consumed) = Self::decode_long_extended(&buf[pos..])?;
+                attrs.push(attr);
+                pos += consumed;
+                continue;
+            } else {
+                let mut attr = Attribute::new(attr_type, None);
+                attr.set_value(value)?;
+                attrs.push(attr);
+            }
+
+            pos += length;
+        }
+
+        Ok(attrs)
+    }
+
+    // Builds the extended attribute from the data following the
+    // Extended-Type field, which starts with the Vendor-Id and the EVS-Type
+    // for Extended-Vendor-Specific attributes.
+    fn decode_extended(
+        attr_type: u8,
+        extended_type: u8,
+        data: &[u8],
+    ) -> Result<Attribute, RadiusError> {
+        let mut attr = if extended_type == EXTENDED_VENDOR_SPECIFIC_TYPE {
+            if data.len() < EVS_HEADER_LENGTH {
+                return Err(RadiusError::InvalidExtendedAttribute(attr_type));
+            }
+
+            let vendor_id = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+            let vendor = Vendor::new(vendor_id, data[VENDOR_ID_LENGTH].into());
+            Attribute::new_extended(attr_type, extended_type, Some(vendor))
+        } else {
+            Attribute::new_extended(attr_type, extended_type, None)
+        };
+
+        let data = match attr.vendor {
+            Some(_) => &data[EVS_HEADER_LENGTH..],
+            None => data,
+        };
+        attr.set_value(data)?;
+
+        Ok(attr)
+    }
+
+    // Decodes the long extended attribute at the beginning of the given `buf`
+    // reassembling its fragments. Returns the attribute with the number of
+    // consumed octets.
+    //
+    // According to RFC 6929 2.2 the fragments of an attribute must be
+    // contiguous and have the same Type and Extended-Type.
+    fn decode_long_extended(buf: &[u8]) -> Result<(Attribute, usize), RadiusError> {
+        let header = ATTRIBUTE_HEADER_LENGTH + EXTENDED_TYPE_LENGTH + FLAGS_LENGTH;
+        let (attr_type, extended_type) = (buf[0], buf.get(2).copied());
+
+        let mut data = Vec::new();
+        let mut pos = 0;
+        loop {
+            let fragment = &buf[pos..];
+            if fragment.len() < header
+                || (fragment[1] as usize) < header
+                || fragment[1] as usize > fragment.len()
+                || fragment[0] != attr_type
+                || Some(fragment[2]) != extended_type
+            {
+                return Err(RadiusError::InvalidExtendedAttribute(attr_type));
+            }
+
+            let length = fragment[1] as usize;
+            data.extend_from_slice(&fragment[header..length]);
+            pos += length;
+
+            if fragment[3] & MORE_FLAG == 0 {
+                break;
+            }
+        }
+
+        let attr = Self::decode_extended(attr_type, buf[2], &data)?;
+        Ok((attr, pos))
+    }
+
+    // Decodes the value of a Vendor-Specific attribute that follows the
+    // recommended format of RFC 2865 5.26 or the format of the vendor from
+    // the given `formats`. If the previous Vendor-Specific attribute is
+    // `continued`, the value of its last vendor attribute is continued by the
+    // first vendor attribute of this one.
+    //
+    // Returns true if the last vendor attribute has the continuation flag.
+    fn decode_vendor_specific(
+        value: &[u8],
+        formats: &VendorFormats,
+        mut continued: bool,
+        attrs: &mut Vec<Attribute>,
+    ) -> Result<bool, RadiusError> {
+        if value.len() < VENDOR_ID_LENGTH {
+            return Err(RadiusError::InvalidAttributeLength(
+                VENDOR_SPECIFIC_TYPE,
+                (value.len() + ATTRIBUTE_HEADER_LENGTH) as u8,
+            ));
+        }
+
+        let vendor_id = u32::from_be_bytes([value[0], value[1], value[2], value[3]]);
+        let format = formats.get(&vendor_id).copied().unwrap_or_default();
+        let (type_width, length_width) = (format.type_width(), format.length_width());
+        let header = format.header_length();
+        let mut pos = VENDOR_ID_LENGTH;
+
+        while pos < value.len() {
+            if value.len() - pos < header {
+                return Err(RadiusError::InvalidVendorAttributeLength(
+                    vendor_id,
+                    value[pos] as u32,
+                    0,
+                ));
+            }
+
+            let vendor_type = read_number(&value[pos..pos + type_width]) as u32;
+            // without the length field the vendor attribute takes the rest
+            // of the Vendor-Specific attribute
+            let length = match length_width {
+                0 => value.len() - pos,
+                _ => read_number(&value[pos + type_width..pos + type_width + length_width]),
+            };
+            if length < header || pos + length > value.len() {
+                return Err(RadiusError::InvalidVendorAttributeLength(
+                    vendor_id,
+                    vendor_type,
+                    length,
+                ));
+            }
+
+            let data = &value[pos + header..pos + length];
+            let code = Some((vendor_id, vendor_type));
+            match attrs.last_mut() {
+                Some(last) if continued && last.vendor().map(Vendor::code) == code => {
+                    last.value.extend_from_slice(data);
+                }
+                _ => {
+                    let vendor = Vendor::with_format(vendor_id, vendor_type, format);
+                    let mut attr = Attribute::new(VENDOR_SPECIFIC_TYPE, Some(vendor));
+                    attr.set_value(data)?;
+                    attrs.push(attr);
+                }
+            }
+
+            continued = format.continuation
+                && value[pos + type_width + length_width] & CONTINUATION_FLAG != 0;
+            pos += length;
+        }
+
+        Ok(continued)
+    }
+}
+
+// Reads a big endian number of up to eight octets.
+fn read_number(buf: &[u8]) -> usize {
+    buf.iter().fold(0, |n, b| (n << 8) | *b as usize)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Vendor {
     vendor_id: u32,
-    vendor_type: u8,
-    length: u8,
+    vendor_type: u32,
+    format: VendorFormat,
 }
 
 impl Vendor {
@@ -83,11 +764,459 @@ impl Vendor {
     /// let attr: Attribute = Attribute::new(26, Some(vendor));
     /// ```
     #[inline]
-    pub fn new(id: u32, vendor_type: u8) -> Vendor {
+    pub fn new(id: u32, vendor_type: u32) -> Vendor {
+        Vendor::with_format(id, vendor_type, VendorFormat::default())
+    }
+
+    /// Creates new `vendor` part of a RADIUS attribute of a vendor which
+    /// Vendor-Specific attributes have the given `format`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::attribute::{Attribute, Vendor, VendorFormat};
+    ///
+    /// let format = "4,0".parse::<VendorFormat>().unwrap();
+    /// let mut attr = Attribute::new(26, Some(Vendor::with_format(429, 0x900f, format)));
+    /// attr.set_value(b"us").unwrap();
+    ///
+    /// let mut buf = Vec::new();
+    /// attr.encode(&mut buf);
+    /// assert_eq!(buf, [26, 12, 0, 0, 0x01, 0xad, 0, 0, 0x90, 0x0f, b'u', b's']);
+    /// ```
+    #[inline]
+    pub fn with_format(id: u32, vendor_type: u32, format: VendorFormat) -> Vendor {
         Vendor {
             vendor_id: id,
-            vendor_type: vendor_type,
-            length: 0,
+            vendor_type,
+            format,
+        }
+    }
+
+    /// Returns the IANA private enterprise number of the vendor.
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.vendor_id
+    }
+
+    /// Returns the type of the attribute within the vendor space.
+    #[inline]
+    pub fn vendor_type(&self) -> u32 {
+        self.vendor_type
+    }
+
+    /// Returns the format of the Vendor-Specific attributes of the vendor.
+    #[inline]
+    pub fn format(&self) -> VendorFormat {
+        self.format
+    }
+
+    // Returns the vendor id and the type of the attribute within the vendor
+    // space.
+    fn code(&self) -> (u32, u32) {
+        (self.vendor_id, self.vendor_type)
+    }
+
+    // Appends the type, the length and the continuation fields of a vendor
+    // attribute with a value of the given `length` to the `buf`.
+    fn encode_header(&self, length: usize, more: bool, buf: &mut Vec<u8>) {
+        let format = &self.format;
+        buf.extend_from_slice(&self.vendor_type.to_be_bytes()[4 - format.type_width()..]);
+
+        let length = (format.header_length() + length) as u32;
+        buf.extend_from_slice(&length.to_be_bytes()[4 - format.length_width()..]);
+
+        if format.continuation {
+            buf.push(if more { CONTINUATION_FLAG } else { 0 });
+        }
+    }
+}
+
+/// Formats of the Vendor-Specific attributes by the ids of the vendors.
+pub type VendorFormats = HashMap<u32, VendorFormat>;
+
+/// Format of the vendor attributes carried by the Vendor-Specific attributes
+/// of a vendor.
+///
+/// Most of the vendors follow the format recommended by RFC 2865 5.26 with
+/// one octet `Vendor type` and `Vendor length` fields, others use wider type
+/// fields, wider or no length fields at all, or add the continuation octet
+/// after the length like WiMAX does. The format is written as
+/// `<type width>,<length width>[,c]`, e.g. `1,1` for the recommended format,
+/// `4,0` for USR or `1,1,c` for WiMAX.
+///
+/// Values of vendor attributes with the continuation octet that do not fit
+/// into one Vendor-Specific attribute are split into several consecutive
+/// Vendor-Specific attributes with the continuation flag set in all but the
+/// last one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VendorFormat {
+    type_width: usize,
+    length_width: usize,
+    continuation: bool,
+}
+
+impl VendorFormat {
+    /// Creates new `VendorFormat` with the given widths of the type and
+    /// length fields in octets.
+    ///
+    /// The type field could be `1`, `2` or `4` octets long and the length
+    /// field `0`, `1` or `2` octets long. The continuation octet is allowed
+    /// with the recommended format only.
+    pub fn new(
+        type_width: usize,
+        length_width: usize,
+        continuation: bool,
+    ) -> Result<VendorFormat, RadiusError> {
+        let format = VendorFormat {
+            type_width,
+            length_width,
+            continuation,
+        };
+
+        match (type_width, length_width, continuation) {
+            (1, 1, _) | (1 | 2 | 4, 0..=2, false) => Ok(format),
+            _ => Err(RadiusError::InvalidVendorFormat(format.to_string())),
         }
     }
+
+    /// Returns the width of the `Vendor type` field in octets.
+    #[inline]
+    pub fn type_width(&self) -> usize {
+        self.type_width
+    }
+
+    /// Returns the width of the `Vendor length` field in octets.
+    #[inline]
+    pub fn length_width(&self) -> usize {
+        self.length_width
+    }
+
+    /// Returns true if the vendor attributes have the continuation octet.
+    #[inline]
+    pub fn has_continuation(&self) -> bool {
+        self.continuation
+    }
+
+    /// Returns the maximum type of a vendor attribute that fits into the
+    /// `Vendor type` field.
+    pub fn max_vendor_type(&self) -> u32 {
+        match self.type_width {
+            1 => u8::MAX as u32,
+            2 => u16::MAX as u32,
+            _ => u32::MAX,
+        }
+    }
+
+    // Returns the size of the header of a vendor attribute.
+    fn header_length(&self) -> usize {
+        self.type_width + self.length_width + self.continuation as usize
+    }
+}
+
+impl Default for VendorFormat {
+    /// The format recommended by RFC 2865 5.26.
+    fn default() -> VendorFormat {
+        VendorFormat {
+            type_width: 1,
+            length_width: 1,
+            continuation: false,
+        }
+    }
+}
+
+impl FromStr for VendorFormat {
+    type Err = RadiusError;
+
+    /// Parses the format of the Vendor-Specific attributes of a vendor from
+    /// a dictionary, like `2,1` or `1,1,c`.
+    fn from_str(s: &str) -> Result<VendorFormat, RadiusError> {
+        let invalid = || RadiusError::InvalidVendorFormat(s.to_string());
+        let fields: Vec<&str> = s.split(',').map(str::trim).collect();
+
+        let (type_width, length_width, continuation) = match fields.as_slice() {
+            [type_width, length_width] => (type_width, length_width, false),
+            [type_width, length_width, "c"] => (type_width, length_width, true),
+            _ => return Err(invalid()),
+        };
+
+        VendorFormat::new(
+            type_width.parse().map_err(|_| invalid())?,
+            length_width.parse().map_err(|_| invalid())?,
+            continuation,
+        )
+        .map_err(|_| invalid())
+    }
+}
+
+impl fmt::Display for VendorFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.type_width, self.length_width)?;
+        if self.continuation {
+            write!(f, ",c")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod attribute_tests {
+    use super::*;
+
+    #[test]
+    fn encode_decode_test() {
+        let mut user_name = Attribute::new(1, None);
+        user_name.set_value(b"username@nas-id").unwrap();
+
+        let mut imsi = Attribute::new(VENDOR_SPECIFIC_TYPE, Some(Vendor::new(10415, 1)));
+        imsi.set_value(b"262019876543200").unwrap();
+
+        let mut buf: Vec<u8> = Vec::new();
+        user_name.encode(&mut buf);
+        imsi.encode(&mut buf);
+
+        assert_eq!(buf.len(), user_name.encoded_length() + imsi.encoded_length());
+        assert_eq!(&buf[17..25], &[26, 23, 0, 0, 0x28, 0xaf, 1, 17]);
+
+        let attrs = Attribute::decode(&buf).unwrap();
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].attr_type(), 1);
+        assert_eq!(attrs[0].value(), b"username@nas-id");
+        assert_eq!(attrs[1].attr_type(), VENDOR_SPECIFIC_TYPE);
+        assert_eq!(attrs[1].vendor().unwrap().id(), 10415);
+        assert_eq!(attrs[1].vendor().unwrap().vendor_type(), 1);
+        assert_eq!(attrs[1].value(), b"262019876543200");
+    }
+
+    #[test]
+    fn decode_multiple_vendor_attributes_test() {
+        let buf = [26, 13, 0, 0, 0x48, 0xf9, 1, 3, b'a', 2, 4, b'b', b'c'];
+        let attrs = Attribute::decode(&buf).unwrap();
+
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].vendor().unwrap().id(), 18681);
+        assert_eq!(attrs[0].value(), b"a");
+        assert_eq!(attrs[1].vendor().unwrap().vendor_type(), 2);
+        assert_eq!(attrs[1].value(), b"bc");
+    }
+
+    #[test]
+    fn value_too_long_test() {
+        let mut attr = Attribute::new(1, None);
+        assert!(attr.set_value(&[0; 253]).is_ok());
+        assert!(attr.set_value(&[0; 254]).is_err());
+
+        let mut attr = Attribute::new(VENDOR_SPECIFIC_TYPE, Some(Vendor::new(10415, 1)));
+        assert!(attr.set_value(&[0; 247]).is_ok());
+        assert!(attr.set_value(&[0; 248]).is_err());
+    }
+
+    #[test]
+    fn decode_invalid_length_test() {
+        assert!(Attribute::decode(&[1, 1]).is_err());
+        assert!(Attribute::decode(&[1, 6, b'a']).is_err());
+        assert!(Attribute::decode(&[1]).is_err());
+        assert!(Attribute::decode(&[26, 8, 0, 0, 0x28, 0xaf, 1, 7]).is_err());
+    }
+
+    #[test]
+    fn concat_test() {
+        let value: Vec<u8> = (0..500).map(|i| i as u8).collect();
+        let mut attr = Attribute::new(VENDOR_SPECIFIC_TYPE, Some(Vendor::new(18681, 4)));
+        assert!(attr.set_value(&value).is_err());
+        attr.set_concat(true).set_value(&value).unwrap();
+
+        let mut buf: Vec<u8> = Vec::new();
+        attr.encode(&mut buf);
+
+        // 500 octets are split into 247, 247 and 6 octets
+        assert_eq!(attr.encoded_length(), 3 * 8 + 500);
+        assert_eq!(buf.len(), attr.encoded_length());
+        assert_eq!(&buf[..8], &[26, 255, 0, 0, 0x48, 0xf9, 4, 249]);
+        assert_eq!(&buf[510..518], &[26, 14, 0, 0, 0x48, 0xf9, 4, 8]);
+
+        let attrs = Attribute::decode(&buf).unwrap();
+        assert_eq!(attrs.len(), 3);
+        assert_eq!(attrs[0].value(), &value[..247]);
+        assert_eq!(attrs[2].value(), &value[494..]);
+
+        let mut empty = Attribute::new(79, None);
+        empty.set_concat(true);
+        let mut buf: Vec<u8> = Vec::new();
+        empty.encode(&mut buf);
+        assert_eq!(buf, vec![79, 2]);
+    }
+
+    #[test]
+    fn extended_test() {
+        let mut frag_status = Attribute::new_extended(241, 1, None);
+        frag_status.set_value(&[0, 0, 0, 1]).unwrap();
+
+        let vendor = Some(Vendor::new(18681, 7));
+        let mut evs = Attribute::new_extended(242, EXTENDED_VENDOR_SPECIFIC_TYPE, vendor);
+        evs.set_value(b"abc").unwrap();
+
+        let mut buf: Vec<u8> = Vec::new();
+        frag_status.encode(&mut buf);
+        evs.encode(&mut buf);
+
+        assert_eq!(buf.len(), frag_status.encoded_length() + evs.encoded_length());
+        assert_eq!(&buf[..7], &[241, 7, 1, 0, 0, 0, 1]);
+        assert_eq!(&buf[7..], &[242, 11, 26, 0, 0, 0x48, 0xf9, 7, b'a', b'b', b'c']);
+
+        let attrs = Attribute::decode(&buf).unwrap();
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].attr_type(), 241);
+        assert_eq!(attrs[0].extended_type(), Some(1));
+        assert_eq!(attrs[0].value(), &[0, 0, 0, 1]);
+        assert_eq!(attrs[1].extended_type(), Some(EXTENDED_VENDOR_SPECIFIC_TYPE));
+        assert_eq!(attrs[1].vendor().unwrap().id(), 18681);
+        assert_eq!(attrs[1].vendor().unwrap().vendor_type(), 7);
+        assert_eq!(attrs[1].value(), b"abc");
+
+        let mut attr = Attribute::new_extended(241, 1, None);
+        assert!(attr.set_value(&[0; 252]).is_ok());
+        assert!(attr.set_value(&[0; 253]).is_err());
+
+        assert!(Attribute::decode(&[241, 2]).is_err());
+        assert!(Attribute::decode(&[241, 6, 26, 0, 0, 0x48]).is_err());
+    }
+
+    #[test]
+    fn long_extended_test() {
+        let value: Vec<u8> = (0..600).map(|i| i as u8).collect();
+        let vendor = Some(Vendor::new(18681, 1));
+        let mut attr = Attribute::new_extended(245, EXTENDED_VENDOR_SPECIFIC_TYPE, vendor);
+        attr.set_value(&value).unwrap();
+
+        let mut user_name = Attribute::new(1, None);
+        user_name.set_value(b"user").unwrap();
+
+        let mut buf: Vec<u8> = Vec::new();
+        attr.encode(&mut buf);
+        user_name.encode(&mut buf);
+
+        // 605 octets of data are split into fragments of 251, 251 and 103
+        assert_eq!(attr.encoded_length(), 3 * 4 + 605);
+        assert_eq!(buf.len(), attr.encoded_length() + user_name.encoded_length());
+        assert_eq!(&buf[..9], &[245, 255, 26, 0x80, 0, 0, 0x48, 0xf9, 1]);
+        assert_eq!(&buf[255..259], &[245, 255, 26, 0x80]);
+        assert_eq!(&buf[510..514], &[245, 107, 26, 0]);
+
+        let attrs = Attribute::decode(&buf).unwrap();
+        assert_eq!(attrs.len(), 2);
+        assert!(attrs[0].is_long_extended());
+        assert_eq!(attrs[0].vendor().unwrap().id(), 18681);
+        assert_eq!(attrs[0].value(), value.as_slice());
+        assert_eq!(attrs[1].value(), b"user");
+
+        // an empty attribute takes a single fragment
+        let empty = Attribute::new_extended(246, 1, None);
+        let mut buf: Vec<u8> = Vec::new();
+        empty.encode(&mut buf);
+        assert_eq!(buf, vec![246, 4, 1, 0]);
+        assert_eq!(empty.encoded_length(), 4);
+        assert_eq!(Attribute::decode(&buf).unwrap()[0].value(), b"");
+
+        // fragments must be terminated and have the same types
+        assert!(Attribute::decode(&[245, 5, 1, 0x80, 1]).is_err());
+        assert!(Attribute::decode(&[245, 5, 1, 0x80, 1, 245, 5, 2, 0, 2]).is_err());
+        assert!(Attribute::decode(&[245, 5, 1, 0x80, 1, 1, 3, b'a']).is_err());
+    }
+
+    #[test]
+    fn tag_test() {
+        let mut attr = Attribute::new(VENDOR_SPECIFIC_TYPE, Some(Vendor::new(9, 1)));
+        attr.set_tag(Some(MAX_TAG)).unwrap();
+        assert_eq!(attr.max_value_length(), 246);
+        assert!(attr.set_value(&[b'a'; 247]).is_err());
+        attr.set_value(b"a").unwrap();
+
+        let mut buf: Vec<u8> = Vec::new();
+        attr.encode(&mut buf);
+        assert_eq!(buf, vec![26, 10, 0, 0, 0, 9, 1, 4, MAX_TAG, b'a']);
+        assert_eq!(attr.encoded_length(), buf.len());
+
+        // decoded attributes carry the tag within their values
+        let decoded = Attribute::decode(&buf).unwrap();
+        assert_eq!(decoded[0].tag(), None);
+        assert_eq!(decoded[0].value(), &[MAX_TAG, b'a']);
+
+        let mut long = Attribute::new(1, None);
+        long.set_value(&[b'a'; 253]).unwrap();
+        assert!(long.set_tag(Some(1)).is_err());
+        assert_eq!(long.tag(), None);
+        assert!(attr.set_tag(Some(MAX_TAG + 1)).is_err());
+        assert_eq!(attr.set_tag(None).unwrap().encoded_length(), 9);
+    }
+
+    #[test]
+    fn vendor_format_test() {
+        assert_eq!("1,1".parse::<VendorFormat>().unwrap(), VendorFormat::default());
+        assert_eq!("2,2".parse::<VendorFormat>().unwrap().to_string(), "2,2");
+        assert!("1,1,c".parse::<VendorFormat>().unwrap().has_continuation());
+        assert_eq!("4,0".parse::<VendorFormat>().unwrap().max_vendor_type(), u32::MAX);
+        assert!("3,1".parse::<VendorFormat>().is_err());
+        assert!("2,2,c".parse::<VendorFormat>().is_err());
+        assert!("1".parse::<VendorFormat>().is_err());
+
+        let mut formats = VendorFormats::new();
+        formats.insert(9, "2,2".parse().unwrap());
+        formats.insert(429, "4,0".parse().unwrap());
+
+        let mut wide = Attribute::new(
+            VENDOR_SPECIFIC_TYPE,
+            Some(Vendor::with_format(9, 300, formats[&9])),
+        );
+        wide.set_value(b"abc").unwrap();
+        let mut usr = Attribute::new(
+            VENDOR_SPECIFIC_TYPE,
+            Some(Vendor::with_format(429, 0x9801, formats[&429])),
+        );
+        usr.set_value(b"de").unwrap();
+
+        let mut buf: Vec<u8> = Vec::new();
+        wide.encode(&mut buf);
+        usr.encode(&mut buf);
+        assert_eq!(&buf[..13], &[26, 13, 0, 0, 0, 9, 1, 44, 0, 7, b'a', b'b', b'c']);
+        assert_eq!(&buf[13..], &[26, 12, 0, 0, 1, 173, 0, 0, 0x98, 1, b'd', b'e']);
+        assert_eq!(buf.len(), wide.encoded_length() + usr.encoded_length());
+
+        let attrs = Attribute::decode_with_formats(&buf, &formats).unwrap();
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].vendor().unwrap().vendor_type(), 300);
+        assert_eq!(attrs[0].value(), b"abc");
+        assert_eq!(attrs[1].vendor().unwrap().vendor_type(), 0x9801);
+        assert_eq!(attrs[1].value(), b"de");
+
+        // the vendor length must cover its header
+        assert!(Attribute::decode_with_formats(&[26, 9, 0, 0, 0, 9, 1, 44, 0], &formats).is_err());
+    }
+
+    #[test]
+    fn vendor_continuation_test() {
+        let format: VendorFormat = "1,1,c".parse().unwrap();
+        let mut formats = VendorFormats::new();
+        formats.insert(24757, format);
+
+        let value: Vec<u8> = (0..400).map(|i| i as u8).collect();
+        let vendor = Some(Vendor::with_format(24757, 1, format));
+        let mut attr = Attribute::new(VENDOR_SPECIFIC_TYPE, vendor);
+        attr.set_value(&value).unwrap();
+
+        let mut buf: Vec<u8> = Vec::new();
+        attr.encode(&mut buf);
+
+        // 400 octets are split into pieces of 246 and 154 octets
+        assert_eq!(&buf[..9], &[26, 255, 0, 0, 0x60, 0xb5, 1, 249, 0x80]);
+        assert_eq!(&buf[255..264], &[26, 163, 0, 0, 0x60, 0xb5, 1, 157, 0]);
+        assert_eq!(buf.len(), attr.encoded_length());
+
+        let attrs = Attribute::decode_with_formats(&buf, &formats).unwrap();
+        assert_eq!(attrs.len(), 1);
+        assert_eq!(attrs[0].value(), value.as_slice());
+
+        // without the format the pieces are separate attributes
+        assert_eq!(Attribute::decode(&buf).unwrap().len(), 2);
+    }
 }
@@ -1,13 +1,32 @@
 /// Defines the types and auxilary functions to load RADIUS dictionaries.
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::env;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-//use super::RADIUS_DICTIONARIES;
-use super::attribute::{Attribute, Vendor};
+use super::attribute::{
+    Attribute, Vendor, VendorFormat, VendorFormats, EXTENDED_TYPES, EXTENDED_VENDOR_SPECIFIC_TYPE,
+    LONG_EXTENDED_TYPES, MAX_TAG, VENDOR_SPECIFIC_TYPE,
+};
 use super::error::RadiusError;
+use super::packet::AUTHENTICATOR_LENGTH;
+use super::password::{
+    hide_tunnel_password, hide_user_password, unhide_tunnel_password, unhide_user_password,
+    SALT_LENGTH,
+};
+use super::random;
+use super::value::{AttributeValue, DataType};
+
+mod export;
+mod freeradius;
+mod lint;
+
+pub use export::{export_dictionaries, ExportFormat};
+pub use lint::lint_dictionaries;
 
 use walkdir::WalkDir;
 use yaml_rust::yaml::Yaml;
@@ -21,132 +40,1623 @@ const RADIUS_DICTIONARIES_DIR: &'static str = "/usr/share/radius";
 // whirl RADIUS dictionaries are located.
 const ENV_RADIUS_DICTIONARIES_DIR: &'static str = "RADIUS_DICTIONARIES_DIR";
 
+/// Definition of a RADIUS attribute loaded from a RADIUS dictionary.
+#[derive(Debug, Clone)]
+pub struct AttributeDefinition {
+    name: String,
+    id: u32,
+    extended: Option<u8>,
+    vendor: Option<u32>,
+    // format of the Vendor-Specific attributes of the vendor, resolved when
+    // the definition is added to a `Dictionary`
+    vendor_format: VendorFormat,
+    data_type: DataType,
+    flags: AttributeFlags,
+    source: Option<PathBuf>,
+    enum_type: Option<Arc<EnumType>>,
+}
+
+impl AttributeDefinition {
+    /// Creates new `AttributeDefinition`.
+    ///
+    /// For vendor specific attributes `id` is the type of the attribute
+    /// within the vendor space, which could be wider than one octet for
+    /// vendors with non-standard formats (see `VendorFormat`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::dictionary::AttributeDefinition;
+    /// use radius::value::DataType;
+    ///
+    /// let def = AttributeDefinition::new("3GPP-IMSI", 1, Some(10415), DataType::String);
+    /// ```
+    #[inline]
+    pub fn new(name: &str, id: u32, vendor: Option<u32>, data_type: DataType) -> AttributeDefinition {
+        AttributeDefinition {
+            name: name.to_string(),
+            id,
+            extended: None,
+            vendor,
+            vendor_format: VendorFormat::default(),
+            data_type,
+            flags: AttributeFlags::default(),
+            source: None,
+            enum_type: None,
+        }
+    }
+
+    /// Creates new `AttributeDefinition` of an extended attribute according
+    /// to RFC 6929.
+    ///
+    /// The `extended` is the type of the parent extended attribute (one of
+    /// `241`-`246`) and `id` is the `Extended-Type` of the attribute. For
+    /// vendor specific attributes `id` is the `EVS-Type` of the attribute
+    /// carried by the Extended-Vendor-Specific attribute of the parent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::dictionary::AttributeDefinition;
+    /// use radius::value::DataType;
+    ///
+    /// let def = AttributeDefinition::new_extended("Frag-Status", 241, 1, None, DataType::Integer);
+    /// assert_eq!(def.attribute().attr_type(), 241);
+    /// assert_eq!(def.attribute().extended_type(), Some(1));
+    /// ```
+    #[inline]
+    pub fn new_extended(
+        name: &str,
+        extended: u8,
+        id: u8,
+        vendor: Option<u32>,
+        data_type: DataType,
+    ) -> AttributeDefinition {
+        AttributeDefinition {
+            extended: Some(extended),
+            ..AttributeDefinition::new(name, id.into(), vendor, data_type)
+        }
+    }
+
+    /// Returns the name of the RADIUS attribute.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type of the RADIUS attribute or the type within the vendor
+    /// space for vendor specific attributes. For extended attributes it is
+    /// the `Extended-Type` or the `EVS-Type` of the attribute.
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the type of the parent attribute for extended attributes.
+    #[inline]
+    pub fn extended(&self) -> Option<u8> {
+        self.extended
+    }
+
+    /// Returns the vendor id for vendor specific attributes.
+    #[inline]
+    pub fn vendor(&self) -> Option<u32> {
+        self.vendor
+    }
+
+    /// Returns the data type of the RADIUS attribute as it is declared in the
+    /// dictionary.
+    #[inline]
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    /// Returns the type the data type of the RADIUS attribute is resolved
+    /// to.
+    ///
+    /// References to named types are resolved to the base types the named
+    /// types are defined with. References to named types that are not defined
+    /// by any of the loaded dictionaries are resolved to `integer`.
+    pub fn base_type(&self) -> DataType {
+        match &self.data_type {
+            DataType::Named(_) => match self.enum_type.as_ref().map(|t| t.data_type()) {
+                Some(DataType::Named(_)) | None => DataType::Integer,
+                Some(base) => base.clone(),
+            },
+            data_type => data_type.clone(),
+        }
+    }
+
+    /// Returns the named type of the RADIUS attribute if the attribute is
+    /// declared with a type from the `types` section of the dictionaries.
+    #[inline]
+    pub fn enum_type(&self) -> Option<&EnumType> {
+        self.enum_type.as_deref()
+    }
+
+    /// Returns the flags of the RADIUS attribute.
+    #[inline]
+    pub fn flags(&self) -> &AttributeFlags {
+        &self.flags
+    }
+
+    /// Sets the flags of the RADIUS attribute.
+    #[inline]
+    pub fn set_flags(&mut self, flags: AttributeFlags) -> &mut Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Returns the path to the dictionary the RADIUS attribute was loaded
+    /// from.
+    #[inline]
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    /// Sets the path to the dictionary the RADIUS attribute was loaded from.
+    #[inline]
+    pub fn set_source(&mut self, source: &Path) -> &mut Self {
+        self.source = Some(source.to_owned());
+        self
+    }
+
+    /// Creates new `Attribute` with empty value described by this definition.
+    pub fn attribute(&self) -> Attribute {
+        // According to RFC 2865 5.26:
+        //
+        // Type
+        //       26 for Vendor-Specific.
+        //
+        // and according to RFC 6929 2.4 Extended-Vendor-Specific attributes
+        // have the Extended-Type 26.
+        let mut attr = match (self.extended, self.vendor) {
+            (None, None) => Attribute::new(self.id as u8, None),
+            (None, Some(vendor)) => Attribute::new(
+                VENDOR_SPECIFIC_TYPE,
+                Some(Vendor::with_format(vendor, self.id, self.vendor_format)),
+            ),
+            (Some(extended), None) => Attribute::new_extended(extended, self.id as u8, None),
+            (Some(extended), Some(vendor)) => Attribute::new_extended(
+                extended,
+                EXTENDED_VENDOR_SPECIFIC_TYPE,
+                Some(Vendor::new(vendor, self.id)),
+            ),
+        };
+
+        attr.set_concat(self.flags.concat);
+        attr
+    }
+
+    // Returns the key of the attribute within the codes of a `Dictionary`.
+    fn code(&self) -> (Option<u8>, Option<u32>, u32) {
+        (self.extended, self.vendor, self.id)
+    }
+
+    /// Creates new `Attribute` described by this definition that carries the
+    /// given `value`.
+    ///
+    /// Attributes with the `has_tag` flag are created with the tag `0` where
+    /// RFC 2868 3.1 requires the `Tag` field: integer values are shortened to
+    /// `24` bits to make room for it and string values that could be mistaken
+    /// for a tag are preceded by it. The actual tag is set with
+    /// `Attribute::set_tag`.
+    pub fn encode(&self, value: &AttributeValue) -> Result<Attribute, RadiusError> {
+        let data = value.encode();
+        let mut attr = self.attribute();
+
+        let data = match (self.flags.has_tag, self.base_type(), data.as_slice()) {
+            (false, _, data) => data,
+            (true, DataType::Integer, [0, data @ ..]) => {
+                attr.set_tag(Some(0))?;
+                data
+            }
+            (true, DataType::Integer, _) => {
+                return Err(RadiusError::InvalidAttributeValue(
+                    DataType::Integer,
+                    value.to_string(),
+                ));
+            }
+            (true, _, data @ [first, ..]) if *first <= MAX_TAG => {
+                attr.set_tag(Some(0))?;
+                data
+            }
+            (true, _, data) => data,
+        };
+
+        attr.set_value(data)?;
+        Ok(attr)
+    }
+
+    /// Decodes the value of the given `attr` according to the data type of
+    /// this definition.
+    pub fn decode(&self, attr: &Attribute) -> Result<AttributeValue, RadiusError> {
+        AttributeValue::decode(&self.base_type(), &self.untag(attr).1)
+    }
+
+    /// Returns the tag of the given `attr` if the attribute is declared with
+    /// the `has_tag` flag.
+    ///
+    /// Received attributes carry the tag as the first octet of their value,
+    /// which is always present for integer values and the Tunnel-Password
+    /// and is recognized by its range `1..31` for string values (RFC 2868
+    /// 3.1). The tag `0` means that the attribute is not tagged.
+    #[inline]
+    pub fn tag(&self, attr: &Attribute) -> Option<u8> {
+        self.untag(attr).0
+    }
+
+    // Splits the value of the given `attr` into the tag and the value of the
+    // data type of this definition.
+    fn untag<'a>(&self, attr: &'a Attribute) -> (Option<u8>, Cow<'a, [u8]>) {
+        if !self.flags.has_tag {
+            return (attr.tag(), Cow::Borrowed(attr.value()));
+        }
+
+        let integer = self.base_type() == DataType::Integer;
+        let (tag, value) = match (attr.tag(), attr.value()) {
+            (Some(tag), value) => (Some(tag), value),
+            (None, [tag, value @ ..])
+                if integer
+                    || *tag <= MAX_TAG
+                    || self.flags.encrypt == Some(Encryption::TunnelPassword) =>
+            {
+                (Some(*tag), value)
+            }
+            (None, value) => (None, value),
+        };
+
+        let value = match integer {
+            true => Cow::Owned([&[0], value].concat()),
+            false => Cow::Borrowed(value),
+        };
+
+        (tag.filter(|tag| *tag != 0), value)
+    }
+
+    /// Creates new `Attribute` described by this definition that carries the
+    /// given `value` encrypted according to the `encrypt` flag of the
+    /// attribute.
+    ///
+    /// The `secret` is the shared secret of the RADIUS client and server and
+    /// the `authenticator` is the Request Authenticator of the packet the
+    /// attribute will be sent in. Attributes without the `encrypt` flag are
+    /// encoded as is. The Tunnel-Password is hidden with a random salt and
+    /// always has the `Tag` field, the tag is set with `Attribute::set_tag`.
+    pub fn encrypt(
+        &self,
+        value: &AttributeValue,
+        secret: &[u8],
+        authenticator: &[u8; AUTHENTICATOR_LENGTH],
+    ) -> Result<Attribute, RadiusError> {
+        let mut attr = self.attribute();
+
+        match self.flags.encrypt {
+            None => return self.encode(value),
+            Some(Encryption::UserPassword) => {
+                attr.set_value(&hide_user_password(&value.encode(), secret, authenticator)?)?;
+            }
+            Some(Encryption::TunnelPassword) => {
+                // the most significant bit of the salt must be set according
+                // to RFC 2868 3.5
+                let mut salt = [0; SALT_LENGTH];
+                random::fill(&mut salt);
+                salt[0] |= 0x80;
+
+                let data = hide_tunnel_password(&value.encode(), secret, authenticator, salt)?;
+                attr.set_tag(Some(0))?;
+                attr.set_value(&data)?;
+            }
+        }
+
+        Ok(attr)
+    }
+
+    /// Decodes the value of the given `attr` encrypted according to the
+    /// `encrypt` flag of the attribute.
+    ///
+    /// This is the reverse of `encrypt` and is mostly useful for RADIUS
+    /// servers.
+    pub fn decrypt(
+        &self,
+        attr: &Attribute,
+        secret: &[u8],
+        authenticator: &[u8; AUTHENTICATOR_LENGTH],
+    ) -> Result<AttributeValue, RadiusError> {
+        let data = match self.flags.encrypt {
+            None => return self.decode(attr),
+            Some(Encryption::UserPassword) => {
+                unhide_user_password(&self.untag(attr).1, secret, authenticator)?
+            }
+            Some(Encryption::TunnelPassword) => {
+                unhide_tunnel_password(&self.untag(attr).1, secret, authenticator)?
+            }
+        };
+
+        AttributeValue::decode(&self.base_type(), &data)
+    }
+
+    /// Parses the textual representation of a value of the attribute.
+    ///
+    /// For attributes of named types the names of values from the `types`
+    /// section of the dictionaries are accepted in addition to numbers.
+    pub fn parse_value(&self, s: &str) -> Result<AttributeValue, RadiusError> {
+        let base = self.base_type();
+
+        if let Some(value) = self.enum_type().and_then(|enum_type| enum_type.value(s)) {
+            return AttributeValue::from_integer(&base, value as i128);
+        }
+
+        AttributeValue::parse(&base, s)
+    }
+
+    /// Converts the given integer to a value of the attribute.
+    pub fn integer_value(&self, n: i128) -> Result<AttributeValue, RadiusError> {
+        AttributeValue::from_integer(&self.base_type(), n)
+    }
+
+    /// Returns the textual representation of the given `value` of the
+    /// attribute.
+    ///
+    /// Values of attributes of named types are rendered by their names if the
+    /// name is defined within the `types` section of the dictionaries.
+    pub fn format_value(&self, value: &AttributeValue) -> String {
+        if let Some(enum_type) = self.enum_type() {
+            let n = match value {
+                AttributeValue::Integer(n) => Some(*n as u64),
+                AttributeValue::Integer64(n) => Some(*n),
+                AttributeValue::Byte(n) => Some(*n as u64),
+                _ => None,
+            };
+
+            if let Some(value_name) = n.and_then(|n| enum_type.value_name(n)) {
+                return value_name.to_string();
+            }
+        }
+
+        value.to_string()
+    }
+}
+
+/// Encryption of a RADIUS attribute value declared by the `encrypt` flag
+/// within RADIUS dictionaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encryption {
+    /// User-Password hiding described in RFC 2865 5.2. Declared as
+    /// `encrypt: true` or `encrypt: 1`.
+    UserPassword,
+    /// Tunnel-Password hiding with a salt described in RFC 2868 3.5.
+    /// Declared as `encrypt: 2`.
+    TunnelPassword,
+}
+
+/// Flags of a RADIUS attribute declared by the `flags` key of an attribute
+/// within RADIUS dictionaries.
+#[derive(Debug, Clone, Default)]
+pub struct AttributeFlags {
+    /// Encryption of the attribute value.
+    pub encrypt: Option<Encryption>,
+    /// Whether values longer than one attribute could carry are split into
+    /// several consecutive attributes, declared as `concat: true`.
+    pub concat: bool,
+    /// Whether the value of the attribute is preceded by the `Tag` field of
+    /// RFC 2868 3.1, declared as `has_tag: true`.
+    pub has_tag: bool,
+}
+
+/// Named type defined within the `types` section of RADIUS dictionaries.
+///
+/// Every named type has a base type and an enumeration of named values.
+#[derive(Debug, Clone)]
+pub struct EnumType {
+    name: String,
+    data_type: DataType,
+    values: Vec<(String, u64)>,
+    by_name: HashMap<String, u64>,
+    by_value: HashMap<u64, String>,
+}
+
+impl EnumType {
+    /// Creates new `EnumType` without values.
+    #[inline]
+    pub fn new(name: &str, data_type: DataType) -> EnumType {
+        EnumType {
+            name: name.to_string(),
+            data_type,
+            values: Vec::new(),
+            by_name: HashMap::new(),
+            by_value: HashMap::new(),
+        }
+    }
+
+    /// Returns the name of the type.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the base type of the type.
+    #[inline]
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    /// Returns all the values of the type in the order they were defined.
+    #[inline]
+    pub fn values(&self) -> &[(String, u64)] {
+        &self.values
+    }
+
+    /// Adds new named value to the type.
+    ///
+    /// If the name or the value is already defined, the first definition is
+    /// used for lookups.
+    pub fn insert(&mut self, name: &str, value: u64) {
+        self.values.push((name.to_string(), value));
+        self.by_name.entry(name.to_string()).or_insert(value);
+        self.by_value.entry(value).or_insert_with(|| name.to_string());
+    }
+
+    /// Returns the value with the given `name`.
+    #[inline]
+    pub fn value(&self, name: &str) -> Option<u64> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Returns the name of the given `value`.
+    #[inline]
+    pub fn value_name(&self, value: u64) -> Option<&str> {
+        self.by_value.get(&value).map(|s| s.as_str())
+    }
+}
+
+/// RADIUS attributes and named types loaded from a set of RADIUS
+/// dictionaries.
+///
+/// Attributes could be looked up by their names and by their codes, i.e.
+/// the vendor id and the type of the attribute within the vendor space (or
+/// the standard space for attributes without vendor). Extended attributes
+/// are looked up within the space of their parent attribute. A loaded `Dictionary`
+/// is never changed, so it could be shared between threads with `Arc`
+/// without any locking.
+///
+/// # Examples
+///
+/// ```
+/// use radius::dictionary::{AttributeDefinition, Dictionary};
+/// use radius::value::DataType;
+///
+/// let mut dictionary = Dictionary::new();
+/// dictionary.insert(AttributeDefinition::new("3GPP-IMSI", 1, Some(10415), DataType::String));
+///
+/// assert_eq!(dictionary.attribute("3GPP-IMSI").unwrap().id(), 1);
+/// assert_eq!(
+///     dictionary.attribute_by_code(Some(10415), 1).unwrap().name(),
+///     "3GPP-IMSI"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    attributes: HashMap<String, AttributeDefinition>,
+    codes: HashMap<(Option<u8>, Option<u32>, u32), String>,
+    types: HashMap<String, Arc<EnumType>>,
+    vendors: HashMap<u32, String>,
+    formats: VendorFormats,
+}
+
+impl Dictionary {
+    /// Creates new empty `Dictionary`.
+    pub fn new() -> Dictionary {
+        Dictionary::default()
+    }
+
+    /// Adds the given attribute `definition` to the dictionary.
+    ///
+    /// A definition with the same name replaces the previous one. If several
+    /// attributes share the same code, the lookup by code returns the one
+    /// added last.
+    pub fn insert(&mut self, mut definition: AttributeDefinition) -> &mut Self {
+        if let DataType::Named(name) = &definition.data_type {
+            definition.enum_type = self.types.get(name).cloned();
+        }
+        if let Some(vendor) = definition.vendor {
+            definition.vendor_format = self.vendor_format(vendor);
+        }
+
+        if let Some(previous) = self.attributes.get(&definition.name) {
+            let code = previous.code();
+            if self.codes.get(&code) == Some(&definition.name) {
+                self.codes.remove(&code);
+            }
+        }
+
+        self.codes.insert(definition.code(), definition.name.clone());
+        self.attributes.insert(definition.name.clone(), definition);
+        self
+    }
+
+    /// Adds the given named type to the dictionary.
+    ///
+    /// Types with the same name are merged, so vendor dictionaries could
+    /// extend standard types with own values. The base type of the first
+    /// definition is kept.
+    pub fn insert_type(&mut self, enum_type: EnumType) -> &mut Self {
+        let name = enum_type.name().to_string();
+        let merged = match self.types.remove(&name) {
+            Some(mut existing) => {
+                let merged = Arc::make_mut(&mut existing);
+                for (value_name, value) in enum_type.values() {
+                    merged.insert(value_name, *value);
+                }
+                existing
+            }
+            None => Arc::new(enum_type),
+        };
+
+        for definition in self.attributes.values_mut() {
+            if definition.data_type == DataType::Named(name.clone()) {
+                definition.enum_type = Some(merged.clone());
+            }
+        }
+
+        self.types.insert(name, merged);
+        self
+    }
+
+    /// Returns the definition of the attribute with the given `name`.
+    #[inline]
+    pub fn attribute(&self, name: &str) -> Option<&AttributeDefinition> {
+        self.attributes.get(name)
+    }
+
+    /// Returns the definition of the attribute with the given `id` within
+    /// the space of the given `vendor`.
+    #[inline]
+    pub fn attribute_by_code(&self, vendor: Option<u32>, id: u32) -> Option<&AttributeDefinition> {
+        self.codes
+            .get(&(None, vendor, id))
+            .and_then(|name| self.attributes.get(name))
+    }
+
+    /// Returns the definition of the extended attribute with the given `id`
+    /// within the space of the parent attribute `extended` and the given
+    /// `vendor` (see `AttributeDefinition::new_extended`).
+    #[inline]
+    pub fn extended_attribute_by_code(
+        &self,
+        extended: u8,
+        vendor: Option<u32>,
+        id: u32,
+    ) -> Option<&AttributeDefinition> {
+        self.codes
+            .get(&(Some(extended), vendor, id))
+            .and_then(|name| self.attributes.get(name))
+    }
+
+    /// Returns the definition that describes the given decoded `attr`.
+    pub fn definition_of(&self, attr: &Attribute) -> Option<&AttributeDefinition> {
+        match (attr.extended_type(), attr.vendor()) {
+            (None, Some(vendor)) => self.attribute_by_code(Some(vendor.id()), vendor.vendor_type()),
+            (None, None) => self.attribute_by_code(None, attr.attr_type().into()),
+            (Some(_), Some(vendor)) => self.extended_attribute_by_code(
+                attr.attr_type(),
+                Some(vendor.id()),
+                vendor.vendor_type(),
+            ),
+            (Some(extended_type), None) => {
+                self.extended_attribute_by_code(attr.attr_type(), None, extended_type.into())
+            }
+        }
+    }
+
+    /// Joins the values of consecutive attributes of the same code that are
+    /// declared with the `concat` flag, so values split into several
+    /// attributes by `Attribute::encode` are decoded as a single `Attribute`.
+    /// Other attributes are returned as is.
+    pub fn join_attributes(&self, attrs: &[Attribute]) -> Vec<Attribute> {
+        let mut joined: Vec<Attribute> = Vec::with_capacity(attrs.len());
+
+        for attr in attrs {
+            let concat = self.definition_of(attr).is_some_and(|d| d.flags().concat);
+            if let Some(last) = joined.last_mut().filter(|last| concat && same_code(last, attr)) {
+                let mut value = last.value().to_vec();
+                value.extend_from_slice(attr.value());
+                if last.set_concat(true).set_value(&value).is_ok() {
+                    continue;
+                }
+            }
+
+            joined.push(attr.clone());
+        }
+
+        joined
+    }
+
+    /// Returns all the attribute definitions in arbitrary order.
+    pub fn attributes(&self) -> impl Iterator<Item = &AttributeDefinition> {
+        self.attributes.values()
+    }
+
+    /// Returns the named type with the given `name`.
+    #[inline]
+    pub fn enum_type(&self, name: &str) -> Option<&EnumType> {
+        self.types.get(name).map(|t| t.as_ref())
+    }
+
+    /// Returns all the named types in arbitrary order.
+    pub fn types(&self) -> impl Iterator<Item = &EnumType> {
+        self.types.values().map(|t| t.as_ref())
+    }
+
+    /// Sets the name of the vendor with the given `id`.
+    #[inline]
+    pub fn insert_vendor(&mut self, id: u32, name: &str) -> &mut Self {
+        self.vendors.insert(id, name.to_string());
+        self
+    }
+
+    /// Returns the name of the vendor with the given `id` if it is declared
+    /// by the dictionaries.
+    #[inline]
+    pub fn vendor_name(&self, id: u32) -> Option<&str> {
+        self.vendors.get(&id).map(|name| name.as_str())
+    }
+
+    /// Sets the format of the Vendor-Specific attributes of the vendor with
+    /// the given `id`.
+    ///
+    /// Attributes of the vendor are encoded according to the format, the
+    /// format of the attributes that are already added is changed as well.
+    pub fn insert_vendor_format(&mut self, id: u32, format: VendorFormat) -> &mut Self {
+        for definition in self.attributes.values_mut() {
+            if definition.vendor == Some(id) {
+                definition.vendor_format = format;
+            }
+        }
+
+        self.formats.insert(id, format);
+        self
+    }
+
+    /// Returns the format of the Vendor-Specific attributes of the vendor
+    /// with the given `id`, which is the format recommended by RFC 2865 5.26
+    /// unless the dictionaries declare another one.
+    #[inline]
+    pub fn vendor_format(&self, id: u32) -> VendorFormat {
+        self.formats.get(&id).copied().unwrap_or_default()
+    }
+
+    /// Returns the formats of the Vendor-Specific attributes declared by the
+    /// dictionaries, the replies should be decoded with (see
+    /// `Packet::decode_with_formats`).
+    #[inline]
+    pub fn vendor_formats(&self) -> &VendorFormats {
+        &self.formats
+    }
+
+    /// Returns the number of attribute definitions.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    /// Returns true if the dictionary has no attribute definitions.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    /// Resolves references to named types within the given `data_type` to the
+    /// base types the named types are defined with.
+    ///
+    /// References to named types that are not defined by the dictionary are
+    /// resolved to `integer`.
+    pub fn base_type(&self, data_type: &DataType) -> DataType {
+        match data_type {
+            DataType::Named(name) => match self.enum_type(name).map(|t| t.data_type()) {
+                Some(DataType::Named(_)) | None => DataType::Integer,
+                Some(base) => base.clone(),
+            },
+            DataType::Array(element) => DataType::Array(Box::new(self.base_type(element))),
+            _ => data_type.clone(),
+        }
+    }
+}
+
 /// Set RADIUS of dictionaries to load.
 pub enum DictionarySet {
     /// Load all existing RADIUS dictionaries.
     All,
-    /// Load RADIUS dictionaries from the given `vector`.
+    /// Load RADIUS dictionaries with the given names.
+    ///
+    /// A dictionary is matched by its file name (`dictionary.3gpp.yaml`),
+    /// by its file name without the `dictionary.` prefix and the `.yaml`
+    /// extension (`3gpp`) or by the vendor id it defines (`10415`). Names are
+    /// compared case-insensitively. FreeRADIUS dictionaries are matched by
+    /// their names only.
     Set(Vec<String>),
 }
 
+impl DictionarySet {
+    // Checks whether the dictionary with the given `path` and `vendor` id
+    // is selected by the set and returns the name it was selected by.
+    fn matches(&self, path: &Path, vendor: Option<i64>) -> Option<&str> {
+        let names = match self {
+            DictionarySet::All => return Some(""),
+            DictionarySet::Set(names) => names,
+        };
+
+        let file_name = path.file_name().and_then(OsStr::to_str).unwrap_or("");
+        let short_name = file_name.strip_prefix("dictionary.").unwrap_or(file_name);
+        let short_name = short_name.strip_suffix(".yaml").unwrap_or(short_name);
+
+        names
+            .iter()
+            .find(|name| {
+                name.eq_ignore_ascii_case(file_name)
+                    || name.eq_ignore_ascii_case(short_name)
+                    || vendor.is_some_and(|v| v.to_string() == name.as_str())
+            })
+            .map(|name| name.as_str())
+    }
+}
+
 /// Load set of RADIUS dictionaries specified by the given
 /// dictionary `set`.
 ///
-/// The dictionaries will be loaded from:
+/// The dictionaries will be looked up in:
 ///
-///   * The `path` which points to directory with RADIUS dictionaries.
-///   * If the `path` is `None` the `RADIUS_DICTIONARIES_DIR` environment
-///     variable will be checked.
+///   * The `paths` which point to directories with RADIUS dictionaries.
+///   * If the `paths` are empty the `RADIUS_DICTIONARIES_DIR` environment
+///     variable will be checked. It may contain several directories
+///     separated by colons.
 ///   * `/usr/share/radius` directory will be used if both previous souces
 ///     are not set.
 ///
+/// Directories are searched in the given order and a dictionary file found
+/// in one directory hides the files with the same name in all the following
+/// ones, so local directories could override the shipped dictionaries.
+///
+/// The format of a dictionary is detected by its file name: `*.yaml` files
+/// are whirl YAML dictionaries and files named `dictionary` or
+/// `dictionary.*` are FreeRADIUS dictionaries, so the paths could point to
+/// an existing `/usr/share/freeradius` tree. Other files are ignored.
+///
 /// If loading of RADIUS dictionaries will be succesfully executed the
-/// hashmap with mapping of RADIUS attribute names to `radius::Attribute`
-/// will be returned. Otherwise one of `RadiusError` value.
-pub fn load_dictionaries(
-    _set: DictionarySet,
-    path: Option<PathBuf>,
+/// returned `Dictionary` will contain all the RADIUS attributes and named
+/// types of the loaded dictionaries. Otherwise one of `RadiusError` value
+/// will be returned. If some of the dictionaries requested by
+/// `DictionarySet::Set` are not found, `RadiusError::DictionaryNotFound` is
+/// returned.
+pub fn load_dictionaries(set: DictionarySet, paths: &[PathBuf]) -> Result<Dictionary, RadiusError> {
+    load(&set, paths, None)
+}
+
+/// Loads the set of RADIUS dictionaries of the given `format` only.
+///
+/// Unlike `load_dictionaries` the format is not detected by the names of
+/// files: with `DictionaryFormat::FreeRadius` every file named `dictionary`
+/// or `dictionary.*` is parsed as FreeRADIUS dictionary and with
+/// `DictionaryFormat::Yaml` only `*.yaml` files are loaded.
+pub fn load_dictionaries_as(
+    set: DictionarySet,
+    paths: &[PathBuf],
+    format: DictionaryFormat,
+) -> Result<Dictionary, RadiusError> {
+    load(&set, paths, Some(format))
+}
+
+fn load(
+    set: &DictionarySet,
+    paths: &[PathBuf],
+    format: Option<DictionaryFormat>,
+) -> Result<Dictionary, RadiusError> {
+    let mut dict = Dictionary::new();
+    let mut parser = freeradius::Parser::new();
+
+    for file in dictionary_files(set, paths, format)? {
+        match file {
+            DictionaryFile::Yaml(dictionary, document) => {
+                load_dictionary(&mut dict, &dictionary, &document)?
+            }
+            DictionaryFile::FreeRadius(dictionary) => parser.parse(&mut dict, &dictionary)?,
+        }
+    }
+
+    parser.finish(&mut dict)?;
+    Ok(dict)
+}
+
+/// Format of RADIUS dictionaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DictionaryFormat {
+    /// whirl YAML dictionaries, files with the `.yaml` extension.
+    Yaml,
+    /// FreeRADIUS dictionaries, files named `dictionary` or `dictionary.*`
+    /// like `dictionary.rfc2865`.
+    FreeRadius,
+}
+
+impl DictionaryFormat {
+    // Detects the format of the given dictionary file by its name if the
+    // `format` is not forced.
+    fn of(path: &Path, format: Option<DictionaryFormat>) -> Option<DictionaryFormat> {
+        let file_name = path.file_name().and_then(OsStr::to_str).unwrap_or("");
+        let is_yaml = path.extension().and_then(OsStr::to_str) == Some("yaml");
+        let is_freeradius = file_name == "dictionary" || file_name.starts_with("dictionary.");
+
+        match format {
+            Some(DictionaryFormat::Yaml) if is_yaml => Some(DictionaryFormat::Yaml),
+            Some(DictionaryFormat::FreeRadius) if is_freeradius => {
+                Some(DictionaryFormat::FreeRadius)
+            }
+            Some(_) => None,
+            None if is_yaml => Some(DictionaryFormat::Yaml),
+            None if is_freeradius => Some(DictionaryFormat::FreeRadius),
+            None => None,
+        }
+    }
+}
+
+// Dictionary selected to be loaded.
+enum DictionaryFile {
+    Yaml(PathBuf, Yaml),
+    FreeRadius(PathBuf),
+}
+
+// Looks up the dictionaries selected by the given `set` within the `paths`
+// and returns them in the order they should be loaded.
+fn dictionary_files(
+    set: &DictionarySet,
+    paths: &[PathBuf],
+    format: Option<DictionaryFormat>,
+) -> Result<Vec<DictionaryFile>, RadiusError> {
+    let mut files: Vec<DictionaryFile> = Vec::new();
+    let mut found: Vec<&str> = Vec::new();
+    let mut loaded: Vec<OsString> = Vec::new();
+
+    for dicts_dir in dictionaries_path(paths) {
+        if !dicts_dir.exists() {
+            return Err(RadiusError::InvalidDictionaryDir(dicts_dir));
+        }
+
+        if !dicts_dir.is_dir() {
+            return Err(RadiusError::InvalidDictionaryDir(dicts_dir));
+        }
+
+        for entry in WalkDir::new(&dicts_dir)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            let dictionary: &Path = entry.path();
+            if !dictionary.is_file() {
+                continue;
+            }
+
+            // the dictionary is hidden by the one from a previous directory
+            let file_name = entry.file_name().to_owned();
+            if loaded.contains(&file_name) {
+                continue;
+            }
+
+            // vendor ids are known before parsing for YAML dictionaries only
+            let (file, vendor) = match DictionaryFormat::of(dictionary, format) {
+                Some(DictionaryFormat::Yaml) => {
+                    let document = read_dictionary(dictionary)?;
+                    let vendor = document["vendor"].as_i64();
+                    (DictionaryFile::Yaml(dictionary.to_owned(), document), vendor)
+                }
+                Some(DictionaryFormat::FreeRadius) => {
+                    (DictionaryFile::FreeRadius(dictionary.to_owned()), None)
+                }
+                None => continue,
+            };
+
+            let name = match set.matches(dictionary, vendor) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            files.push(file);
+            found.push(name);
+            loaded.push(file_name);
+        }
+    }
+
+    if let DictionarySet::Set(names) = set {
+        if let Some(name) = names.iter().find(|name| !found.contains(&name.as_str())) {
+            return Err(RadiusError::DictionaryNotFound(name.to_string()));
+        }
+    }
+
+    Ok(files)
+}
+
+// Reads and parses the YAML document of the given RADIUS dictionary.
+fn read_dictionary(dictionary: &Path) -> Result<Yaml, RadiusError> {
+    // Try to open RADIUS dictionary
+    let fd = File::open(dictionary);
+    match fd {
+        Ok(_) => {}
+        Err(err) => {
+            return Err(RadiusError::InvalidDictionaryFile(
+                err,
+                dictionary.to_owned(),
+            ));
+        }
+    }
+
+    // read yaml data, dictionaries which are not UTF-8 are not readable
+    let mut dict = String::new();
+    if let Err(err) = fd.unwrap().read_to_string(&mut dict) {
+        return Err(RadiusError::InvalidDictionaryFile(
+            err,
+            dictionary.to_owned(),
+        ));
+    }
+
+    // load yaml
+    let yaml = YamlLoader::load_from_str(dict.as_ref());
+    match yaml {
+        Ok(_) => {}
+        Err(err) => {
+            return Err(RadiusError::InvalidYaml(err, dictionary.to_owned()));
+        }
+    };
+
+    // empty dictionaries are reported by `load_dictionary`, so the linter
+    // could report them along with other problems
+    Ok(yaml.unwrap().into_iter().next().unwrap_or(Yaml::Null))
+}
+
+// Loads the attributes and types defined by the given RADIUS `dictionary`
+// into the `dict`.
+fn load_dictionary(
+    dict: &mut Dictionary,
+    dictionary: &Path,
+    document: &Yaml,
 ) -> Result<(), RadiusError> {
-    let dicts_dir = dictionaries_path(path);
+    if let Yaml::Null = document {
+        return Err(RadiusError::DictionaryEmpty(dictionary.to_owned()));
+    }
 
-    if !dicts_dir.exists() {
-        return Err(RadiusError::InvalidDictionaryDir(dicts_dir));
+    // start to build RADIUS attributes map
+    let vendor = &document["vendor"];
+    if let (Yaml::Integer(id), Yaml::String(name)) = (vendor, &document["vendor-name"]) {
+        dict.insert_vendor(*id as u32, name);
     }
 
-    if !dicts_dir.is_dir() {
-        return Err(RadiusError::InvalidDictionaryDir(dicts_dir));
+    // the format of the Vendor-Specific attributes of the vendor
+    let format = match &document["format"] {
+        Yaml::BadValue => VendorFormat::default(),
+        Yaml::String(format) => format.parse::<VendorFormat>()?,
+        other => return Err(RadiusError::InvalidVendorFormat(format!("{:?}", other))),
+    };
+    if let Yaml::Integer(id) = vendor {
+        dict.insert_vendor_format(*id as u32, format);
     }
+    let attributes: &Vec<Yaml> = match document["attributes"].as_vec() {
+        Some(attributes) => attributes,
+        None => return Err(RadiusError::DictionaryMissedAttributes(dictionary.to_owned())),
+    };
 
-    for entry in WalkDir::new(dicts_dir).into_iter().filter_map(|e| e.ok()) {
-        let dictionary: &Path = entry.path();
-        if dictionary.is_file() && dictionary.extension().unwrap() == "yaml" {
-            // Try to open RADIUS dictionary
-            let fd = File::open(dictionary);
-            match fd {
-                Ok(_) => {}
-                Err(err) => {
-                    return Err(RadiusError::InvalidDictionaryFile(
-                        err,
+    // Go through the RADIUS attributes within yaml document
+    for attribute in attributes {
+        // read attribute name
+        let key = match &attribute["attribute"] {
+            Yaml::String(value) => value,
+            _ => {
+                return Err(RadiusError::DictionaryMissedAttrKey(dictionary.to_owned()));
+            }
+        };
+
+        // read attribute id, extended attributes are declared as `241.1`
+        let (extended, id) = match &attribute["id"] {
+            Yaml::BadValue => {
+                return Err(RadiusError::DictionaryMissedAttrId(
+                    dictionary.to_owned(),
+                    key.to_string(),
+                ));
+            }
+            id => match attribute_id(id) {
+                Some(id) => id,
+                None => {
+                    return Err(RadiusError::DictionaryInvalidAttrId(
                         dictionary.to_owned(),
+                        key.to_string(),
                     ));
                 }
+            },
+        };
+
+        // read attribute type
+        let data_type = match &attribute["type"] {
+            Yaml::String(value) => value.parse::<DataType>()?,
+            _ => {
+                return Err(RadiusError::DictionaryMissedAttrType(
+                    dictionary.to_owned(),
+                    key.to_string(),
+                ));
             }
+        };
 
-            // read yaml data
-            let mut dict = String::new();
-            fd.unwrap().read_to_string(&mut dict).unwrap();
+        // read possible vendor id
+        let v = match vendor {
+            Yaml::BadValue => None,
+            Yaml::Integer(vnd) => Some(*vnd as u32),
+            _ => {
+                return Err(RadiusError::DictionaryIvalidVendorId(dictionary.to_owned()));
+            }
+        };
 
-            // load yaml
-            let yaml = YamlLoader::load_from_str(dict.as_ref());
-            match yaml {
-                Ok(_) => {}
-                Err(err) => {
-                    return Err(RadiusError::InvalidYaml(err, dictionary.to_owned()));
+        // the id should fit into the type field of the attribute
+        let max_id = match (extended, v) {
+            (None, Some(_)) => format.max_vendor_type() as i64,
+            _ => u8::MAX as i64,
+        };
+        if !(0..=max_id).contains(&id) {
+            return Err(RadiusError::DictionaryAttrIdOutOfRange(
+                dictionary.to_owned(),
+                key.to_string(),
+                id,
+            ));
+        }
+
+        // read attribute flags
+        let mut flags = AttributeFlags::default();
+        match &attribute["flags"]["encrypt"] {
+            Yaml::BadValue | Yaml::Boolean(false) => {}
+            Yaml::Boolean(true) | Yaml::Integer(1) => {
+                flags.encrypt = Some(Encryption::UserPassword);
+            }
+            Yaml::Integer(2) => flags.encrypt = Some(Encryption::TunnelPassword),
+            _ => {
+                return Err(RadiusError::DictionaryInvalidAttrFlag(
+                    dictionary.to_owned(),
+                    key.to_string(),
+                ));
+            }
+        }
+
+        match &attribute["flags"]["concat"] {
+            Yaml::BadValue => {}
+            Yaml::Boolean(concat) => flags.concat = *concat,
+            _ => {
+                return Err(RadiusError::DictionaryInvalidAttrFlag(
+                    dictionary.to_owned(),
+                    key.to_string(),
+                ));
+            }
+        }
+
+        match &attribute["flags"]["has_tag"] {
+            Yaml::BadValue => {}
+            Yaml::Boolean(has_tag) => flags.has_tag = *has_tag,
+            _ => {
+                return Err(RadiusError::DictionaryInvalidAttrFlag(
+                    dictionary.to_owned(),
+                    key.to_string(),
+                ));
+            }
+        }
+
+        // insert new attribute definition into the dictionary
+        let mut definition = match extended {
+            Some(extended) => {
+                AttributeDefinition::new_extended(key, extended, id as u8, v, data_type)
+            }
+            None => AttributeDefinition::new(key, id as u32, v, data_type),
+        };
+        definition.set_flags(flags).set_source(dictionary);
+        dict.insert(definition);
+    }
+
+    // Go through the types within yaml document. Types with the same
+    // name from different dictionaries are merged, so vendor
+    // dictionaries could extend standard types with own values.
+    if let Yaml::Hash(types) = &document["types"] {
+        for (name, definition) in types {
+            let name = match name {
+                Yaml::String(name) => name,
+                _ => {
+                    return Err(RadiusError::DictionaryMissedAttrKey(dictionary.to_owned()));
                 }
             };
 
-            // start to build RADIUS attributes map
-            let document: &Yaml = &yaml.unwrap()[0];
-            let vendor = &document["vendor"];
-            let attributes: &Vec<Yaml> = &document["attributes"].as_vec().unwrap();
+            let data_type = match &definition["type"] {
+                Yaml::String(value) => value.parse::<DataType>()?,
+                _ => {
+                    return Err(RadiusError::DictionaryMissedAttrType(
+                        dictionary.to_owned(),
+                        name.to_string(),
+                    ));
+                }
+            };
 
-            // Go through the RADIUS attributes within yaml document
-            for attribute in attributes {
-                // read attribute name
-                let key = match &attribute["attribute"] {
-                    Yaml::String(value) => value,
-                    _ => {
-                        return Err(RadiusError::DictionaryMissedAttrKey(dictionary.to_owned()));
-                    }
-                };
+            let mut enum_type = EnumType::new(name, data_type);
+
+            let values = match &definition["values"] {
+                Yaml::Array(values) => values.as_slice(),
+                Yaml::BadValue => &[],
+                _ => {
+                    return Err(RadiusError::DictionaryInvalidTypeValue(
+                        dictionary.to_owned(),
+                        name.to_string(),
+                    ));
+                }
+            };
 
-                // read attribute id
-                let id = match &attribute["id"] {
-                    Yaml::Integer(value) => value,
+            for value in values {
+                match (&value["name"], &value["value"]) {
+                    (Yaml::String(value_name), Yaml::Integer(v)) if *v >= 0 => {
+                        enum_type.insert(value_name, *v as u64);
+                    }
                     _ => {
-                        return Err(RadiusError::DictionaryMissedAttrId(
+                        return Err(RadiusError::DictionaryInvalidTypeValue(
                             dictionary.to_owned(),
-                            key.to_string(),
+                            name.to_string(),
                         ));
                     }
-                };
-
-                // read possible vendor id
-                let v = match vendor {
-                    Yaml::BadValue => None,
-                    Yaml::Integer(vnd) => Some(Vendor::new(vnd.clone() as u32, id.clone() as u8)),
-                    _ => {
-                        return Err(RadiusError::DictionaryIvalidVendorId(dictionary.to_owned()));
-                    }
-                };
-
-                // According to RFC 2865 5.26:
-                //
-                // Type
-                //       26 for Vendor-Specific.
-                let attr_id = match v {
-                    None => id.clone() as u8,
-                    Some(_) => 26,
-                };
-
-                // insert new attribute into hash
-                let new_attr = Attribute::new(attr_id, v);
-                super::RADIUS_DICTIONARIES.lock().unwrap().insert(key.to_string(), new_attr);
+                }
             }
+
+            dict.insert_type(enum_type);
         }
     }
 
     Ok(())
 }
 
-fn dictionaries_path(path: Option<PathBuf>) -> PathBuf {
-    path.or_else(|| -> Option<PathBuf> {
-        env::var_os(ENV_RADIUS_DICTIONARIES_DIR)
-            .as_ref()
-            .and_then(|p: &OsString| -> Option<PathBuf> {
-                Some(AsRef::<Path>::as_ref(p).to_owned())
-            })
-            .or_else(|| -> Option<PathBuf> { Some(Path::new(RADIUS_DICTIONARIES_DIR).to_owned()) })
-    })
-    .unwrap()
+// Returns true if both attributes have the same type within the same vendor
+// and extended spaces.
+fn same_code(a: &Attribute, b: &Attribute) -> bool {
+    let code = |attr: &Attribute| {
+        let vendor = attr.vendor().map(|v| (v.id(), v.vendor_type()));
+        (attr.attr_type(), attr.extended_type(), vendor)
+    };
+
+    code(a) == code(b)
+}
+
+// Reads the `id` of an attribute which is either the type of the attribute
+// or, for extended attributes, the type of the parent attribute and the
+// `Extended-Type` (`EVS-Type` within vendor dictionaries) separated by a dot.
+// Returns the type of the parent attribute with the id.
+fn attribute_id(id: &Yaml) -> Option<(Option<u8>, i64)> {
+    let id = match id {
+        Yaml::Integer(id) => return Some((None, *id)),
+        // an unquoted `241.1` is read as a real number
+        Yaml::String(id) | Yaml::Real(id) => id,
+        _ => return None,
+    };
+
+    let (extended, id) = id.split_once('.')?;
+    let extended = extended.parse::<u8>().ok()?;
+    if !EXTENDED_TYPES.contains(&extended) && !LONG_EXTENDED_TYPES.contains(&extended) {
+        return None;
+    }
+
+    Some((Some(extended), id.parse::<i64>().ok()?))
+}
+
+fn dictionaries_path(paths: &[PathBuf]) -> Vec<PathBuf> {
+    if !paths.is_empty() {
+        return paths.to_vec();
+    }
+
+    match env::var_os(ENV_RADIUS_DICTIONARIES_DIR) {
+        Some(p) => env::split_paths(&p).filter(|p| !p.as_os_str().is_empty()).collect(),
+        None => vec![Path::new(RADIUS_DICTIONARIES_DIR).to_owned()],
+    }
+}
+
+#[cfg(test)]
+mod dictionary_tests {
+    use super::*;
+    use crate::packet::{Code, Packet};
+    use crate::test_dir::TempDir;
+
+    fn shipped_dictionaries() -> Vec<PathBuf> {
+        vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("../../share/radius")]
+    }
+
+    #[test]
+    fn load_dictionaries_test() {
+        let dict = load_dictionaries(DictionarySet::All, &shipped_dictionaries()).unwrap();
+        let rat_type = dict.attribute("3GPP-RAT-Type").unwrap();
+        let nas_ip = dict.attribute("NAS-IP-Address").unwrap();
+
+        assert_eq!(rat_type.vendor(), Some(10415));
+        assert_eq!(dict.vendor_name(10415), Some("3GPP"));
+        assert_eq!(rat_type.id(), 21);
+        assert!(rat_type.source().unwrap().ends_with("dictionary.3gpp.yaml"));
+        assert_eq!(rat_type.data_type(), &DataType::Named("rat-type".to_string()));
+        assert_eq!(rat_type.base_type(), DataType::Byte);
+        assert_eq!(dict.base_type(rat_type.data_type()), DataType::Byte);
+
+        let value = AttributeValue::from_integer(&rat_type.base_type(), 6).unwrap();
+        let attr = rat_type.encode(&value).unwrap();
+        assert_eq!(attr.value(), &[6]);
+        assert_eq!(rat_type.decode(&attr).unwrap(), value);
+        assert_eq!(dict.definition_of(&attr).unwrap().name(), "3GPP-RAT-Type");
+
+        let value = AttributeValue::parse(&nas_ip.base_type(), "127.0.0.1").unwrap();
+        let attr = nas_ip.encode(&value).unwrap();
+        assert_eq!(attr.value(), &[127, 0, 0, 1]);
+        assert_eq!(dict.definition_of(&attr).unwrap().name(), "NAS-IP-Address");
+        assert_eq!(dict.attribute_by_code(None, 4).unwrap().name(), "NAS-IP-Address");
+        assert!(dict.attribute_by_code(Some(1), 4).is_none());
+    }
+
+    #[test]
+    fn dictionary_test() {
+        let mut dict = Dictionary::new();
+        dict.insert(AttributeDefinition::new("Test-Type", 1, None, DataType::Named("test".to_string())));
+        assert!(dict.attribute("Test-Type").unwrap().enum_type().is_none());
+        assert_eq!(dict.attribute("Test-Type").unwrap().base_type(), DataType::Integer);
+
+        // types defined after the attributes are resolved as well
+        let mut test = EnumType::new("test", DataType::Byte);
+        test.insert("One", 1);
+        dict.insert_type(test);
+        let mut test = EnumType::new("test", DataType::Integer);
+        test.insert("Two", 2);
+        test.insert("Uno", 1);
+        dict.insert_type(test);
+
+        let definition = dict.attribute("Test-Type").unwrap();
+        assert_eq!(definition.base_type(), DataType::Byte);
+        assert_eq!(definition.parse_value("Two").unwrap(), AttributeValue::Byte(2));
+        assert_eq!(definition.format_value(&AttributeValue::Byte(1)), "One");
+        assert_eq!(dict.enum_type("test").unwrap().values().len(), 3);
+
+        // redefinition moves the attribute to the new code
+        dict.insert(AttributeDefinition::new("Test-Type", 2, Some(1), DataType::String));
+        assert!(dict.attribute_by_code(None, 1).is_none());
+        assert_eq!(dict.attribute_by_code(Some(1), 2).unwrap().name(), "Test-Type");
+        assert_eq!(dict.len(), 1);
+    }
+
+    #[test]
+    fn enum_types_test() {
+        let dict = load_dictionaries(DictionarySet::All, &shipped_dictionaries()).unwrap();
+        let service_type = dict.attribute("Service-Type").unwrap();
+        let rat_type = dict.attribute("3GPP-RAT-Type").unwrap();
+
+        assert_eq!(
+            service_type.parse_value("Framed-User").unwrap(),
+            AttributeValue::Integer(2)
+        );
+        assert_eq!(service_type.parse_value("2").unwrap(), AttributeValue::Integer(2));
+        assert!(service_type.parse_value("Unknown-User").is_err());
+
+        // service-type is extended by the travelping dictionary
+        assert_eq!(
+            service_type.parse_value("TP-CAPWAP-WTP").unwrap(),
+            AttributeValue::Integer(0x48f90001)
+        );
+        assert_eq!(
+            service_type.format_value(&AttributeValue::Integer(0x48f90002)),
+            "TP-CAPWAP-STA"
+        );
+        assert_eq!(service_type.format_value(&AttributeValue::Integer(100)), "100");
+
+        assert_eq!(rat_type.parse_value("EUTRAN").unwrap(), AttributeValue::Byte(6));
+        assert_eq!(rat_type.format_value(&AttributeValue::Byte(6)), "EUTRAN");
+    }
+
+    #[test]
+    fn user_password_test() {
+        let dict = load_dictionaries(DictionarySet::All, &shipped_dictionaries()).unwrap();
+        let user_name = dict.attribute("User-Name").unwrap();
+        let user_password = dict.attribute("User-Password").unwrap();
+
+        assert_eq!(user_name.flags().encrypt, None);
+        assert_eq!(user_password.flags().encrypt, Some(Encryption::UserPassword));
+
+        let authenticator = [0x42; AUTHENTICATOR_LENGTH];
+        let value = user_password.parse_value("password").unwrap();
+        let attr = user_password.encrypt(&value, b"secret", &authenticator).unwrap();
+
+        assert_eq!(attr.value().len(), 16);
+        assert_ne!(&attr.value()[..8], b"password");
+        assert_eq!(
+            user_password.decrypt(&attr, b"secret", &authenticator).unwrap(),
+            value
+        );
+
+        let value = user_name.parse_value("user").unwrap();
+        let attr = user_name.encrypt(&value, b"secret", &authenticator).unwrap();
+        assert_eq!(attr.value(), b"user");
+    }
+
+    #[test]
+    fn extended_attributes_test() {
+        let dir = TempDir::new(
+            "extended",
+            &[
+                (
+                    "dictionary.extended.yaml",
+                    "---\nvendor: 18681\nattributes:\n  - attribute: Whirl-Long\n    id: \"245.1\"\n    type: octets\n  - attribute: Whirl-Short\n    id: 241.1\n    type: string\n",
+                ),
+                (
+                    "dictionary.invalid.yaml",
+                    "---\nattributes:\n  - attribute: Whirl-Invalid\n    id: \"26.1\"\n    type: string\n",
+                ),
+            ],
+        );
+
+        let mut paths = dir.paths().to_vec();
+        paths.extend(shipped_dictionaries());
+        let set = DictionarySet::Set(vec!["extended".to_string(), "rfc6929".to_string()]);
+        let result = load_dictionaries(set, &paths);
+        let invalid = load_dictionaries(DictionarySet::Set(vec!["invalid".to_string()]), &paths);
+        let dict = result.unwrap();
+
+        assert!(matches!(
+            invalid,
+            Err(RadiusError::DictionaryInvalidAttrId(_, key)) if key == "Whirl-Invalid"
+        ));
+
+        let frag_status = dict.attribute("Frag-Status").unwrap();
+        assert_eq!(frag_status.extended(), Some(241));
+        let value = frag_status.parse_value("More-Data-Pending").unwrap();
+        let attr = frag_status.encode(&value).unwrap();
+        assert_eq!(attr.attr_type(), 241);
+        assert_eq!(attr.extended_type(), Some(1));
+        assert_eq!(dict.definition_of(&attr).unwrap().name(), "Frag-Status");
+        assert!(dict.attribute_by_code(None, 1).is_none());
+
+        // extended attributes of vendor dictionaries are Extended-Vendor-Specific
+        let whirl_long = dict.attribute("Whirl-Long").unwrap();
+        let value = AttributeValue::Octets(vec![0x42; 1000]);
+        let attr = whirl_long.encode(&value).unwrap();
+        let mut buf = Vec::new();
+        attr.encode(&mut buf);
+        assert_eq!(&buf[..4], &[245, 255, 26, 0x80]);
+
+        let decoded = Attribute::decode(&buf).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(dict.definition_of(&decoded[0]).unwrap().name(), "Whirl-Long");
+        assert_eq!(whirl_long.decode(&decoded[0]).unwrap(), value);
+
+        let whirl_short = dict.extended_attribute_by_code(241, Some(18681), 1).unwrap();
+        assert_eq!(whirl_short.name(), "Whirl-Short");
+        assert_eq!(whirl_short.attribute().max_value_length(), 247);
+    }
+
+    #[test]
+    fn tagged_attributes_test() {
+        let dict = load_dictionaries(DictionarySet::All, &shipped_dictionaries()).unwrap();
+        let tunnel_type = dict.attribute("Tunnel-Type").unwrap();
+        let server_endpoint = dict.attribute("Tunnel-Server-Endpoint").unwrap();
+        let tunnel_password = dict.attribute("Tunnel-Password").unwrap();
+        let authenticator = [0x42; AUTHENTICATOR_LENGTH];
+
+        assert!(tunnel_type.flags().has_tag);
+        assert_eq!(tunnel_password.flags().encrypt, Some(Encryption::TunnelPassword));
+
+        let vlan = tunnel_type.parse_value("VLAN").unwrap();
+        let mut tunnel_type_attr = tunnel_type.encode(&vlan).unwrap();
+        tunnel_type_attr.set_tag(Some(1)).unwrap();
+        assert!(tunnel_type.encode(&AttributeValue::Integer(0x1000000)).is_err());
+
+        let endpoint = AttributeValue::String("192.0.2.1".to_string());
+        let mut endpoint_attr = server_endpoint.encode(&endpoint).unwrap();
+        assert_eq!(endpoint_attr.tag(), None);
+        endpoint_attr.set_tag(Some(2)).unwrap();
+
+        let password = AttributeValue::String("tunnel".to_string());
+        let mut password_attr = tunnel_password.encrypt(&password, b"secret", &authenticator).unwrap();
+        password_attr.set_tag(Some(3)).unwrap();
+
+        let mut buf = Vec::new();
+        tunnel_type_attr.encode(&mut buf);
+        endpoint_attr.encode(&mut buf);
+        password_attr.encode(&mut buf);
+        assert_eq!(&buf[..9], &[64, 6, 1, 0, 0, 13, 67, 12, 2]);
+        // the Tag and Salt fields precede the hidden password
+        assert_eq!(&buf[18..21], &[69, 21, 3]);
+        assert!(buf[21] & 0x80 != 0);
+
+        let attrs = Attribute::decode(&buf).unwrap();
+        assert_eq!(tunnel_type.tag(&attrs[0]), Some(1));
+        assert_eq!(tunnel_type.decode(&attrs[0]).unwrap(), vlan);
+        assert_eq!(server_endpoint.tag(&attrs[1]), Some(2));
+        assert_eq!(server_endpoint.decode(&attrs[1]).unwrap(), endpoint);
+        assert_eq!(tunnel_password.tag(&attrs[2]), Some(3));
+        assert_eq!(
+            tunnel_password.decrypt(&attrs[2], b"secret", &authenticator).unwrap(),
+            password
+        );
+
+        // untagged string values are sent as is unless they look like a tag
+        let untagged = Attribute::decode(&[67, 4, b'a', b'b']).unwrap();
+        assert_eq!(server_endpoint.tag(&untagged[0]), None);
+        assert_eq!(
+            server_endpoint.decode(&untagged[0]).unwrap(),
+            AttributeValue::String("ab".to_string())
+        );
+        let low = AttributeValue::String("\u{1}b".to_string());
+        let attr = server_endpoint.encode(&low).unwrap();
+        let mut buf = Vec::new();
+        attr.encode(&mut buf);
+        assert_eq!(buf, vec![67, 5, 0, 1, b'b']);
+        let decoded = Attribute::decode(&buf).unwrap();
+        assert_eq!(server_endpoint.tag(&decoded[0]), None);
+        assert_eq!(server_endpoint.decode(&decoded[0]).unwrap(), low);
+    }
+
+    #[test]
+    fn vendor_format_test() {
+        let dir = TempDir::new(
+            "format",
+            &[
+                (
+                    "dictionary.wide.yaml",
+                    "---\nvendor: 9\nformat: \"2,2\"\nattributes:\n  - attribute: Whirl-Wide\n    id: 300\n    type: string\n",
+                ),
+                (
+                    "dictionary.range.yaml",
+                    "---\nvendor: 9\nattributes:\n  - attribute: Whirl-Wide\n    id: 300\n    type: string\n",
+                ),
+                (
+                    "dictionary.invalid.yaml",
+                    "---\nvendor: 9\nformat: \"3,1\"\nattributes: []\n",
+                ),
+            ],
+        );
+
+        let load = |name: &str| {
+            let set = DictionarySet::Set(vec![name.to_string()]);
+            load_dictionaries(set, dir.paths())
+        };
+        let wide = load("wide");
+        let range = load("range");
+        let invalid = load("invalid");
+        let dict = wide.unwrap();
+
+        assert!(matches!(
+            range,
+            Err(RadiusError::DictionaryAttrIdOutOfRange(_, key, 300)) if key == "Whirl-Wide"
+        ));
+        assert!(matches!(
+            invalid,
+            Err(RadiusError::InvalidVendorFormat(format)) if format == "3,1"
+        ));
+
+        let format = dict.vendor_format(9);
+        assert_eq!(format.type_width(), 2);
+        assert_eq!(format.length_width(), 2);
+        assert_eq!(dict.vendor_formats().len(), 1);
+
+        let whirl_wide = dict.attribute("Whirl-Wide").unwrap();
+        let value = AttributeValue::String("abc".to_string());
+        let mut buf = Vec::new();
+        whirl_wide.encode(&value).unwrap().encode(&mut buf);
+        assert_eq!(&buf[2..10], &[0, 0, 0, 9, 1, 44, 0, 7]);
+
+        // replies are decoded with the formats of the dictionaries
+        let decoded = Attribute::decode_with_formats(&buf, dict.vendor_formats()).unwrap();
+        assert_eq!(dict.definition_of(&decoded[0]).unwrap().name(), "Whirl-Wide");
+        assert_eq!(whirl_wide.decode(&decoded[0]).unwrap(), value);
+    }
+
+    #[test]
+    fn concat_attributes_test() {
+        let dict = load_dictionaries(DictionarySet::All, &shipped_dictionaries()).unwrap();
+        let eap_message = dict.attribute("EAP-Message").unwrap();
+        let gateway_config = dict.attribute("TP-Gateway-Config").unwrap();
+        let user_name = dict.attribute("User-Name").unwrap();
+
+        assert!(eap_message.flags().concat);
+        assert!(!user_name.flags().concat);
+
+        let config = AttributeValue::String("x".repeat(600));
+        let mut packet = Packet::new(Code::AccessRequest, 1);
+        packet.add_attribute(eap_message.encode(&AttributeValue::Octets(vec![1; 300])).unwrap());
+        packet.add_attribute(gateway_config.encode(&config).unwrap());
+        packet.add_attribute(user_name.encode(&AttributeValue::String("a".to_string())).unwrap());
+        packet.add_attribute(user_name.encode(&AttributeValue::String("b".to_string())).unwrap());
+        assert!(user_name.encode(&config).is_err());
+
+        let decoded = Packet::decode(&packet.encode().unwrap()).unwrap();
+        assert_eq!(decoded.attributes().len(), 7);
+
+        let attrs = dict.join_attributes(decoded.attributes());
+        assert_eq!(attrs.len(), 4);
+        assert_eq!(attrs[0].value(), &[1; 300]);
+        assert_eq!(gateway_config.decode(&attrs[1]).unwrap(), config);
+        // attributes without the flag are never joined
+        assert_eq!(attrs[2].value(), b"a");
+        assert_eq!(attrs[3].value(), b"b");
+    }
+
+    #[test]
+    fn dictionary_set_test() {
+        let path = Path::new("/usr/share/radius/dictionary.3gpp.yaml");
+        let set = DictionarySet::Set(vec!["3GPP".to_string(), "18681".to_string()]);
+
+        assert_eq!(DictionarySet::All.matches(path, Some(10415)), Some(""));
+        assert_eq!(set.matches(path, Some(10415)), Some("3GPP"));
+        assert_eq!(
+            set.matches(Path::new("dictionary.travelping.yaml"), Some(18681)),
+            Some("18681")
+        );
+        assert_eq!(set.matches(Path::new("dictionary.rfc2865.yaml"), None), None);
+
+        let set = DictionarySet::Set(vec!["dictionary.rfc2865.yaml".to_string()]);
+        assert!(set.matches(Path::new("dictionary.rfc2865.yaml"), None).is_some());
+
+        assert!(matches!(
+            load_dictionaries(
+                DictionarySet::Set(vec!["rfc2865".to_string(), "unknown".to_string()]),
+                &shipped_dictionaries()
+            ),
+            Err(RadiusError::DictionaryNotFound(name)) if name == "unknown"
+        ));
+    }
+
+    #[test]
+    fn incomplete_dictionary_test() {
+        let dir = TempDir::new(
+            "incomplete",
+            &[
+                ("dictionary.empty.yaml", ""),
+                ("dictionary.noattrs.yaml", "---\nvendor: 9\nvendor-name: Whirl\n"),
+            ],
+        );
+
+        let load = |name: &str| {
+            load_dictionaries(DictionarySet::Set(vec![name.to_string()]), dir.paths())
+        };
+        assert!(matches!(
+            load("empty"),
+            Err(RadiusError::DictionaryEmpty(path)) if path == dir.join("dictionary.empty.yaml")
+        ));
+        assert!(matches!(
+            load("noattrs"),
+            Err(RadiusError::DictionaryMissedAttributes(path))
+                if path == dir.join("dictionary.noattrs.yaml")
+        ));
+
+        std::fs::write(dir.join("dictionary.binary.yaml"), [0xff, 0xfe, 0x00]).unwrap();
+        assert!(matches!(
+            load("binary"),
+            Err(RadiusError::InvalidDictionaryFile(_, path))
+                if path == dir.join("dictionary.binary.yaml")
+        ));
+    }
+
+    #[test]
+    fn dictionaries_search_path_test() {
+        let dictionary = |id: u8| {
+            format!(
+                "---\nattributes:\n  - attribute: Whirl-Search-Path\n    id: {}\n    type: string\n",
+                id
+            )
+        };
+        let root = TempDir::new(
+            "dictionaries",
+            &[
+                ("local/dictionary.search.yaml", &dictionary(1)),
+                ("shipped/dictionary.search.yaml", &dictionary(2)),
+            ],
+        );
+        let local = root.join("local");
+
+        let result = load_dictionaries(
+            DictionarySet::Set(vec!["search".to_string()]),
+            &[local.clone(), root.join("shipped")],
+        );
+        let dict = result.unwrap();
+
+        // the dictionary from the first directory hides the second one
+        assert_eq!(dict.len(), 1);
+        let definition = dict.attribute("Whirl-Search-Path").unwrap();
+
+        assert_eq!(definition.id(), 1);
+        assert_eq!(definition.source(), Some(local.join("dictionary.search.yaml").as_path()));
+    }
 }
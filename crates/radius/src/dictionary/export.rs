@@ -0,0 +1,464 @@
+/// Defines the auxilary functions to export RADIUS dictionaries into the
+/// formats of other tools.
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{AttributeDefinition, Dictionary, Encryption};
+use crate::attribute::{VendorFormat, EXTENDED_TYPES, VENDOR_SPECIFIC_TYPE};
+use crate::error::RadiusError;
+use crate::value::DataType;
+
+// The file the attributes without a source dictionary are exported to.
+const DEFAULT_DICTIONARY: &str = "dictionary.whirl";
+
+/// Format of exported RADIUS dictionaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// FreeRADIUS dictionaries.
+    FreeRadius,
+    /// Wireshark RADIUS dictionaries. They are written into the `radius`
+    /// subdirectory, so the files could be exported right into the personal
+    /// configuration directory of Wireshark.
+    Wireshark,
+}
+
+/// Exports the attributes, vendors and named types of the given `dict` as
+/// dictionaries of the given `format` into the directory `dir`.
+///
+/// The attributes of every source dictionary are written into a file named
+/// after it without the `.yaml` extension (`dictionary.3gpp.yaml` is
+/// exported as `dictionary.3gpp`) and the main `dictionary` file includes
+/// all of them. Values of named types are written as `VALUE` lines of every
+/// attribute of the type. Extended attributes are written with dotted ids
+/// (`241.1`) and Extended-Vendor-Specific attributes within the vendor blocks
+/// of their parents (`BEGIN-VENDOR <vendor> parent=Extended-Vendor-Specific-1`).
+/// Vendors with another than the default `1,1` format of their attributes are
+/// declared with the `format=` option. The parents of extended attributes
+/// (`Extended-Attribute-1` and `Extended-Vendor-Specific-1`) are declared by
+/// the main FreeRADIUS `dictionary` before the other files are included.
+/// Existing files are overwritten.
+///
+/// Returns the paths of the written files with the main `dictionary` file
+/// being the last one.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::Path;
+///
+/// use radius::dictionary::{export_dictionaries, load_dictionaries, DictionarySet, ExportFormat};
+///
+/// let dict = load_dictionaries(DictionarySet::All, &[]).unwrap();
+/// export_dictionaries(&dict, ExportFormat::FreeRadius, Path::new("/tmp/whirl")).unwrap();
+/// ```
+pub fn export_dictionaries(
+    dict: &Dictionary,
+    format: ExportFormat,
+    dir: &Path,
+) -> Result<Vec<PathBuf>, RadiusError> {
+    let dir = match format {
+        ExportFormat::FreeRadius => dir.to_owned(),
+        ExportFormat::Wireshark => dir.join("radius"),
+    };
+
+    if let Err(err) = fs::create_dir_all(&dir) {
+        return Err(RadiusError::DictionaryWriteFailed(err, dir));
+    }
+
+    // group the attributes by the files they are exported to
+    let mut dictionaries: BTreeMap<String, Vec<&AttributeDefinition>> = BTreeMap::new();
+    for definition in dict.attributes() {
+        dictionaries
+            .entry(file_name(definition.source()))
+            .or_default()
+            .push(definition);
+    }
+
+    let mut files = Vec::new();
+    let mut main = String::from("# RADIUS dictionaries exported by whirl\n\n");
+    if format == ExportFormat::FreeRadius {
+        main.push_str(&extended_parents(dict));
+    }
+    let mut declared: HashSet<u32> = HashSet::new();
+
+    for (name, mut definitions) in dictionaries {
+        definitions.sort_by(|a, b| {
+            (a.vendor(), a.extended(), a.id(), a.name()).cmp(&(
+                b.vendor(),
+                b.extended(),
+                b.id(),
+                b.name(),
+            ))
+        });
+
+        let mut content = String::from("# RADIUS dictionary exported by whirl\n");
+        // the vendor and the parent extended attribute of the current block
+        let mut block: Option<(u32, Option<u8>)> = None;
+
+        for (i, definition) in definitions.iter().enumerate() {
+            let next = definition.vendor().map(|id| (id, definition.extended()));
+            if i == 0 || next != block {
+                if let Some((id, _)) = block {
+                    content.push_str(&format!("END-VENDOR\t{}\n", vendor_name(dict, id)));
+                }
+
+                if let Some((id, extended)) = next {
+                    let name = vendor_name(dict, id);
+                    if declared.insert(id) {
+                        let format = dict.vendor_format(id);
+                        if format == VendorFormat::default() {
+                            content.push_str(&format!("\nVENDOR\t\t{}\t{}\n", name, id));
+                        } else {
+                            content.push_str(&format!(
+                                "\nVENDOR\t\t{}\t{}\tformat={}\n",
+                                name, id, format
+                            ));
+                        }
+                    }
+                    match extended {
+                        Some(extended) => content.push_str(&format!(
+                            "\nBEGIN-VENDOR\t{}\tparent=Extended-Vendor-Specific-{}\n",
+                            name,
+                            extended - EXTENDED_TYPES.start() + 1
+                        )),
+                        None => content.push_str(&format!("\nBEGIN-VENDOR\t{}\n", name)),
+                    }
+                }
+
+                content.push('\n');
+                block = next;
+            }
+
+            content.push_str(&attribute(definition, format));
+            for (value_name, value) in definition
+                .enum_type()
+                .map(|t| t.values())
+                .unwrap_or_default()
+            {
+                content.push_str(&format!(
+                    "VALUE\t{}\t{}\t{}\n",
+                    definition.name(),
+                    value_name
+                        .split_whitespace()
+                        .collect::<Vec<&str>>()
+                        .join("-"),
+                    value
+                ));
+            }
+        }
+
+        if let Some((id, _)) = block {
+            content.push_str(&format!("END-VENDOR\t{}\n", vendor_name(dict, id)));
+        }
+
+        let path = dir.join(&name);
+        write(&path, &content)?;
+        main.push_str(&format!("$INCLUDE {}\n", name));
+        files.push(path);
+    }
+
+    let path = dir.join("dictionary");
+    write(&path, &main)?;
+    files.push(path);
+
+    Ok(files)
+}
+
+// Formats the declarations of the parents of the extended attributes of the
+// given `dict`, as FreeRADIUS refuses dotted ids of undeclared parents:
+//
+//   ATTRIBUTE Extended-Attribute-1 241 extended
+//   ATTRIBUTE Extended-Vendor-Specific-1 241.26 evs
+//
+// Parents declared by the dictionaries themselves are not repeated.
+fn extended_parents(dict: &Dictionary) -> String {
+    let mut parents: BTreeMap<u8, bool> = BTreeMap::new();
+    for definition in dict.attributes() {
+        if let Some(extended) = definition.extended() {
+            let evs = parents.entry(extended).or_insert(false);
+            *evs |= definition.vendor().is_some();
+        }
+    }
+
+    let mut content = String::new();
+    for (extended, evs) in parents {
+        let number = extended - EXTENDED_TYPES.start() + 1;
+        let data_type = if EXTENDED_TYPES.contains(&extended) {
+            "extended"
+        } else {
+            "long-extended"
+        };
+
+        let name = format!("Extended-Attribute-{}", number);
+        if dict.attribute(&name).is_none() {
+            content.push_str(&format!("ATTRIBUTE\t{}\t{}\t{}\n", name, extended, data_type));
+        }
+        let name = format!("Extended-Vendor-Specific-{}", number);
+        if evs && dict.attribute(&name).is_none() {
+            content.push_str(&format!(
+                "ATTRIBUTE\t{}\t{}.{}\tevs\n",
+                name, extended, VENDOR_SPECIFIC_TYPE
+            ));
+        }
+    }
+    if !content.is_empty() {
+        content.push('\n');
+    }
+
+    content
+}
+
+// Returns the name of the file the attributes loaded from the `source`
+// dictionary are exported to.
+fn file_name(source: Option<&Path>) -> String {
+    let file_name = source.and_then(Path::file_name).and_then(OsStr::to_str);
+    match file_name.map(|name| name.strip_suffix(".yaml").unwrap_or(name)) {
+        // the main FreeRADIUS dictionary is generated
+        Some("dictionary") | None => DEFAULT_DICTIONARY.to_string(),
+        Some(name) if name.starts_with("dictionary.") => name.to_string(),
+        Some(name) => format!("dictionary.{}", name),
+    }
+}
+
+// Returns the name of the vendor with the given `id`, vendors without names
+// are named after their ids.
+fn vendor_name(dict: &Dictionary, id: u32) -> String {
+    match dict.vendor_name(id) {
+        Some(name) => name.to_string(),
+        None => format!("Vendor-{}", id),
+    }
+}
+
+// Formats the `ATTRIBUTE` line of the given attribute `definition`.
+fn attribute(definition: &AttributeDefinition, format: ExportFormat) -> String {
+    let mut flags = Vec::new();
+
+    let data_type = match (definition.base_type(), format) {
+        (DataType::Array(_), ExportFormat::Wireshark) => "octets",
+        (DataType::Array(element), ExportFormat::FreeRadius) => {
+            flags.push("array");
+            type_name(&element, format)
+        }
+        (data_type, _) => type_name(&data_type, format),
+    };
+
+    if definition.flags().has_tag {
+        flags.push("has_tag");
+    }
+    match definition.flags().encrypt {
+        Some(Encryption::UserPassword) => flags.push("encrypt=1"),
+        Some(Encryption::TunnelPassword) => flags.push("encrypt=2"),
+        None => {}
+    }
+    if definition.flags().concat && format == ExportFormat::FreeRadius {
+        flags.push("concat");
+    }
+
+    // Extended-Vendor-Specific attributes are written within the blocks of
+    // their parents
+    let id = match (definition.extended(), definition.vendor()) {
+        (Some(extended), None) => format!("{}.{}", extended, definition.id()),
+        _ => definition.id().to_string(),
+    };
+
+    let mut line = format!("ATTRIBUTE\t{}\t{}\t{}", definition.name(), id, data_type);
+    if !flags.is_empty() {
+        line.push('\t');
+        line.push_str(&flags.join(","));
+    }
+    line.push('\n');
+
+    line
+}
+
+// Returns the name of the given base type within the given `format`.
+fn type_name(data_type: &DataType, format: ExportFormat) -> &'static str {
+    match (data_type, format) {
+        (DataType::String, _) => "string",
+        (DataType::Integer, _) => "integer",
+        (DataType::Integer64, _) => "integer64",
+        (DataType::Ipv4, _) => "ipaddr",
+        (DataType::Ipv6, _) => "ipv6addr",
+        (DataType::Byte, _) => "byte",
+        (DataType::Short, _) => "short",
+        (DataType::Signed, _) => "signed",
+        (DataType::Date, _) => "date",
+        (DataType::TimeDelta, ExportFormat::FreeRadius) => "time_delta",
+        (DataType::TimeDelta, ExportFormat::Wireshark) => "integer",
+        (DataType::Ipv4Prefix, ExportFormat::FreeRadius) => "ipv4prefix",
+        (DataType::Ipv6Prefix, _) => "ipv6prefix",
+        (DataType::Ifid, _) => "ifid",
+        (DataType::ComboIp, _) => "combo-ip",
+        (DataType::Vsa, ExportFormat::FreeRadius) => "vsa",
+        (DataType::Named(_), _) => "integer",
+        _ => "octets",
+    }
+}
+
+// Writes the `content` of the dictionary into the file with the given `path`.
+fn write(path: &Path, content: &str) -> Result<(), RadiusError> {
+    match fs::write(path, content) {
+        Ok(()) => Ok(()),
+        Err(err) => Err(RadiusError::DictionaryWriteFailed(err, path.to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod export_tests {
+    use super::super::{load_dictionaries, load_dictionaries_as, DictionaryFormat, DictionarySet};
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    fn shipped_dictionaries() -> Vec<PathBuf> {
+        vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("../../share/radius")]
+    }
+
+    #[test]
+    fn export_freeradius_test() {
+        let dir = TempDir::new("export", &[]);
+        let dict = load_dictionaries(DictionarySet::All, &shipped_dictionaries()).unwrap();
+
+        let files = export_dictionaries(&dict, ExportFormat::FreeRadius, dir.path()).unwrap();
+        let rfc2865 = fs::read_to_string(dir.join("dictionary.rfc2865")).unwrap();
+        let rfc2868 = fs::read_to_string(dir.join("dictionary.rfc2868")).unwrap();
+        let rfc3162 = fs::read_to_string(dir.join("dictionary.rfc3162")).unwrap();
+        let main = fs::read_to_string(dir.join("dictionary")).unwrap();
+        let exported = load_dictionaries_as(
+            DictionarySet::All,
+            dir.paths(),
+            DictionaryFormat::FreeRadius,
+        );
+        let exported = exported.unwrap();
+
+        assert_eq!(files.last(), Some(&dir.join("dictionary")));
+        assert!(files.contains(&dir.join("dictionary.3gpp")));
+        assert!(rfc2865.contains("ATTRIBUTE\tUser-Password\t2\tstring\tencrypt=1\n"));
+        assert!(rfc3162.contains("ATTRIBUTE\tFramed-IPv6-Prefix\t97\tipv6prefix\n"));
+        assert!(rfc2868.contains("ATTRIBUTE\tTunnel-Password\t69\tstring\thas_tag,encrypt=2\n"));
+        assert!(rfc2865.contains("VALUE\tService-Type\tFramed-User\t2\n"));
+        // parents of extended attributes are declared before they are used
+        assert!(main.starts_with(
+            "# RADIUS dictionaries exported by whirl\n\n\
+             ATTRIBUTE\tExtended-Attribute-1\t241\textended\n\n\
+             $INCLUDE "
+        ));
+
+        // the exported dictionaries describe the same attributes
+        assert_eq!(exported.len(), dict.len());
+        assert_eq!(exported.vendor_name(10415), Some("3GPP"));
+        for definition in dict.attributes() {
+            let other = exported.attribute(definition.name()).unwrap();
+            assert_eq!(other.id(), definition.id());
+            assert_eq!(other.extended(), definition.extended());
+            assert_eq!(other.vendor(), definition.vendor());
+            if let Some(vendor) = definition.vendor() {
+                assert_eq!(exported.vendor_format(vendor), dict.vendor_format(vendor));
+            }
+            // structured 3GPP values are exported as octets
+            let base_type = match definition.base_type() {
+                DataType::UserLocationInfo | DataType::MsTimeZone | DataType::PacketFilter => {
+                    DataType::Octets
+                }
+                base_type => base_type,
+            };
+            assert_eq!(other.base_type(), base_type);
+            assert_eq!(other.flags().encrypt, definition.flags().encrypt);
+            assert_eq!(other.flags().concat, definition.flags().concat);
+            assert_eq!(other.flags().has_tag, definition.flags().has_tag);
+
+            let values = |d: &AttributeDefinition| -> Vec<u64> {
+                let values = d.enum_type().map(|t| t.values()).unwrap_or_default();
+                values.iter().map(|(_, value)| *value).collect()
+            };
+            assert_eq!(values(other), values(definition), "{}", definition.name());
+        }
+    }
+
+    // Returns the dictionary with vendor and extended attributes.
+    fn extended_dictionary() -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.insert(AttributeDefinition::new(
+            "Vendor-Specific",
+            26,
+            None,
+            DataType::Vsa,
+        ))
+        .insert(AttributeDefinition::new(
+            "Whirl-Addresses",
+            1,
+            Some(32473),
+            DataType::Array(Box::new(DataType::Ipv4)),
+        ))
+        .insert(AttributeDefinition::new_extended(
+            "Whirl-Long",
+            245,
+            1,
+            Some(32473),
+            DataType::Octets,
+        ))
+        .insert(AttributeDefinition::new_extended(
+            "Frag-Status",
+            241,
+            1,
+            None,
+            DataType::Integer,
+        ));
+        dict
+    }
+
+    #[test]
+    fn export_freeradius_extended_test() {
+        let dir = TempDir::new("export-extended", &[]);
+        let dict = extended_dictionary();
+
+        export_dictionaries(&dict, ExportFormat::FreeRadius, dir.path()).unwrap();
+        let main = fs::read_to_string(dir.join("dictionary")).unwrap();
+
+        assert_eq!(
+            main,
+            "# RADIUS dictionaries exported by whirl\n\n\
+             ATTRIBUTE\tExtended-Attribute-1\t241\textended\n\
+             ATTRIBUTE\tExtended-Attribute-5\t245\tlong-extended\n\
+             ATTRIBUTE\tExtended-Vendor-Specific-5\t245.26\tevs\n\
+             \n\
+             $INCLUDE dictionary.whirl\n"
+        );
+    }
+
+    #[test]
+    fn export_wireshark_test() {
+        let dir = TempDir::new("export-wireshark", &[]);
+        let dict = extended_dictionary();
+
+        let files = export_dictionaries(&dict, ExportFormat::Wireshark, dir.path()).unwrap();
+        let whirl = fs::read_to_string(dir.join("radius").join(DEFAULT_DICTIONARY)).unwrap();
+        let main = fs::read_to_string(dir.join("radius").join("dictionary")).unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            main,
+            "# RADIUS dictionaries exported by whirl\n\n$INCLUDE dictionary.whirl\n"
+        );
+        assert_eq!(
+            whirl,
+            "# RADIUS dictionary exported by whirl\n\
+             \n\
+             ATTRIBUTE\tVendor-Specific\t26\toctets\n\
+             ATTRIBUTE\tFrag-Status\t241.1\tinteger\n\
+             \n\
+             VENDOR\t\tVendor-32473\t32473\n\
+             \n\
+             BEGIN-VENDOR\tVendor-32473\n\
+             \n\
+             ATTRIBUTE\tWhirl-Addresses\t1\toctets\n\
+             END-VENDOR\tVendor-32473\n\
+             \n\
+             BEGIN-VENDOR\tVendor-32473\tparent=Extended-Vendor-Specific-5\n\
+             \n\
+             ATTRIBUTE\tWhirl-Long\t1\toctets\n\
+             END-VENDOR\tVendor-32473\n"
+        );
+    }
+}
@@ -0,0 +1,689 @@
+/// Defines the auxilary functions to load RADIUS dictionaries in the
+/// FreeRADIUS format.
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{AttributeDefinition, AttributeFlags, Dictionary, Encryption, EnumType};
+use crate::attribute::{
+    VendorFormat, EXTENDED_TYPES, EXTENDED_VENDOR_SPECIFIC_TYPE, LONG_EXTENDED_TYPES,
+};
+use crate::error::RadiusError;
+use crate::value::DataType;
+
+/// Parser of FreeRADIUS dictionaries.
+///
+/// Attributes are added to the dictionary while the files are parsed, the
+/// `VALUE` lines are collected and turned into named types by `finish`, as
+/// values could be defined before the attributes they belong to.
+///
+/// Only the subset of the format that could be represented by `Dictionary`
+/// is supported: attributes with ids that do not fit into the type field,
+/// attributes within `BEGIN-TLV` blocks or other protocols, dotted TLV
+/// attributes, attributes of unknown types and unknown keywords are skipped.
+/// Extended attributes are declared with dotted ids (`241.1` and
+/// `241.26.<vendor>.1`) or within the vendor blocks of Extended-Vendor-Specific
+/// attributes (`BEGIN-VENDOR <vendor> parent=Extended-Vendor-Specific-1`).
+pub(super) struct Parser {
+    vendors: HashMap<String, u32>,
+    // parent types of the Extended-Vendor-Specific attributes by their names
+    evs: HashMap<String, u8>,
+    // the files that were already parsed, as a file could be included by
+    // another one and found in a dictionaries directory at the same time
+    parsed: HashSet<PathBuf>,
+    // names of the skipped attributes, their values are skipped as well
+    skipped: HashSet<String>,
+    // (dictionary, attribute, name, value) of the collected values
+    values: Vec<(PathBuf, String, String, u64)>,
+}
+
+impl Parser {
+    pub(super) fn new() -> Parser {
+        Parser {
+            vendors: HashMap::new(),
+            evs: HashMap::new(),
+            parsed: HashSet::new(),
+            skipped: HashSet::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Parses the given FreeRADIUS `dictionary` and the dictionaries it
+    /// includes, adding the attributes to the `dict`.
+    pub(super) fn parse(
+        &mut self,
+        dict: &mut Dictionary,
+        dictionary: &Path,
+    ) -> Result<(), RadiusError> {
+        let canonical = fs::canonicalize(dictionary).unwrap_or_else(|_| dictionary.to_owned());
+        if !self.parsed.insert(canonical) {
+            return Ok(());
+        }
+
+        let content = match fs::read(dictionary) {
+            Ok(content) => String::from_utf8_lossy(&content).into_owned(),
+            Err(err) => {
+                return Err(RadiusError::InvalidDictionaryFile(
+                    err,
+                    dictionary.to_owned(),
+                ));
+            }
+        };
+
+        let invalid = |number: usize, line: &str| {
+            RadiusError::DictionaryInvalidLine(
+                dictionary.to_owned(),
+                number,
+                line.trim().to_string(),
+            )
+        };
+
+        // vendor of the current BEGIN-VENDOR block
+        let mut vendor: Option<u32> = None;
+        // parent extended attribute of the current BEGIN-VENDOR block of
+        // Extended-Vendor-Specific attributes
+        let mut extended: Option<u8> = None;
+        // nesting level of BEGIN-TLV blocks
+        let mut tlv = 0;
+        // whether the current BEGIN-PROTOCOL block is not RADIUS
+        let mut foreign = false;
+
+        for (number, line) in content.lines().enumerate().map(|(n, l)| (n + 1, l)) {
+            let tokens: Vec<&str> = line
+                .split('#')
+                .next()
+                .unwrap_or("")
+                .split_whitespace()
+                .collect();
+
+            match tokens.as_slice() {
+                [] => {}
+                ["$INCLUDE", path] | ["$INCLUDE-", path] => {
+                    let path = dictionary.parent().unwrap_or(Path::new("")).join(path);
+                    if tokens[0] == "$INCLUDE-" && !path.exists() {
+                        continue;
+                    }
+                    self.parse(dict, &path)?;
+                }
+                ["VENDOR", name, id, options @ ..] => {
+                    let id = parse_number(id).ok_or_else(|| invalid(number, line))? as u32;
+                    self.vendors.insert(name.to_string(), id);
+                    dict.insert_vendor(id, name);
+
+                    for option in options {
+                        if let Some(format) = option.strip_prefix("format=") {
+                            let format = format.parse::<VendorFormat>();
+                            dict.insert_vendor_format(id, format.map_err(|_| invalid(number, line))?);
+                        }
+                    }
+                }
+                ["BEGIN-VENDOR", name, options @ ..] => {
+                    match self.vendors.get(*name) {
+                        Some(id) => vendor = Some(*id),
+                        None => {
+                            return Err(RadiusError::DictionaryUnknownVendor(
+                                dictionary.to_owned(),
+                                name.to_string(),
+                            ));
+                        }
+                    }
+
+                    extended = None;
+                    for option in options {
+                        if let Some(parent) = option.strip_prefix("parent=") {
+                            let parent = self.evs_parent(parent);
+                            extended = Some(parent.ok_or_else(|| invalid(number, line))?);
+                        } else if let Some(format) = option.strip_prefix("format=") {
+                            extended = extended.or(self.evs_parent(format));
+                        }
+                    }
+                }
+                ["END-VENDOR", ..] => {
+                    vendor = None;
+                    extended = None;
+                }
+                ["BEGIN-TLV", ..] => tlv += 1,
+                ["END-TLV", ..] => tlv -= 1,
+                ["BEGIN-PROTOCOL", name, ..] => foreign = *name != "RADIUS",
+                ["END-PROTOCOL", ..] => foreign = false,
+                ["ATTRIBUTE", name, id, data_type, options @ ..] => {
+                    if *data_type == "evs" {
+                        if let Some(parent) = id.split('.').next().and_then(parse_number) {
+                            self.evs.insert(name.to_string(), parent as u8);
+                        }
+                    }
+
+                    // in the old syntax the vendor is named by the options
+                    let vendor = options
+                        .iter()
+                        .find_map(|option| self.vendors.get(*option).copied())
+                        .or(vendor);
+                    let (extended, vendor, id) = match parse_id(id, extended, vendor) {
+                        Some(code) => code,
+                        None if id.contains('.') => {
+                            self.skipped.insert(name.to_string());
+                            continue;
+                        }
+                        None => return Err(invalid(number, line)),
+                    };
+
+                    // ids of vendor attributes should fit into the type field of
+                    // the vendor format
+                    let max_id = match (extended, vendor) {
+                        (None, Some(vendor)) => dict.vendor_format(vendor).max_vendor_type() as u64,
+                        _ => u8::MAX as u64,
+                    };
+
+                    let data_type = match base_type(data_type) {
+                        Some(data_type) if tlv == 0 && !foreign && (1..=max_id).contains(&id) => {
+                            data_type
+                        }
+                        _ => {
+                            self.skipped.insert(name.to_string());
+                            continue;
+                        }
+                    };
+
+                    let (definition, flags) =
+                        self.attribute(name, (extended, id as u32), vendor, data_type, options);
+                    let mut definition = definition.ok_or_else(|| invalid(number, line))?;
+                    definition.set_flags(flags).set_source(dictionary);
+                    dict.insert(definition);
+                }
+                ["VALUE", attribute, name, value, ..] => {
+                    let value = parse_number(value).ok_or_else(|| invalid(number, line))?;
+                    self.values.push((
+                        dictionary.to_owned(),
+                        attribute.to_string(),
+                        name.to_string(),
+                        value,
+                    ));
+                }
+                ["ATTRIBUTE", ..] | ["VALUE", ..] | ["VENDOR", ..] | ["BEGIN-VENDOR"] => {
+                    return Err(invalid(number, line));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    // Builds the definition of an attribute from the options of the
+    // `ATTRIBUTE` line, which could contain flags and, in the old syntax, the
+    // name of the vendor that is resolved by the caller.
+    fn attribute(
+        &self,
+        name: &str,
+        (extended, id): (Option<u8>, u32),
+        vendor: Option<u32>,
+        mut data_type: DataType,
+        options: &[&str],
+    ) -> (Option<AttributeDefinition>, AttributeFlags) {
+        let mut flags = AttributeFlags::default();
+
+        for option in options {
+            if self.vendors.contains_key(*option) {
+                continue;
+            }
+
+            for flag in option.split(',') {
+                match flag {
+                    "encrypt=1" => flags.encrypt = Some(Encryption::UserPassword),
+                    "encrypt=2" => flags.encrypt = Some(Encryption::TunnelPassword),
+                    "has_tag" => flags.has_tag = true,
+                    "concat" => flags.concat = true,
+                    "array" if data_type.fixed_size().is_some() => {
+                        data_type = DataType::Array(Box::new(data_type));
+                    }
+                    "array" => return (None, flags),
+                    _ => {}
+                }
+            }
+        }
+
+        let definition = match extended {
+            Some(extended) => {
+                AttributeDefinition::new_extended(name, extended, id as u8, vendor, data_type)
+            }
+            None => AttributeDefinition::new(name, id, vendor, data_type),
+        };
+
+        (Some(definition), flags)
+    }
+
+    // Returns the type of the parent attribute of the Extended-Vendor-Specific
+    // attribute with the given name. The standard names are resolved even if
+    // the attributes are not declared by the parsed dictionaries.
+    fn evs_parent(&self, name: &str) -> Option<u8> {
+        if let Some(parent) = self.evs.get(name) {
+            return Some(*parent);
+        }
+
+        let number = name
+            .strip_prefix("Extended-Vendor-Specific-")?
+            .parse::<u8>()
+            .ok()?;
+        let parent = number.checked_add(*EXTENDED_TYPES.start() - 1)?;
+        is_extended(parent as u64).then_some(parent)
+    }
+
+    /// Adds the collected values to the `dict`.
+    ///
+    /// Values of an attribute declared with a base type are added to the
+    /// named type with the lowercased name of the attribute, so values of
+    /// `Service-Type` end up in the `service-type` type like within the YAML
+    /// dictionaries and the attribute is changed to refer to the type.
+    pub(super) fn finish(self, dict: &mut Dictionary) -> Result<(), RadiusError> {
+        let mut types: Vec<EnumType> = Vec::new();
+
+        for (dictionary, attribute, name, value) in self.values {
+            let definition = match dict.attribute(&attribute) {
+                Some(definition) => definition,
+                None if self.skipped.contains(&attribute) => continue,
+                None => {
+                    return Err(RadiusError::DictionaryUnknownAttribute(
+                        dictionary, attribute,
+                    ))
+                }
+            };
+
+            let type_name = match definition.data_type() {
+                DataType::Named(type_name) => type_name.to_string(),
+                data_type => {
+                    let type_name = attribute.to_lowercase();
+                    let mut definition = definition.clone();
+                    definition.data_type = DataType::Named(type_name.clone());
+                    types.push(EnumType::new(&type_name, data_type.clone()));
+                    dict.insert(definition);
+                    type_name
+                }
+            };
+
+            match types.iter_mut().rev().find(|t| t.name() == type_name) {
+                Some(enum_type) => enum_type.insert(&name, value),
+                None => {
+                    let mut enum_type =
+                        EnumType::new(&type_name, definition_base_type(dict, &attribute));
+                    enum_type.insert(&name, value);
+                    types.push(enum_type);
+                }
+            }
+        }
+
+        for enum_type in types {
+            dict.insert_type(enum_type);
+        }
+
+        Ok(())
+    }
+}
+
+// Returns the base type of the attribute with the given name.
+fn definition_base_type(dict: &Dictionary, attribute: &str) -> DataType {
+    dict.attribute(attribute)
+        .map(|definition| definition.base_type())
+        .unwrap_or(DataType::Integer)
+}
+
+// Maps a FreeRADIUS data type to the type of the attribute, both the types
+// of FreeRADIUS v3 and their v4 names like `uint32` are known. Returns `None`
+// for types of attributes that could not be represented by `Dictionary` and
+// for unknown types, like `ether` or `abinary`, as values of such attributes
+// would be taken and shown as raw octets.
+fn base_type(data_type: &str) -> Option<DataType> {
+    // fixed-size octets are declared as octets[N]
+    let data_type = data_type.split('[').next().unwrap_or(data_type);
+
+    match data_type {
+        "string" => Some(DataType::String),
+        "octets" => Some(DataType::Octets),
+        "integer" | "uint32" => Some(DataType::Integer),
+        "integer64" | "uint64" => Some(DataType::Integer64),
+        "ipaddr" | "ipv4addr" => Some(DataType::Ipv4),
+        "ipv6addr" => Some(DataType::Ipv6),
+        "byte" | "uint8" | "bool" => Some(DataType::Byte),
+        "short" | "uint16" => Some(DataType::Short),
+        "signed" | "int32" => Some(DataType::Signed),
+        "date" => Some(DataType::Date),
+        "time_delta" => Some(DataType::TimeDelta),
+        "ipv4prefix" => Some(DataType::Ipv4Prefix),
+        "ipv6prefix" => Some(DataType::Ipv6Prefix),
+        "ifid" => Some(DataType::Ifid),
+        "combo-ip" => Some(DataType::ComboIp),
+        "vsa" => Some(DataType::Vsa),
+        _ => None,
+    }
+}
+
+// Parses the id of an attribute declared within the vendor block of the
+// given `vendor` and the parent `extended` attribute. Dotted ids are parsed
+// for extended attributes only, as `241.1` and `241.26.<vendor>.1` for
+// Extended-Vendor-Specific ones. Returns the parent extended attribute, the
+// vendor and the id of the attribute.
+fn parse_id(
+    id: &str,
+    extended: Option<u8>,
+    vendor: Option<u32>,
+) -> Option<(Option<u8>, Option<u32>, u64)> {
+    let numbers = id
+        .split('.')
+        .map(parse_number)
+        .collect::<Option<Vec<u64>>>()?;
+
+    match numbers.as_slice() {
+        [id] => Some((extended, vendor, *id)),
+        [parent, id] if is_extended(*parent) => Some((Some(*parent as u8), None, *id)),
+        [parent, evs, vendor, id]
+            if is_extended(*parent) && *evs == EXTENDED_VENDOR_SPECIFIC_TYPE as u64 =>
+        {
+            Some((Some(*parent as u8), Some(*vendor as u32), *id))
+        }
+        _ => None,
+    }
+}
+
+// Returns true if the given type is the type of an extended attribute.
+fn is_extended(attr_type: u64) -> bool {
+    match u8::try_from(attr_type) {
+        Ok(attr_type) => {
+            EXTENDED_TYPES.contains(&attr_type) || LONG_EXTENDED_TYPES.contains(&attr_type)
+        }
+        Err(_) => false,
+    }
+}
+
+// Parses a decimal or a hexadecimal (0x prefixed) number.
+fn parse_number(number: &str) -> Option<u64> {
+    match number.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => number.parse::<u64>().ok(),
+    }
+}
+
+#[cfg(test)]
+mod freeradius_tests {
+    use super::super::{load_dictionaries, load_dictionaries_as, DictionaryFormat, DictionarySet};
+    use super::*;
+    use crate::test_dir::TempDir;
+    use crate::value::AttributeValue;
+
+    const DICTIONARY: &str = "
+# master dictionary
+$INCLUDE dictionary.rfc2865
+$INCLUDE- dictionary.missing
+$INCLUDE dictionary.vendor
+";
+
+    const RFC2865: &str = "
+VALUE   Service-Type    Framed-User     2
+ATTRIBUTE   User-Name       1   string
+ATTRIBUTE   User-Password   2   string  encrypt=1
+ATTRIBUTE   NAS-IP-Address  4   ipaddr
+ATTRIBUTE   Service-Type    6   integer
+VALUE   Service-Type    Login-User      1   # trailing comment
+ATTRIBUTE   Class           25  octets
+ATTRIBUTE   Tunnel-Password 69  string  has_tag,encrypt=2
+ATTRIBUTE   Extended-Attribute-1    241 extended
+ATTRIBUTE   Frag-Status     241.1   integer
+VALUE   Frag-Status     Reserved    0
+ATTRIBUTE   Server-Internal 1000    integer
+";
+
+    const VENDOR: &str = "
+VENDOR      Whirl       32473
+VENDOR      Old-Whirl   32474
+VENDOR      Wide-Whirl  32475   format=2,1
+
+BEGIN-VENDOR Whirl
+ATTRIBUTE   Whirl-Rate      1   integer64
+ATTRIBUTE   Whirl-Mode      2   byte
+VALUE   Whirl-Mode      Fast    0x01
+ATTRIBUTE   Whirl-Address   3   ipv6addr    array
+ATTRIBUTE   Whirl-Container 4   tlv
+ATTRIBUTE   Whirl-Prefix    5   ipv6prefix
+BEGIN-TLV   Whirl-Container
+ATTRIBUTE   Whirl-Child     1   string
+END-TLV     Whirl-Container
+END-VENDOR  Whirl
+
+BEGIN-VENDOR Whirl  parent=Extended-Vendor-Specific-5
+ATTRIBUTE   Whirl-Long      1   octets
+END-VENDOR  Whirl
+
+ATTRIBUTE   Whirl-Evs       241.26.32473.2  string
+ATTRIBUTE   Old-Whirl-Name  1   string  Old-Whirl
+ATTRIBUTE   Wide-Whirl-Name 300 string  Wide-Whirl
+";
+
+    fn write_dictionaries(name: &str, files: &[(&str, &str)]) -> TempDir {
+        TempDir::new(&format!("freeradius-{}", name), files)
+    }
+
+    #[test]
+    fn load_freeradius_test() {
+        let dir = write_dictionaries(
+            "load",
+            &[
+                ("dictionary", DICTIONARY),
+                ("dictionary.rfc2865", RFC2865),
+                ("dictionary.vendor", VENDOR),
+                ("README", "not a dictionary"),
+            ],
+        );
+
+        let result = load_dictionaries(DictionarySet::All, dir.paths());
+        let dict = result.unwrap();
+
+        assert_eq!(dict.len(), 15);
+        assert!(dict.attribute("Extended-Attribute-1").is_none());
+        assert!(dict.attribute("Server-Internal").is_none());
+        assert!(dict.attribute("Whirl-Child").is_none());
+
+        let user_password = dict.attribute("User-Password").unwrap();
+        assert_eq!(
+            user_password.flags().encrypt,
+            Some(Encryption::UserPassword)
+        );
+        assert!(user_password
+            .source()
+            .unwrap()
+            .ends_with("dictionary.rfc2865"));
+        let tunnel_password = dict.attribute("Tunnel-Password").unwrap();
+        assert!(tunnel_password.flags().has_tag);
+        assert_eq!(
+            tunnel_password.flags().encrypt,
+            Some(Encryption::TunnelPassword)
+        );
+        assert_eq!(
+            dict.attribute("NAS-IP-Address").unwrap().data_type(),
+            &DataType::Ipv4
+        );
+
+        let service_type = dict.attribute("Service-Type").unwrap();
+        assert_eq!(
+            service_type.data_type(),
+            &DataType::Named("service-type".to_string())
+        );
+        assert_eq!(service_type.base_type(), DataType::Integer);
+        assert_eq!(
+            service_type.parse_value("Framed-User").unwrap(),
+            AttributeValue::Integer(2)
+        );
+        assert_eq!(
+            service_type.format_value(&AttributeValue::Integer(1)),
+            "Login-User"
+        );
+
+        let whirl_mode = dict.attribute("Whirl-Mode").unwrap();
+        assert_eq!(whirl_mode.vendor(), Some(32473));
+        assert_eq!(dict.vendor_name(32473), Some("Whirl"));
+        assert_eq!(
+            whirl_mode.parse_value("Fast").unwrap(),
+            AttributeValue::Byte(1)
+        );
+        assert_eq!(
+            dict.attribute("Whirl-Prefix").unwrap().data_type(),
+            &DataType::Ipv6Prefix
+        );
+        assert_eq!(
+            dict.attribute("Whirl-Address").unwrap().data_type(),
+            &DataType::Array(Box::new(DataType::Ipv6))
+        );
+        assert_eq!(
+            dict.attribute_by_code(Some(32474), 1).unwrap().name(),
+            "Old-Whirl-Name"
+        );
+        assert_eq!(dict.vendor_format(32475), "2,1".parse().unwrap());
+        assert_eq!(
+            dict.attribute_by_code(Some(32475), 300).unwrap().name(),
+            "Wide-Whirl-Name"
+        );
+
+        let frag_status = dict.attribute("Frag-Status").unwrap();
+        assert_eq!(frag_status.extended(), Some(241));
+        assert_eq!(frag_status.id(), 1);
+        assert_eq!(
+            frag_status.parse_value("Reserved").unwrap(),
+            AttributeValue::Integer(0)
+        );
+        assert_eq!(
+            dict.extended_attribute_by_code(245, Some(32473), 1)
+                .unwrap()
+                .name(),
+            "Whirl-Long"
+        );
+        assert_eq!(
+            dict.extended_attribute_by_code(241, Some(32473), 2)
+                .unwrap()
+                .name(),
+            "Whirl-Evs"
+        );
+    }
+
+    #[test]
+    fn load_mixed_formats_test() {
+        let dir = write_dictionaries(
+            "mixed",
+            &[
+                ("dictionary.rfc2865", RFC2865),
+                (
+                    "dictionary.types.yaml",
+                    "---\nattributes: []\ntypes:\n  service-type:\n    type: integer\n    values:\n      - name: \"Callback-Login-User\"\n        value: 3\n",
+                ),
+            ],
+        );
+
+        let dirs = dir.paths();
+        let mixed = load_dictionaries(DictionarySet::All, dirs);
+        let yaml = load_dictionaries_as(DictionarySet::All, dirs, DictionaryFormat::Yaml);
+        let freeradius = load_dictionaries_as(
+            DictionarySet::Set(vec!["rfc2865".to_string()]),
+            dirs,
+            DictionaryFormat::FreeRadius,
+        );
+
+        // values from both formats are merged into the same type
+        let mixed = mixed.unwrap();
+        let service_type = mixed.attribute("Service-Type").unwrap();
+        assert_eq!(
+            service_type.parse_value("Login-User").unwrap(),
+            AttributeValue::Integer(1)
+        );
+        assert_eq!(
+            service_type.parse_value("Callback-Login-User").unwrap(),
+            AttributeValue::Integer(3)
+        );
+
+        assert!(yaml.unwrap().is_empty());
+        assert_eq!(freeradius.unwrap().len(), 7);
+    }
+
+    #[test]
+    fn invalid_freeradius_test() {
+        let dir = write_dictionaries(
+            "invalid",
+            &[
+                (
+                    "dictionary.line",
+                    "ATTRIBUTE   Line-Attribute  one string\n",
+                ),
+                ("dictionary.vendor", "BEGIN-VENDOR Unknown\n"),
+                ("dictionary.format", "VENDOR  Whirl   32473   format=3,1\n"),
+                ("dictionary.value", "VALUE   Unknown-Attribute   One 1\n"),
+            ],
+        );
+
+        let load = |name: &str| {
+            load_dictionaries(
+                DictionarySet::Set(vec![name.to_string()]),
+                dir.paths(),
+            )
+        };
+        let line = load("line");
+        let vendor = load("vendor");
+        let value = load("value");
+        let format = load("format");
+
+        assert!(matches!(
+            line,
+            Err(RadiusError::DictionaryInvalidLine(_, 1, line)) if line == "ATTRIBUTE   Line-Attribute  one string"
+        ));
+        assert!(matches!(
+            vendor,
+            Err(RadiusError::DictionaryUnknownVendor(_, name)) if name == "Unknown"
+        ));
+        assert!(matches!(
+            value,
+            Err(RadiusError::DictionaryUnknownAttribute(_, name)) if name == "Unknown-Attribute"
+        ));
+        assert!(matches!(
+            format,
+            Err(RadiusError::DictionaryInvalidLine(_, 1, _))
+        ));
+    }
+    #[test]
+    fn freeradius_types_test() {
+        let dir = write_dictionaries(
+            "types",
+            &[(
+                "dictionary",
+                "
+ATTRIBUTE   V4-Byte         1   uint8
+ATTRIBUTE   V4-Short        2   uint16
+ATTRIBUTE   V4-Integer      3   uint32
+ATTRIBUTE   V4-Integer64    4   uint64
+ATTRIBUTE   V4-Signed       5   int32
+ATTRIBUTE   V4-Bool         6   bool
+VALUE   V4-Integer      Enabled     1
+ATTRIBUTE   Ether-Address   7   ether
+ATTRIBUTE   Ascend-Filter   8   abinary
+VALUE   Ether-Address   Broadcast   1
+ATTRIBUTE   Raw-Data        9   octets[4]
+",
+            )],
+        );
+
+        let dict = load_dictionaries(DictionarySet::All, dir.paths()).unwrap();
+
+        for (name, data_type) in [
+            ("V4-Byte", DataType::Byte),
+            ("V4-Short", DataType::Short),
+            ("V4-Integer64", DataType::Integer64),
+            ("V4-Signed", DataType::Signed),
+            ("V4-Bool", DataType::Byte),
+            ("Raw-Data", DataType::Octets),
+        ] {
+            assert_eq!(dict.attribute(name).unwrap().data_type(), &data_type, "{}", name);
+        }
+
+        let integer = dict.attribute("V4-Integer").unwrap();
+        assert_eq!(integer.base_type(), DataType::Integer);
+        assert_eq!(integer.format_value(&AttributeValue::Integer(1)), "Enabled");
+
+        // attributes of unknown types are skipped with their values
+        assert!(dict.attribute("Ether-Address").is_none());
+        assert!(dict.attribute("Ascend-Filter").is_none());
+        assert_eq!(dict.len(), 7);
+    }
+}
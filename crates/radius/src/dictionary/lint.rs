@@ -0,0 +1,316 @@
+/// Defines the auxilary functions to validate RADIUS dictionaries.
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::freeradius::Parser;
+use super::{
+    attribute_id, dictionary_files, load_dictionary, Dictionary, DictionaryFile, DictionarySet,
+};
+use crate::attribute::VendorFormat;
+use crate::error::RadiusError;
+use crate::value::DataType;
+
+use yaml_rust::yaml::Yaml;
+
+/// Maximum Vendor-Id, the high-order octet of it is 0 (RFC 2865 5.26).
+const MAX_VENDOR_ID: i64 = 0xffffff;
+
+/// Checks the set of RADIUS dictionaries specified by the given dictionary
+/// `set` within the given `paths` (see `load_dictionaries`).
+///
+/// Apart of the errors `load_dictionaries` fails with, the following
+/// problems are reported:
+///
+///   * vendor ids out of range `1..16777215` and attribute ids (or extended
+///     types of extended attributes) out of range `1..255` or, for vendors
+///     with wider type fields (see `VendorFormat`), ids that do not fit into
+///     the type field;
+///   * empty attribute names or names with whitespaces;
+///   * attributes with the same name or with the same id within the space
+///     of the same vendor and the same parent extended attribute;
+///   * types within the `types` sections which are not based on one of the
+///     base types;
+///   * references to types that are not defined by any of the dictionaries;
+///   * values of types with the same name or the same number.
+///
+/// Returns all the found problems in the order of the dictionaries or an
+/// error if the dictionaries could not be found or read.
+pub fn lint_dictionaries(
+    set: DictionarySet,
+    paths: &[PathBuf],
+) -> Result<Vec<RadiusError>, RadiusError> {
+    let mut linter = Linter::default();
+    let mut parser = Parser::new();
+    let mut dict = Dictionary::new();
+
+    for file in dictionary_files(&set, paths, None)? {
+        match file {
+            DictionaryFile::Yaml(dictionary, document) => {
+                // structural errors are reported the same way as by the loader,
+                // ids out of range are reported by the linter for every attribute
+                match load_dictionary(&mut Dictionary::new(), &dictionary, &document) {
+                    Ok(()) | Err(RadiusError::DictionaryAttrIdOutOfRange(..)) => {}
+                    Err(err) => linter.problems.push(err),
+                }
+
+                linter.lint(&dictionary, &document);
+            }
+            // FreeRADIUS dictionaries are only checked to be loadable
+            DictionaryFile::FreeRadius(dictionary) => {
+                if let Err(err) = parser.parse(&mut dict, &dictionary) {
+                    linter.problems.push(err);
+                }
+            }
+        }
+    }
+
+    if let Err(err) = parser.finish(&mut dict) {
+        linter.problems.push(err);
+    }
+
+    Ok(linter.finish())
+}
+
+#[derive(Default)]
+struct Linter {
+    names: HashSet<String>,
+    codes: HashSet<(Option<u32>, Option<u8>, i64)>,
+    types: HashSet<String>,
+    values: HashMap<String, (HashSet<String>, HashSet<u64>)>,
+    // (dictionary, attribute, type) of attributes of named types
+    references: Vec<(PathBuf, String, String)>,
+    problems: Vec<RadiusError>,
+}
+
+impl Linter {
+    fn lint(&mut self, dictionary: &Path, document: &Yaml) {
+        let vendor = match &document["vendor"] {
+            Yaml::Integer(vendor) => {
+                if !(1..=MAX_VENDOR_ID).contains(vendor) {
+                    self.problems.push(RadiusError::DictionaryVendorIdOutOfRange(
+                        dictionary.to_owned(),
+                        *vendor,
+                    ));
+                }
+                Some(*vendor as u32)
+            }
+            _ => None,
+        };
+        let format = match document["format"].as_str().map(|f| f.parse::<VendorFormat>()) {
+            Some(Ok(format)) => format,
+            _ => VendorFormat::default(),
+        };
+
+        let attributes = document["attributes"].as_vec().map(|a| a.as_slice());
+        for attribute in attributes.unwrap_or_default() {
+            let (key, (extended, id)) =
+                match (&attribute["attribute"], attribute_id(&attribute["id"])) {
+                    (Yaml::String(key), Some(id)) => (key, id),
+                    _ => continue,
+                };
+
+            let max_id = match (extended, vendor) {
+                (None, Some(_)) => format.max_vendor_type() as i64,
+                _ => u8::MAX as i64,
+            };
+            if !(1..=max_id).contains(&id) {
+                self.problems.push(RadiusError::DictionaryAttrIdOutOfRange(
+                    dictionary.to_owned(),
+                    key.to_string(),
+                    id,
+                ));
+            }
+
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                self.problems.push(RadiusError::DictionaryInvalidAttrKey(
+                    dictionary.to_owned(),
+                    key.to_string(),
+                ));
+            }
+
+            if !self.names.insert(key.to_string()) {
+                self.problems.push(RadiusError::DictionaryDuplicateAttrKey(
+                    dictionary.to_owned(),
+                    key.to_string(),
+                ));
+            }
+
+            if !self.codes.insert((vendor, extended, id)) {
+                self.problems.push(RadiusError::DictionaryDuplicateAttrId(
+                    dictionary.to_owned(),
+                    key.to_string(),
+                    vendor,
+                    id,
+                ));
+            }
+
+            if let Some(Ok(DataType::Named(name))) =
+                attribute["type"].as_str().map(|t| t.parse::<DataType>())
+            {
+                self.references
+                    .push((dictionary.to_owned(), key.to_string(), name));
+            }
+        }
+
+        let types = match &document["types"] {
+            Yaml::Hash(types) => types,
+            _ => return,
+        };
+
+        for (name, definition) in types {
+            let name = match name.as_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            self.types.insert(name.to_string());
+
+            if let Some(Ok(DataType::Named(base))) =
+                definition["type"].as_str().map(|t| t.parse::<DataType>())
+            {
+                self.problems.push(RadiusError::DictionaryUnknownBaseType(
+                    dictionary.to_owned(),
+                    name.to_string(),
+                    base,
+                ));
+            }
+
+            let (names, numbers) = self.values.entry(name.to_string()).or_default();
+            let values = definition["values"].as_vec().map(|v| v.as_slice());
+            for value in values.unwrap_or_default() {
+                let (value_name, number) = match (&value["name"], &value["value"]) {
+                    (Yaml::String(value_name), Yaml::Integer(number)) if *number >= 0 => {
+                        (value_name, *number as u64)
+                    }
+                    _ => continue,
+                };
+
+                if !names.insert(value_name.to_string()) {
+                    self.problems.push(RadiusError::DictionaryDuplicateValueName(
+                        dictionary.to_owned(),
+                        name.to_string(),
+                        value_name.to_string(),
+                    ));
+                }
+
+                if !numbers.insert(number) {
+                    self.problems.push(RadiusError::DictionaryDuplicateValue(
+                        dictionary.to_owned(),
+                        name.to_string(),
+                        number,
+                    ));
+                }
+            }
+        }
+    }
+
+    // Reports the references to types that are not defined by any of the
+    // dictionaries as types could be defined after the attributes.
+    fn finish(mut self) -> Vec<RadiusError> {
+        for (dictionary, key, name) in self.references {
+            if !self.types.contains(&name) {
+                self.problems
+                    .push(RadiusError::DictionaryUnresolvedType(dictionary, key, name));
+            }
+        }
+
+        self.problems
+    }
+}
+
+#[cfg(test)]
+mod lint_tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    #[test]
+    fn shipped_dictionaries_test() {
+        let shipped = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../share/radius");
+        let problems = lint_dictionaries(DictionarySet::All, &[shipped]).unwrap();
+
+        assert!(problems.is_empty(), "{:#?}", problems);
+    }
+
+    #[test]
+    fn lint_incomplete_dictionaries_test() {
+        let dir = TempDir::new(
+            "lint-incomplete",
+            &[
+                ("dictionary.empty.yaml", ""),
+                ("dictionary.header.yaml", "---\n"),
+                ("dictionary.noattrs.yaml", "---\nvendor: 9\n"),
+            ],
+        );
+
+        let problems = lint_dictionaries(DictionarySet::All, dir.paths()).unwrap();
+        let problems: Vec<String> = problems.iter().map(|p| format!("{:?}", p)).collect();
+
+        let file = |name: &str| format!("{:?}", dir.join(name));
+        assert_eq!(
+            problems,
+            vec![
+                format!("DictionaryEmpty({})", file("dictionary.empty.yaml")),
+                format!("DictionaryEmpty({})", file("dictionary.header.yaml")),
+                format!("DictionaryMissedAttributes({})", file("dictionary.noattrs.yaml")),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_dictionaries_test() {
+        let dir = TempDir::new(
+            "lint",
+            &[(
+                "dictionary.lint.yaml",
+                r#"---
+vendor: 16777216
+attributes:
+  - attribute: "Lint-One"
+    id: 1
+    type: ivp6
+  - attribute: "Lint-One"
+    id: 2
+    type: string
+  - attribute: "Lint-Two"
+    id: 2
+    type: lint-type
+  - attribute: "Lint-Three"
+    id: 256
+    type: integer
+  - attribute: "Lint- Four"
+    id: 4
+    type: integer
+types:
+  lint-type:
+    type: integr
+    values:
+      - name: "Telnet"
+        value: 0
+      - name: "Telnet"
+        value: 1
+      - name: "Rlogin"
+        value: 1
+"#,
+            )],
+        );
+
+        let set = DictionarySet::Set(vec!["lint".to_string()]);
+        let result = lint_dictionaries(set, dir.paths());
+        let problems: Vec<String> = result.unwrap().iter().map(|p| format!("{:?}", p)).collect();
+
+        let file = format!("{:?}", dir.join("dictionary.lint.yaml"));
+        assert_eq!(
+            problems,
+            vec![
+                format!("DictionaryVendorIdOutOfRange({}, 16777216)", file),
+                format!("DictionaryDuplicateAttrKey({}, \"Lint-One\")", file),
+                format!("DictionaryDuplicateAttrId({}, \"Lint-Two\", Some(16777216), 2)", file),
+                format!("DictionaryAttrIdOutOfRange({}, \"Lint-Three\", 256)", file),
+                format!("DictionaryInvalidAttrKey({}, \"Lint- Four\")", file),
+                format!("DictionaryUnknownBaseType({}, \"lint-type\", \"integr\")", file),
+                format!("DictionaryDuplicateValueName({}, \"lint-type\", \"Telnet\")", file),
+                format!("DictionaryDuplicateValue({}, \"lint-type\", 1)", file),
+                format!("DictionaryUnresolvedType({}, \"Lint-One\", \"ivp6\")", file),
+            ]
+        );
+    }
+}
@@ -4,15 +4,23 @@ use std::path::PathBuf;
 
 use yaml_rust::scanner::ScanError;
 
+use super::value::DataType;
+
 /// Path to the directory with RADIUS dictionaries.
 type RadiusDictionariesDir = PathBuf;
 
-/// Path to the RADIUS dictionary YAML file.
+/// Path to the RADIUS dictionary YAML or FreeRADIUS file.
 type RadiusDictionary = PathBuf;
 
 /// RADIUS attribute name.
 type RadiusAttrKey = String;
 
+/// RADIUS attribute type.
+type RadiusAttrType = u8;
+
+/// IANA private enterprise number of a RADIUS vendor.
+type RadiusVendorId = u32;
+
 /// Represents an error that may appear during any interractions
 /// within the library.
 #[derive(Debug)]
@@ -24,10 +32,10 @@ pub enum RadiusError {
     /// dictionary.
     InvalidDictionaryDir(RadiusDictionariesDir),
     /// Will be returned from the `load_dictionaries` if the given RADIUS
-    /// dictionary could not be opened.
+    /// dictionary could not be opened or read.
     ///
     /// The first argument will contain io::Error with the reason why the
-    /// dictionary file could not be opened and the second argument will
+    /// dictionary file could not be read and the second argument will
     /// contain path to the failed RADIUS dictionary.
     InvalidDictionaryFile(std::io::Error, RadiusDictionary),
     /// Will be returned from the `load_dictionaries` if the given RADIUS
@@ -45,6 +53,18 @@ pub enum RadiusError {
     /// dictionary.
     DictionaryMissedAttrKey(RadiusDictionary),
     /// Will be returned from the `load_dictionaries` if the given RADIUS
+    /// dictionary does not contain any YAML document.
+    ///
+    /// The first argument will contain path to the given/failed RADIUS
+    /// dictionary.
+    DictionaryEmpty(RadiusDictionary),
+    /// Will be returned from the `load_dictionaries` if the given RADIUS
+    /// dictionary does not contain the `attributes` list.
+    ///
+    /// The first argument will contain path to the given/failed RADIUS
+    /// dictionary.
+    DictionaryMissedAttributes(RadiusDictionary),
+    /// Will be returned from the `load_dictionaries` if the given RADIUS
     /// dictionary does not contain `id` key for definition of any
     /// RADIUS attributes.
     ///
@@ -52,10 +72,235 @@ pub enum RadiusError {
     /// dictionary. The second argument is the name of the RADIUS attribute
     /// with the missed `id`.
     DictionaryMissedAttrId(RadiusDictionary, RadiusAttrKey),
+    /// Will be returned from the `load_dictionaries` if the `id` of a RADIUS
+    /// attribute is neither a number nor an extended id like `241.1` within
+    /// the space of one of the extended attributes `241`-`246`.
+    ///
+    /// The first argument will contain path to the given/failed RADIUS
+    /// dictionary. The second argument is the name of the RADIUS attribute
+    /// with the invalid `id`.
+    DictionaryInvalidAttrId(RadiusDictionary, RadiusAttrKey),
+    /// Will be returned from the `load_dictionaries` if the given RADIUS
+    /// dictionary does not contain `type` key for definition of any
+    /// RADIUS attribute or type.
+    ///
+    /// The first argument will contain path to the given/failed RADIUS
+    /// dictionary. The second argument is the name of the RADIUS attribute
+    /// or type with the missed `type`.
+    DictionaryMissedAttrType(RadiusDictionary, RadiusAttrKey),
+    /// Will be returned from the `load_dictionaries` if the `values` of a
+    /// type within the `types` section of the given RADIUS dictionary are not
+    /// a list of `name`/`value` pairs with non-negative integer values.
+    ///
+    /// The first argument will contain path to the given/failed RADIUS
+    /// dictionary. The second argument is the name of the invalid type.
+    DictionaryInvalidTypeValue(RadiusDictionary, RadiusAttrKey),
+    /// Will be returned from the `load_dictionaries` if the `flags` of a
+    /// RADIUS attribute within the given RADIUS dictionary contain an invalid
+    /// value.
+    ///
+    /// The first argument will contain path to the given/failed RADIUS
+    /// dictionary. The second argument is the name of the RADIUS attribute
+    /// with the invalid flag.
+    DictionaryInvalidAttrFlag(RadiusDictionary, RadiusAttrKey),
     /// Will be returned from the `load_dictionaries` if the given RADIUS
     /// dictionary contains invalid `vendor-id` key.
     ///
     /// The first argument will contain path to the given/failed RADIUS
     /// dictionary.
     DictionaryIvalidVendorId(RadiusDictionary),
+    /// Will be returned from the `load_dictionaries` if none of the
+    /// directories with RADIUS dictionaries contains the dictionary requested
+    /// by `DictionarySet::Set`.
+    ///
+    /// The argument will contain the name of the requested dictionary.
+    DictionaryNotFound(String),
+    /// Will be returned from the `lint_dictionaries` if the `vendor` of the
+    /// given RADIUS dictionary is out of range `1..16777215`. The high-order
+    /// octet of the Vendor-Id is 0 according to RFC 2865 5.26.
+    ///
+    /// The second argument will contain the rejected vendor id.
+    DictionaryVendorIdOutOfRange(RadiusDictionary, i64),
+    /// Will be returned from the `lint_dictionaries` if the `id` of a
+    /// RADIUS attribute is out of range `1..255` or does not fit into the type
+    /// field of the vendor format, and from the `load_dictionaries` if the
+    /// `id` could not be encoded at all.
+    ///
+    /// The second argument will contain the name of the attribute and the
+    /// third argument will contain the rejected id.
+    DictionaryAttrIdOutOfRange(RadiusDictionary, RadiusAttrKey, i64),
+    /// Will be returned from the `lint_dictionaries` if the name of a RADIUS
+    /// attribute is empty or contains whitespaces.
+    ///
+    /// The second argument will contain the rejected name.
+    DictionaryInvalidAttrKey(RadiusDictionary, RadiusAttrKey),
+    /// Will be returned from the `lint_dictionaries` if a RADIUS attribute
+    /// with the same name is already defined by the same or another RADIUS
+    /// dictionary.
+    ///
+    /// The second argument will contain the name of the attribute.
+    DictionaryDuplicateAttrKey(RadiusDictionary, RadiusAttrKey),
+    /// Will be returned from the `lint_dictionaries` if a RADIUS attribute
+    /// with the same id is already defined within the space of the same
+    /// vendor.
+    ///
+    /// The second argument will contain the name of the attribute, the
+    /// third argument will contain the vendor id and the fourth argument
+    /// will contain the duplicated id.
+    DictionaryDuplicateAttrId(RadiusDictionary, RadiusAttrKey, Option<RadiusVendorId>, i64),
+    /// Will be returned from the `lint_dictionaries` if the `type` of a type
+    /// within the `types` section is not one of the base types.
+    ///
+    /// The second argument will contain the name of the type and the third
+    /// argument will contain the rejected base type.
+    DictionaryUnknownBaseType(RadiusDictionary, RadiusAttrKey, String),
+    /// Will be returned from the `lint_dictionaries` if a RADIUS attribute
+    /// refers to a type that is not defined within the `types` section of
+    /// any of the RADIUS dictionaries.
+    ///
+    /// The second argument will contain the name of the attribute and the
+    /// third argument will contain the name of the unresolved type.
+    DictionaryUnresolvedType(RadiusDictionary, RadiusAttrKey, String),
+    /// Will be returned from the `lint_dictionaries` if a value with the
+    /// same name is already defined for a type.
+    ///
+    /// The second argument will contain the name of the type and the third
+    /// argument will contain the duplicated name.
+    DictionaryDuplicateValueName(RadiusDictionary, RadiusAttrKey, String),
+    /// Will be returned from the `lint_dictionaries` if a value with the
+    /// same number is already defined for a type.
+    ///
+    /// The second argument will contain the name of the type and the third
+    /// argument will contain the duplicated value.
+    DictionaryDuplicateValue(RadiusDictionary, RadiusAttrKey, u64),
+    /// Will be returned from the `load_dictionaries` if a line of the given
+    /// FreeRADIUS dictionary could not be parsed.
+    ///
+    /// The second argument will contain the number of the line and the third
+    /// argument will contain the line itself.
+    DictionaryInvalidLine(RadiusDictionary, usize, String),
+    /// Will be returned from the `load_dictionaries` if the given FreeRADIUS
+    /// dictionary refers to a vendor that is not defined by a `VENDOR` line.
+    ///
+    /// The second argument will contain the name of the unknown vendor.
+    DictionaryUnknownVendor(RadiusDictionary, String),
+    /// Will be returned from the `load_dictionaries` if the given FreeRADIUS
+    /// dictionary defines a `VALUE` for an attribute that is not defined by
+    /// any of the dictionaries.
+    ///
+    /// The second argument will contain the name of the unknown attribute.
+    DictionaryUnknownAttribute(RadiusDictionary, RadiusAttrKey),
+    /// Will be returned from the `export_dictionaries` if an exported
+    /// dictionary or the directory for it could not be written.
+    ///
+    /// The first argument will contain io::Error with the reason and the
+    /// second argument will contain path to the failed file or directory.
+    DictionaryWriteFailed(std::io::Error, RadiusDictionary),
+    /// Will be returned if the given value does not fit into a RADIUS
+    /// attribute.
+    ///
+    /// The first argument will contain type of the RADIUS attribute and the
+    /// second argument will contain length of the rejected value.
+    AttributeValueTooLong(RadiusAttrType, usize),
+    /// Will be returned from the `Attribute::set_tag` if the tag is out of
+    /// range `0..31` or the value of the attribute does not leave room for
+    /// it.
+    ///
+    /// The first argument will contain type of the RADIUS attribute and the
+    /// second argument will contain the rejected tag.
+    InvalidAttributeTag(RadiusAttrType, u8),
+    /// Will be returned from the `Attribute::decode` if the `Length` field of
+    /// a RADIUS attribute is less than the attribute header or points beyond
+    /// the end of the packet.
+    ///
+    /// The first argument will contain type of the RADIUS attribute and the
+    /// second argument will contain the invalid length.
+    InvalidAttributeLength(RadiusAttrType, u8),
+    /// Will be returned from the `Attribute::decode` if a Vendor-Specific
+    /// attribute contains vendor attribute with invalid `Vendor length`.
+    ///
+    /// The first argument will contain the vendor id, the second argument
+    /// will contain type of the vendor attribute and the third argument will
+    /// contain the invalid length.
+    InvalidVendorAttributeLength(RadiusVendorId, u32, usize),
+    /// Will be returned from the `VendorFormat::from_str` if the format of
+    /// Vendor-Specific attributes is not `<type width>,<length width>[,c]`
+    /// with the supported widths.
+    ///
+    /// The argument will contain the rejected format.
+    InvalidVendorFormat(String),
+    /// Will be returned from the `Attribute::decode` if an extended attribute
+    /// of RFC 6929 is truncated or the fragments of a long extended attribute
+    /// are not contiguous or not terminated.
+    ///
+    /// The first argument will contain type of the RADIUS attribute.
+    InvalidExtendedAttribute(RadiusAttrType),
+    /// Will be returned from the `Packet::decode` if the given buffer is
+    /// shorter than the RADIUS packet header or than the `Length` field of the
+    /// packet.
+    ///
+    /// The first argument will contain length of the given buffer.
+    PacketTooShort(usize),
+    /// Will be returned from the `Packet::decode` if the `Length` field of the
+    /// RADIUS packet is out of range `20..4096`.
+    ///
+    /// The first argument will contain the invalid length.
+    InvalidPacketLength(usize),
+    /// Will be returned from the `Packet::encode` if the encoded packet does
+    /// not fit into the maximum RADIUS packet length.
+    ///
+    /// The first argument will contain length of the encoded packet.
+    PacketTooLong(usize),
+    /// Will be returned from the `Packet::decode` if the `Code` field of the
+    /// RADIUS packet is unknown.
+    ///
+    /// The first argument will contain the unknown code.
+    UnknownPacketCode(u8),
+    /// Will be returned from the `load_dictionaries` if the `type` of a RADIUS
+    /// attribute could not be parsed.
+    ///
+    /// The first argument will contain the invalid type.
+    InvalidDataType(String),
+    /// Will be returned if the given value could not be converted to the type
+    /// of a RADIUS attribute.
+    ///
+    /// The first argument will contain the type of the RADIUS attribute and
+    /// the second argument will contain the rejected value.
+    InvalidAttributeValue(DataType, String),
+    /// Will be returned from the `AttributeValue::decode` if the length of
+    /// the received value does not match the type of a RADIUS attribute.
+    ///
+    /// The first argument will contain the type of the RADIUS attribute and
+    /// the second argument will contain the length of the received value.
+    InvalidAttributeValueLength(DataType, usize),
+    /// Will be returned from the `hide_user_password` if the given password
+    /// is longer than `128` octets and from the `hide_tunnel_password` if it
+    /// is longer than `239` octets.
+    ///
+    /// The first argument will contain length of the given password.
+    PasswordTooLong(usize),
+    /// Will be returned from the `unhide_user_password` if the length of the
+    /// given hidden password is not a multiple of `16` octets or out of range
+    /// `16..128`, and from the `unhide_tunnel_password` if the hidden
+    /// Tunnel-Password could not be recovered.
+    ///
+    /// The first argument will contain length of the given hidden password.
+    InvalidHiddenPassword(usize),
+    /// Will be returned from the `Packet::decode_response` if the Response
+    /// Authenticator of the reply does not match the request and the shared
+    /// secret.
+    ///
+    /// The first argument will contain the identifier of the reply.
+    InvalidResponseAuthenticator(u8),
+    /// Will be returned from the `Packet::decode_response` if the reply
+    /// contains Message-Authenticator that does not match the packet and the
+    /// shared secret.
+    ///
+    /// The first argument will contain the identifier of the reply.
+    InvalidMessageAuthenticator(u8),
+    /// Will be returned from the `Packet::decode_response` if the
+    /// Message-Authenticator is required but the reply does not contain it.
+    ///
+    /// The first argument will contain the identifier of the reply.
+    MissingMessageAuthenticator(u8),
 }
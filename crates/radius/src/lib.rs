@@ -6,16 +6,13 @@
 pub mod attribute;
 pub mod dictionary;
 pub mod error;
+pub mod md5;
+pub mod md5_batch;
+pub mod packet;
+pub mod password;
+pub mod tgpp;
+pub mod value;
 
-mod md5;
-
-use lazy_static::lazy_static;
-use std::collections::HashMap;
-use std::sync::{Mutex};
-
-lazy_static! {
-    pub static ref RADIUS_DICTIONARIES: Mutex<HashMap<String, attribute::Attribute>> = {
-        let mut m = HashMap::new();
-        Mutex::new(m)
-    };
-}
+mod random;
+#[cfg(test)]
+mod test_dir;
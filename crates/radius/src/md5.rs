@@ -1,94 +1,180 @@
-/// Implementation of MD5 Message-Digest Algorithm described in RFC 1321
-/// For more info see: https://www.ietf.org/rfc/rfc1321.txt
-
-pub fn md5(input: &[u8], output: &mut [u8]) {
-    const BLK_SIZE: usize = 64;
-    let len: usize = input.len();
-    let (mut a, mut b, mut c, mut d) = (0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476);
+//! Implementation of MD5 Message-Digest Algorithm described in RFC 1321
+//! For more info see: https://www.ietf.org/rfc/rfc1321.txt
+
+/// Size of a block processed by MD5.
+pub(crate) const BLK_SIZE: usize = 64;
+
+/// Size of MD5 digest.
+pub const HASH_SIZE: usize = 16;
+
+/// Initial state of MD5 buffer (RFC 1321 3.3).
+pub(crate) const INIT_STATE: (u32, u32, u32, u32) = (0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476);
+
+/// Incremental MD5 hasher.
+///
+/// Input is fed with `update` in as many parts as needed and only whole
+/// blocks are processed, so a digest over several buffers (e.g. a packet
+/// followed by a shared secret) doesn't require to concatenate them.
+///
+/// # Examples
+///
+/// ```
+/// use radius::md5::{md5, Md5, HASH_SIZE};
+///
+/// let mut hasher = Md5::new();
+/// hasher.update(b"message ");
+/// hasher.update(b"digest");
+///
+/// let mut digest: [u8; HASH_SIZE] = Default::default();
+/// md5(b"message digest", &mut digest);
+///
+/// assert_eq!(hasher.finalize(), digest);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Md5 {
+    state: (u32, u32, u32, u32),
+    buffer: [u8; BLK_SIZE],
+    buffered: usize,
+    length: u64,
+}
 
-    let blocks = (len / 64) + 1;
-    for blk in 0..blocks {
-        if blk == (blocks - 1)  {
-            let last_blk_len = len.wrapping_sub(blk * 64);
+impl Md5 {
+    pub fn new() -> Md5 {
+        Md5 {
+            state: INIT_STATE,
+            buffer: [0; BLK_SIZE],
+            buffered: 0,
+            length: 0,
+        }
+    }
 
-            if last_blk_len == 0 {
-                let mut block: Vec<u8> = vec![0; BLK_SIZE];
-                block[0] = 0x80;
+    /// Feeds the next part of the input to the hasher.
+    pub fn update(&mut self, mut input: &[u8]) {
+        self.length = self.length.wrapping_add(input.len() as u64);
 
-                put_u64_le(&mut block[56..], (len * 8) as u64);
-                (a, b, c, d) = process_block(&block, &(a, b, c, d));
+        // complete the block left from the previous call first
+        if self.buffered > 0 {
+            let n = input.len().min(BLK_SIZE - self.buffered);
+            self.buffer[self.buffered..self.buffered + n].copy_from_slice(&input[..n]);
+            self.buffered += n;
+            input = &input[n..];
 
-                continue;
+            if self.buffered < BLK_SIZE {
+                return;
             }
+            self.state = process_block(&self.buffer, &self.state);
+            self.buffered = 0;
+        }
 
-            if last_blk_len < 56 {
-                let mut block: Vec<u8> = vec![0; BLK_SIZE];
+        let blocks = input.chunks_exact(BLK_SIZE);
+        let remainder = blocks.remainder();
+        for block in blocks {
+            self.state = process_block(block.try_into().unwrap(), &self.state);
+        }
 
-                block[0..last_blk_len].clone_from_slice(&input[blk*64..blk*64+last_blk_len]);
-                block[last_blk_len] = 0x80;
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffered = remainder.len();
+    }
 
-                put_u64_le(&mut block[56..], (len * 8) as u64);
-                (a, b, c, d) = process_block(&block, &(a, b, c, d));
+    /// Pads the input (RFC 1321 3.1, 3.2) and returns the digest.
+    pub fn finalize(mut self) -> [u8; HASH_SIZE] {
+        let bit_length = self.length.wrapping_mul(8);
 
-                continue;
-            }
+        self.buffer[self.buffered] = 0x80;
+        self.buffer[self.buffered + 1..].fill(0);
 
-            if last_blk_len == 56 {
-                let mut block: Vec<u8> = vec![0; BLK_SIZE];
-                let mut additional_block: Vec<u8> = vec![0; BLK_SIZE];
+        // no room left for the length, so it goes to an additional block
+        if self.buffered >= BLK_SIZE - 8 {
+            self.state = process_block(&self.buffer, &self.state);
+            self.buffer.fill(0);
+        }
 
-                block[0..last_blk_len].clone_from_slice(&input[blk*64..blk*64+last_blk_len]);
-                block[last_blk_len] = 0x80;
-                (a, b, c, d) = process_block(&block, &(a, b, c, d));
+        put_u64_le(&mut self.buffer[BLK_SIZE - 8..], bit_length);
+        let (a, b, c, d) = process_block(&self.buffer, &self.state);
 
-                put_u64_le(&mut additional_block[56..], (len * 8) as u64);
-                (a, b, c, d) = process_block(&additional_block, &(a, b, c, d));
-                
-                continue;
-            }
+        let mut digest: [u8; HASH_SIZE] = Default::default();
+        digest[0..4].copy_from_slice(&a.to_le_bytes());
+        digest[4..8].copy_from_slice(&b.to_le_bytes());
+        digest[8..12].copy_from_slice(&c.to_le_bytes());
+        digest[12..16].copy_from_slice(&d.to_le_bytes());
+        digest
+    }
+}
 
-            if last_blk_len > 56 {
-                let mut block: Vec<u8> = vec![0; BLK_SIZE];
-                let mut additional_block: Vec<u8> = vec![0; BLK_SIZE];
-
-                block[0..last_blk_len].clone_from_slice(&input[blk*64..blk*64+last_blk_len]);
-                block[last_blk_len] = 0x80;
-                (a, b, c, d) = process_block(&block, &(a, b, c, d));
-                
-                put_u64_le(&mut additional_block[56..], (len * 8) as u64);
-                (a, b, c, d) = process_block(&additional_block, &(a, b, c, d));
-                
-                continue;                
-            }
+impl Default for Md5 {
+    fn default() -> Md5 {
+        Md5::new()
+    }
+}
+
+/// Calculates MD5 digest of the given `input` into the first 16 octets of
+/// the `output`.
+pub fn md5(input: &[u8], output: &mut [u8]) {
+    let mut hasher = Md5::new();
+    hasher.update(input);
+    output[..HASH_SIZE].copy_from_slice(&hasher.finalize());
+}
+
+/// Incremental HMAC-MD5 keyed hasher described in RFC 2104
+/// For more info see: https://www.ietf.org/rfc/rfc2104.txt
+///
+/// # Examples
+///
+/// ```
+/// use radius::md5::{hmac_md5, HmacMd5, HASH_SIZE};
+///
+/// let mut hasher = HmacMd5::new(b"Jefe");
+/// hasher.update(b"what do ya want ");
+/// hasher.update(b"for nothing?");
+///
+/// let mut digest: [u8; HASH_SIZE] = Default::default();
+/// hmac_md5(b"Jefe", b"what do ya want for nothing?", &mut digest);
+///
+/// assert_eq!(hasher.finalize(), digest);
+/// ```
+#[derive(Debug, Clone)]
+pub struct HmacMd5 {
+    inner: Md5,
+    outer: Md5,
+}
+
+impl HmacMd5 {
+    pub fn new(key: &[u8]) -> HmacMd5 {
+        // keys longer than the block size are hashed first
+        let mut k: [u8; BLK_SIZE] = [0; BLK_SIZE];
+        if key.len() > BLK_SIZE {
+            md5(key, &mut k[..HASH_SIZE]);
         } else {
-            (a, b, c, d) = process_block(&input[blk..(blk + BLK_SIZE as usize)].to_vec(), &(a, b, c, d));
+            k[..key.len()].copy_from_slice(key);
         }
+
+        let mut inner = Md5::new();
+        let mut outer = Md5::new();
+        inner.update(&k.map(|b| b ^ 0x36));
+        outer.update(&k.map(|b| b ^ 0x5c));
+
+        HmacMd5 { inner, outer }
+    }
+
+    /// Feeds the next part of the input to the hasher.
+    pub fn update(&mut self, input: &[u8]) {
+        self.inner.update(input);
     }
 
-    let aa = a.to_le_bytes();
-    let bb = b.to_le_bytes();
-    let cc = c.to_le_bytes();
-    let dd = d.to_le_bytes();
-
-    output[0] = aa[0];
-    output[1] = aa[1];
-    output[2] = aa[2];
-    output[3] = aa[3];
-
-    output[4] = bb[0];
-    output[5] = bb[1];
-    output[6] = bb[2];
-    output[7] = bb[3];
-
-    output[8] = cc[0];
-    output[9] = cc[1];
-    output[10] = cc[2];
-    output[11] = cc[3];
-
-    output[12] = dd[0];
-    output[13] = dd[1];
-    output[14] = dd[2];
-    output[15] = dd[3];
+    /// Returns the message authentication code of the input.
+    pub fn finalize(self) -> [u8; HASH_SIZE] {
+        let mut outer = self.outer;
+        outer.update(&self.inner.finalize());
+        outer.finalize()
+    }
+}
+
+/// Calculates HMAC-MD5 of the given `input` keyed with the `key` into the
+/// first 16 octets of the `output`.
+pub fn hmac_md5(key: &[u8], input: &[u8], output: &mut [u8]) {
+    let mut hasher = HmacMd5::new(key);
+    hasher.update(input);
+    output[..HASH_SIZE].copy_from_slice(&hasher.finalize());
 }
 
 fn f(x: u32, y: u32, z: u32) -> u32 {
@@ -122,7 +208,7 @@ fn put_u64_le(arr: &mut [u8], value: u64) {
     arr[7] = (value >> 56) as u8;
 }
 
-fn process_block(block: &Vec<u8>, state: &(u32, u32, u32, u32)) -> (u32, u32, u32, u32) {
+pub(crate) fn process_block(block: &[u8; BLK_SIZE], state: &(u32, u32, u32, u32)) -> (u32, u32, u32, u32) {
     let (mut a, mut b, mut c, mut d) = state;
     let (aa, bb, cc, dd) = state;
 
@@ -560,8 +646,6 @@ fn process_block(block: &Vec<u8>, state: &(u32, u32, u32, u32)) -> (u32, u32, u3
 mod md5_tests {
     use super::*;
 
-    const HASH_SIZE: usize = 16;
-    
     fn slice_to_hex_digest(data: &[u8]) -> String {
         format!("{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
                 data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7], data[8],
@@ -607,5 +691,67 @@ mod md5_tests {
         test = "6e327dc9ad79694a191ed07bc48a69ed";
         md5("12345678901234567890123456789012345678901234567890123456789012305".as_ref(), &mut res);
         assert_eq!(test, slice_to_hex_digest(&res));        
+
+        test = "57edf4a22be3c955ac49da2e2107b67a";
+        md5("12345678901234567890123456789012345678901234567890123456789012345678901234567890".as_ref(), &mut res);
+        assert_eq!(test, slice_to_hex_digest(&res));
+
+        test = "7707d6ae4e027c70eea2a935c2296f21";
+        md5(&[b'a'; 1000000], &mut res);
+        assert_eq!(test, slice_to_hex_digest(&res));
+    }
+
+    #[test]
+    fn hmac_md5_test() {
+        let mut res: [u8; HASH_SIZE] = Default::default();
+
+        hmac_md5(&[0x0b; 16], "Hi There".as_ref(), &mut res);
+        assert_eq!("9294727a3638bb1c13f48ef8158bfc9d", slice_to_hex_digest(&res));
+
+        hmac_md5("Jefe".as_ref(), "what do ya want for nothing?".as_ref(), &mut res);
+        assert_eq!("750c783e6ab0b503eaa86e310a5db738", slice_to_hex_digest(&res));
+
+        hmac_md5(&[0xaa; 16], &[0xdd; 50], &mut res);
+        assert_eq!("56be34521d144c88dbb8c733f0e8b3f6", slice_to_hex_digest(&res));
+
+        hmac_md5(&[0xaa; 80], "Test Using Larger Than Block-Size Key - Hash Key First".as_ref(), &mut res);
+        assert_eq!("6b1ab7fe4bd7bf8f0b62e6ce61b9d0cd", slice_to_hex_digest(&res));
+    }
+
+    #[test]
+    fn md5_update_test() {
+        let input = [b'x'; 200];
+        let mut expected: [u8; HASH_SIZE] = Default::default();
+
+        for len in [0, 1, 55, 56, 57, 63, 64, 65, 119, 120, 128, 200] {
+            md5(&input[..len], &mut expected);
+
+            // every split point has to give the same digest as one-shot md5
+            for split in 0..=len {
+                let mut hasher = Md5::new();
+                hasher.update(&input[..split]);
+                hasher.update(&input[split..len]);
+                assert_eq!(hasher.finalize(), expected);
+            }
+
+            let mut hasher = Md5::new();
+            for b in input[..len].chunks(1) {
+                hasher.update(b);
+            }
+            assert_eq!(hasher.finalize(), expected);
+        }
+    }
+
+    #[test]
+    fn hmac_md5_update_test() {
+        let mut hasher = HmacMd5::new(&[0xaa; 80]);
+        hasher.update("Test Using Larger Than Block-Size Key ".as_ref());
+        hasher.update("and Larger Than One Block-Size Data".as_ref());
+        assert_eq!("6f630fad67cda0ee1fb1f562db3aa53e", slice_to_hex_digest(&hasher.finalize()));
+
+        let mut hasher = HmacMd5::new(&[0x0c; 16]);
+        hasher.update("Test With ".as_ref());
+        hasher.update("Truncation".as_ref());
+        assert_eq!("56461ef2342edc00f9bab995690efd4c", slice_to_hex_digest(&hasher.finalize()));
     }
 }
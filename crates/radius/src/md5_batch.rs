@@ -0,0 +1,437 @@
+//! Multi-buffer implementation of MD5 that hashes several independent
+//! messages at once.
+//!
+//! Every lane of a SIMD register carries the state of a separate message, so
+//! a batch of packets is hashed with the same number of instructions as a
+//! single packet. SSE2 (4 lanes) and AVX2 (8 lanes) are used on x86_64 when
+//! the CPU supports them, all other targets use the scalar implementation
+//! from `radius::md5`.
+use super::md5::{process_block, BLK_SIZE, HASH_SIZE, INIT_STATE};
+
+/// Maximum number of lanes of all the backends.
+const MAX_LANES: usize = 8;
+
+/// Implementation used to process blocks of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Sse2,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+}
+
+impl Backend {
+    // Returns the fastest backend supported by the current CPU.
+    fn detect() -> Backend {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return Backend::Avx2;
+            }
+            if is_x86_feature_detected!("sse2") {
+                return Backend::Sse2;
+            }
+        }
+
+        Backend::Scalar
+    }
+
+    fn lanes(&self) -> usize {
+        match self {
+            Backend::Scalar => 1,
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse2 => 4,
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => 8,
+        }
+    }
+
+    // Processes one block of every lane. Both `states` and `blocks` have
+    // exactly `lanes()` items.
+    fn process(&self, states: &mut [[u32; 4]], blocks: &[[u8; BLK_SIZE]]) {
+        match self {
+            Backend::Scalar => {
+                for (state, block) in states.iter_mut().zip(blocks) {
+                    let (a, b, c, d) =
+                        process_block(block, &(state[0], state[1], state[2], state[3]));
+                    *state = [a, b, c, d];
+                }
+            }
+            // the backends are selected only if the CPU supports them
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse2 => unsafe { x86::process_sse2(states, blocks) },
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => unsafe { x86::process_avx2(states, blocks) },
+        }
+    }
+}
+
+// Iterates over the padded blocks (RFC 1321 3.1, 3.2) of a message that
+// consists of several parts.
+struct Blocks<'a> {
+    parts: &'a [&'a [u8]],
+    part: usize,
+    offset: usize,
+    length: usize,
+    block: usize,
+    blocks: usize,
+}
+
+impl<'a> Blocks<'a> {
+    fn new(parts: &'a [&'a [u8]]) -> Blocks<'a> {
+        let length: usize = parts.iter().map(|part| part.len()).sum();
+
+        Blocks {
+            parts,
+            part: 0,
+            offset: 0,
+            length,
+            block: 0,
+            // the 0x80 octet and 64-bit length follow the message
+            blocks: (length + 8) / BLK_SIZE + 1,
+        }
+    }
+
+    // Fills the `buf` with the next block of the message. Returns false if
+    // all the blocks were already returned.
+    fn next_block(&mut self, buf: &mut [u8; BLK_SIZE]) -> bool {
+        if self.block == self.blocks {
+            return false;
+        }
+
+        let start = self.block * BLK_SIZE;
+        let mut pos = 0;
+        while pos < BLK_SIZE && self.part < self.parts.len() {
+            let part = &self.parts[self.part][self.offset..];
+            let n = part.len().min(BLK_SIZE - pos);
+            buf[pos..pos + n].copy_from_slice(&part[..n]);
+            pos += n;
+
+            self.offset += n;
+            if self.offset == self.parts[self.part].len() {
+                self.part += 1;
+                self.offset = 0;
+            }
+        }
+        buf[pos..].fill(0);
+
+        if start + pos == self.length && pos < BLK_SIZE {
+            buf[pos] = 0x80;
+        }
+        self.block += 1;
+        if self.block == self.blocks {
+            buf[BLK_SIZE - 8..].copy_from_slice(&((self.length as u64) * 8).to_le_bytes());
+        }
+
+        true
+    }
+}
+
+/// Calculates MD5 digests of a batch of independent messages.
+///
+/// Every message is given as a list of parts which are hashed as if they
+/// were concatenated, e.g. a packet followed by the shared secret. Digests
+/// are returned in the order of the messages.
+///
+/// # Examples
+///
+/// ```
+/// use radius::md5::{md5, HASH_SIZE};
+/// use radius::md5_batch::md5_batch;
+///
+/// let digests = md5_batch(&[&[b"message ", b"digest"], &[b"abc"]]);
+///
+/// let mut digest: [u8; HASH_SIZE] = Default::default();
+/// md5(b"message digest", &mut digest);
+/// assert_eq!(digests[0], digest);
+///
+/// md5(b"abc", &mut digest);
+/// assert_eq!(digests[1], digest);
+/// ```
+pub fn md5_batch(messages: &[&[&[u8]]]) -> Vec<[u8; HASH_SIZE]> {
+    md5_batch_with(Backend::detect(), messages)
+}
+
+fn md5_batch_with(backend: Backend, messages: &[&[&[u8]]]) -> Vec<[u8; HASH_SIZE]> {
+    let lanes = backend.lanes();
+    let init = [INIT_STATE.0, INIT_STATE.1, INIT_STATE.2, INIT_STATE.3];
+    let mut digests: Vec<[u8; HASH_SIZE]> = Vec::with_capacity(messages.len());
+
+    let mut states = [[0u32; 4]; MAX_LANES];
+    let mut blocks = [[0u8; BLK_SIZE]; MAX_LANES];
+
+    for group in messages.chunks(lanes) {
+        let mut iters: Vec<Blocks> = group.iter().map(|parts| Blocks::new(parts)).collect();
+        states[..lanes].fill(init);
+
+        loop {
+            // lanes of the messages which are already hashed (and the unused
+            // lanes of the last group) process a dummy block and keep the
+            // state they had before
+            let mut active = [false; MAX_LANES];
+            for (i, iter) in iters.iter_mut().enumerate() {
+                active[i] = iter.next_block(&mut blocks[i]);
+            }
+            if !active.contains(&true) {
+                break;
+            }
+
+            let saved = states;
+            backend.process(&mut states[..lanes], &blocks[..lanes]);
+            for i in 0..lanes {
+                if !active[i] {
+                    states[i] = saved[i];
+                }
+            }
+        }
+
+        for state in &states[..group.len()] {
+            let mut digest: [u8; HASH_SIZE] = Default::default();
+            for (chunk, word) in digest.chunks_exact_mut(4).zip(state) {
+                chunk.copy_from_slice(&word.to_le_bytes());
+            }
+            digests.push(digest);
+        }
+    }
+
+    digests
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::super::md5::BLK_SIZE;
+    use std::arch::x86_64::*;
+
+    // Additive constants of the steps (RFC 1321 3.4).
+    const K: [u32; 64] = [
+        0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613,
+        0xfd469501, 0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193,
+        0xa679438e, 0x49b40821, 0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d,
+        0x02441453, 0xd8a1e681, 0xe7d3fbc8, 0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed,
+        0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a, 0xfffa3942, 0x8771f681, 0x6d9d6122,
+        0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70, 0x289b7ec6, 0xeaa127fa,
+        0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665, 0xf4292244,
+        0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+        0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb,
+        0xeb86d391,
+    ];
+
+    // Rotation amounts of the steps of every round.
+    const S: [[i32; 4]; 4] = [
+        [7, 12, 17, 22],
+        [5, 9, 14, 20],
+        [4, 11, 16, 23],
+        [6, 10, 15, 21],
+    ];
+
+    // Generates a function that processes one block of every lane with the
+    // given vector type and intrinsics.
+    macro_rules! process_lanes {
+        ($name:ident, $feature:literal, $lanes:expr, $vec:ty,
+         load = $load:ident, store = $store:ident, set1 = $set1:ident,
+         add = $add:ident, and = $and:ident, andnot = $andnot:ident,
+         or = $or:ident, xor = $xor:ident, sll = $sll:ident, srl = $srl:ident) => {
+            #[target_feature(enable = $feature)]
+            pub unsafe fn $name(states: &mut [[u32; 4]], blocks: &[[u8; BLK_SIZE]]) {
+                const LANES: usize = $lanes;
+
+                #[inline(always)]
+                unsafe fn gather(words: [u32; LANES]) -> $vec {
+                    $load(words.as_ptr() as *const $vec)
+                }
+
+                #[inline(always)]
+                unsafe fn rotl(x: $vec, s: i32) -> $vec {
+                    $or(
+                        $sll(x, _mm_cvtsi32_si128(s)),
+                        $srl(x, _mm_cvtsi32_si128(32 - s)),
+                    )
+                }
+
+                let mut w: [$vec; 16] = [$set1(0); 16];
+                for (i, w) in w.iter_mut().enumerate() {
+                    let mut words = [0u32; LANES];
+                    for (word, block) in words.iter_mut().zip(blocks) {
+                        *word = u32::from_le_bytes(block[i * 4..i * 4 + 4].try_into().unwrap());
+                    }
+                    *w = gather(words);
+                }
+
+                let mut init: [$vec; 4] = [$set1(0); 4];
+                for (i, v) in init.iter_mut().enumerate() {
+                    let mut words = [0u32; LANES];
+                    for (word, state) in words.iter_mut().zip(states.iter()) {
+                        *word = state[i];
+                    }
+                    *v = gather(words);
+                }
+
+                let ones = $set1(-1);
+                let [mut a, mut b, mut c, mut d] = init;
+
+                for step in 0..64 {
+                    let round = step / 16;
+                    let (f, g) = match round {
+                        // F(b,c,d) = (b & c) | (!b & d)
+                        0 => ($or($and(b, c), $andnot(b, d)), step),
+                        // G(b,c,d) = (b & d) | (c & !d)
+                        1 => ($or($and(b, d), $andnot(d, c)), (5 * step + 1) % 16),
+                        // H(b,c,d) = b ^ c ^ d
+                        2 => ($xor($xor(b, c), d), (3 * step + 5) % 16),
+                        // I(b,c,d) = c ^ (b | !d)
+                        _ => ($xor(c, $or(b, $xor(d, ones))), (7 * step) % 16),
+                    };
+
+                    let sum = $add($add(a, f), $add($set1(K[step] as i32), w[g]));
+                    a = d;
+                    d = c;
+                    c = b;
+                    b = $add(b, rotl(sum, S[round][step % 4]));
+                }
+
+                for (i, v) in [a, b, c, d].into_iter().enumerate() {
+                    let mut words = [0u32; LANES];
+                    $store(words.as_mut_ptr() as *mut $vec, $add(v, init[i]));
+                    for (word, state) in words.iter().zip(states.iter_mut()) {
+                        state[i] = *word;
+                    }
+                }
+            }
+        };
+    }
+
+    process_lanes!(
+        process_sse2,
+        "sse2",
+        4,
+        __m128i,
+        load = _mm_loadu_si128,
+        store = _mm_storeu_si128,
+        set1 = _mm_set1_epi32,
+        add = _mm_add_epi32,
+        and = _mm_and_si128,
+        andnot = _mm_andnot_si128,
+        or = _mm_or_si128,
+        xor = _mm_xor_si128,
+        sll = _mm_sll_epi32,
+        srl = _mm_srl_epi32
+    );
+
+    process_lanes!(
+        process_avx2,
+        "avx2",
+        8,
+        __m256i,
+        load = _mm256_loadu_si256,
+        store = _mm256_storeu_si256,
+        set1 = _mm256_set1_epi32,
+        add = _mm256_add_epi32,
+        and = _mm256_and_si256,
+        andnot = _mm256_andnot_si256,
+        or = _mm256_or_si256,
+        xor = _mm256_xor_si256,
+        sll = _mm256_sll_epi32,
+        srl = _mm256_srl_epi32
+    );
+}
+
+#[cfg(test)]
+mod md5_batch_tests {
+    use super::*;
+    use crate::md5::md5;
+
+    fn backends() -> Vec<Backend> {
+        let mut backends = vec![Backend::Scalar];
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("sse2") {
+                backends.push(Backend::Sse2);
+            }
+            if is_x86_feature_detected!("avx2") {
+                backends.push(Backend::Avx2);
+            }
+        }
+
+        backends
+    }
+
+    #[test]
+    fn md5_batch_test() {
+        let input: Vec<u8> = (0..=255).cycle().take(300).collect();
+        let secret = b"xyzzy5461";
+
+        // messages of different length cross the block boundaries at
+        // different steps, so lanes of a group finish at different rounds
+        let lengths = [
+            0, 1, 3, 47, 55, 56, 63, 64, 65, 119, 120, 128, 200, 300, 20, 38, 0,
+        ];
+        let messages: Vec<[&[u8]; 2]> = lengths
+            .iter()
+            .map(|len| [&input[..*len], &secret[..]])
+            .collect();
+        let messages: Vec<&[&[u8]]> = messages.iter().map(|m| &m[..]).collect();
+
+        let mut expected: Vec<[u8; HASH_SIZE]> = Vec::new();
+        for len in lengths {
+            let mut data = input[..len].to_vec();
+            data.extend_from_slice(secret);
+
+            let mut digest: [u8; HASH_SIZE] = Default::default();
+            md5(&data, &mut digest);
+            expected.push(digest);
+        }
+
+        for backend in backends() {
+            assert_eq!(
+                md5_batch_with(backend, &messages),
+                expected,
+                "{:?}",
+                backend
+            );
+            assert_eq!(
+                md5_batch_with(backend, &messages[..3]),
+                expected[..3],
+                "{:?}",
+                backend
+            );
+        }
+
+        assert_eq!(md5_batch(&messages), expected);
+        assert!(md5_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn rfc1321_batch_test() {
+        let messages: [&[&[u8]]; 7] = [
+            &[b""],
+            &[b"a"],
+            &[b"abc"],
+            &[b"message digest"],
+            &[b"abcdefghijklm", b"nopqrstuvwxyz"],
+            &[b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789"],
+            &[
+                b"1234567890123456789012345678901234567890",
+                b"1234567890123456789012345678901234567890",
+            ],
+        ];
+        let expected = [
+            "d41d8cd98f00b204e9800998ecf8427e",
+            "0cc175b9c0f1b6a831c399e269772661",
+            "900150983cd24fb0d6963f7d28e17f72",
+            "f96b697d7cb7938d525a2f31aaf161d0",
+            "c3fcd3d76192e4007dfb496cca67e13b",
+            "d174ab98d277d9f5a5611c2c9f419d9f",
+            "57edf4a22be3c955ac49da2e2107b67a",
+        ];
+
+        for backend in backends() {
+            let digests = md5_batch_with(backend, &messages);
+            for (digest, expected) in digests.iter().zip(expected) {
+                let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+                assert_eq!(hex, expected, "{:?}", backend);
+            }
+        }
+    }
+}
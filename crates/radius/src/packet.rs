@@ -0,0 +1,882 @@
+/// Defines the types and auxilary functions to represent and work with
+/// RADIUS packets.
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::attribute::{
+    Attribute, VendorFormats, CHAP_CHALLENGE_TYPE, CHAP_PASSWORD_TYPE, MESSAGE_AUTHENTICATOR_TYPE,
+};
+use super::error::RadiusError;
+use super::md5::{hmac_md5, HmacMd5, Md5};
+use super::md5_batch::md5_batch;
+use super::password::chap_password;
+use super::random;
+
+/// Size of the RADIUS packet header (Code, Identifier, Length and
+/// Authenticator fields).
+pub const HEADER_LENGTH: usize = 20;
+
+/// Size of the `Authenticator` field of a RADIUS packet.
+pub const AUTHENTICATOR_LENGTH: usize = 16;
+
+/// Maximum length of a RADIUS packet according to RFC 2865 3.
+pub const MAX_PACKET_LENGTH: usize = 4096;
+
+/// RADIUS packet codes from RFC 2865, RFC 2866, RFC 5997 and RFC 5176.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Code {
+    AccessRequest = 1,
+    AccessAccept = 2,
+    AccessReject = 3,
+    AccountingRequest = 4,
+    AccountingResponse = 5,
+    AccessChallenge = 11,
+    StatusServer = 12,
+    StatusClient = 13,
+    DisconnectRequest = 40,
+    DisconnectAck = 41,
+    DisconnectNak = 42,
+    CoARequest = 43,
+    CoAAck = 44,
+    CoANak = 45,
+}
+
+impl Code {
+    /// Returns `true` if the Request Authenticator of packets with this code
+    /// is a random number (RFC 2865 3, RFC 5997 3).
+    #[inline]
+    pub fn has_random_authenticator(&self) -> bool {
+        matches!(self, Code::AccessRequest | Code::StatusServer)
+    }
+
+    /// Returns `true` if the Request Authenticator of packets with this code
+    /// is an MD5 hash over the packet and the shared secret (RFC 2866 3,
+    /// RFC 5176 2.3).
+    #[inline]
+    pub fn has_hashed_authenticator(&self) -> bool {
+        matches!(
+            self,
+            Code::AccountingRequest | Code::DisconnectRequest | Code::CoARequest
+        )
+    }
+}
+
+impl TryFrom<u8> for Code {
+    type Error = RadiusError;
+
+    fn try_from(code: u8) -> Result<Code, RadiusError> {
+        match code {
+            1 => Ok(Code::AccessRequest),
+            2 => Ok(Code::AccessAccept),
+            3 => Ok(Code::AccessReject),
+            4 => Ok(Code::AccountingRequest),
+            5 => Ok(Code::AccountingResponse),
+            11 => Ok(Code::AccessChallenge),
+            12 => Ok(Code::StatusServer),
+            13 => Ok(Code::StatusClient),
+            40 => Ok(Code::DisconnectRequest),
+            41 => Ok(Code::DisconnectAck),
+            42 => Ok(Code::DisconnectNak),
+            43 => Ok(Code::CoARequest),
+            44 => Ok(Code::CoAAck),
+            45 => Ok(Code::CoANak),
+            _ => Err(RadiusError::UnknownPacketCode(code)),
+        }
+    }
+}
+
+impl fmt::Display for Code {
+    /// Formats the code with its name from the RFCs, like `Access-Accept`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Code::AccessRequest => "Access-Request",
+            Code::AccessAccept => "Access-Accept",
+            Code::AccessReject => "Access-Reject",
+            Code::AccountingRequest => "Accounting-Request",
+            Code::AccountingResponse => "Accounting-Response",
+            Code::AccessChallenge => "Access-Challenge",
+            Code::StatusServer => "Status-Server",
+            Code::StatusClient => "Status-Client",
+            Code::DisconnectRequest => "Disconnect-Request",
+            Code::DisconnectAck => "Disconnect-ACK",
+            Code::DisconnectNak => "Disconnect-NAK",
+            Code::CoARequest => "CoA-Request",
+            Code::CoAAck => "CoA-ACK",
+            Code::CoANak => "CoA-NAK",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// According to the RFC 2865 the format of the RADIUS packet is:
+///
+///    0                   1                   2                   3
+///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |     Code      |  Identifier   |            Length             |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |                                                               |
+///   |                         Authenticator                         |
+///   |                                                               |
+///   |                                                               |
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///   |  Attributes ...
+///   +-+-+-+-+-+-+-+-+-+-+-+-+-
+///
+/// The `Length` field is not stored within the `Packet` and calculated
+/// during encoding.
+#[derive(Debug, Clone)]
+pub struct Packet {
+    code: Code,
+    identifier: u8,
+    authenticator: [u8; AUTHENTICATOR_LENGTH],
+    attributes: Vec<Attribute>,
+}
+
+impl Packet {
+    /// Creates new `Packet` without attributes.
+    ///
+    /// Access-Request and Status-Server packets get a random Request
+    /// Authenticator, the authenticator of all other packets is zeroed and
+    /// calculated during `encode_request`/`encode_response`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::packet::{Code, Packet};
+    ///
+    /// let packet: Packet = Packet::new(Code::AccessRequest, 1);
+    /// assert_ne!(packet.authenticator(), &[0; 16]);
+    /// ```
+    #[inline]
+    pub fn new(code: Code, identifier: u8) -> Packet {
+        let mut authenticator = [0; AUTHENTICATOR_LENGTH];
+        if code.has_random_authenticator() {
+            random::fill(&mut authenticator);
+        }
+
+        Packet {
+            code,
+            identifier,
+            authenticator,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Returns the code of the packet.
+    #[inline]
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    /// Returns the identifier of the packet.
+    #[inline]
+    pub fn identifier(&self) -> u8 {
+        self.identifier
+    }
+
+    /// Sets the identifier of the packet.
+    #[inline]
+    pub fn set_identifier(&mut self, identifier: u8) {
+        self.identifier = identifier;
+    }
+
+    /// Returns the authenticator of the packet.
+    #[inline]
+    pub fn authenticator(&self) -> &[u8; AUTHENTICATOR_LENGTH] {
+        &self.authenticator
+    }
+
+    /// Sets the authenticator of the packet.
+    #[inline]
+    pub fn set_authenticator(&mut self, authenticator: [u8; AUTHENTICATOR_LENGTH]) {
+        self.authenticator = authenticator;
+    }
+
+    /// Returns the attributes of the packet in the order they were added or
+    /// decoded.
+    #[inline]
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    /// Appends the given `attribute` to the packet.
+    #[inline]
+    pub fn add_attribute(&mut self, attribute: Attribute) {
+        self.attributes.push(attribute);
+    }
+
+    /// Returns the Message-Authenticator attribute of the packet if any.
+    pub fn message_authenticator(&self) -> Option<&Attribute> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.attr_type() == MESSAGE_AUTHENTICATOR_TYPE)
+    }
+
+    /// Adds the Message-Authenticator attribute described in RFC 3579 3.2
+    /// to the packet if it is not there yet.
+    ///
+    /// The attribute is added as the first attribute of the packet and its
+    /// value is calculated during `encode_request`/`encode_response`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::packet::{Code, Packet};
+    ///
+    /// let mut packet = Packet::new(Code::AccessRequest, 1);
+    /// packet.add_message_authenticator();
+    ///
+    /// let buf = packet.encode_request(b"secret").unwrap();
+    /// assert_eq!(&buf[20..22], &[80, 18]);
+    /// assert_ne!(&buf[22..38], &[0; 16]);
+    /// ```
+    pub fn add_message_authenticator(&mut self) {
+        if self.message_authenticator().is_some() {
+            return;
+        }
+
+        let mut attr = Attribute::new(MESSAGE_AUTHENTICATOR_TYPE, None);
+        // value of the Message-Authenticator always fits into an attribute
+        attr.set_value(&[0; AUTHENTICATOR_LENGTH]).unwrap();
+        self.attributes.insert(0, attr);
+    }
+
+    /// Adds the CHAP-Password attribute described in RFC 2865 5.3 calculated
+    /// from the given cleartext `password` to the packet.
+    ///
+    /// The CHAP Ident is random and the challenge is the value of the
+    /// CHAP-Challenge attribute of the packet or the Request Authenticator if
+    /// the packet has no CHAP-Challenge, so the CHAP-Challenge should be added
+    /// before this call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::packet::{Code, Packet};
+    /// use radius::password::chap_password;
+    ///
+    /// let mut packet = Packet::new(Code::AccessRequest, 1);
+    /// packet.add_chap_password(b"password");
+    ///
+    /// let chap = &packet.attributes()[0];
+    /// let ident = chap.value()[0];
+    /// assert_eq!(chap.attr_type(), 3);
+    /// assert_eq!(chap.value(), chap_password(ident, b"password", packet.authenticator()));
+    /// ```
+    pub fn add_chap_password(&mut self, password: &[u8]) {
+        let mut ident = [0; 1];
+        random::fill(&mut ident);
+
+        let challenge = self
+            .attributes
+            .iter()
+            .find(|attr| attr.attr_type() == CHAP_CHALLENGE_TYPE)
+            .map_or(&self.authenticator[..], |attr| attr.value());
+        let value = chap_password(ident[0], password, challenge);
+
+        let mut attr = Attribute::new(CHAP_PASSWORD_TYPE, None);
+        // value of the CHAP-Password always fits into an attribute
+        attr.set_value(&value).unwrap();
+        self.attributes.push(attr);
+    }
+
+    /// Encodes the packet into its wire representation.
+    ///
+    /// The authenticator is written as is, so it should be set before the
+    /// packet is encoded. Returns `RadiusError::PacketTooLong` if the encoded
+    /// packet does not fit into `4096` octets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::attribute::Attribute;
+    /// use radius::packet::{Code, Packet};
+    ///
+    /// let mut user_name = Attribute::new(1, None);
+    /// user_name.set_value(b"user").unwrap();
+    ///
+    /// let mut packet = Packet::new(Code::AccessRequest, 42);
+    /// packet.add_attribute(user_name);
+    ///
+    /// let buf = packet.encode().unwrap();
+    /// assert_eq!(buf.len(), 26);
+    /// assert_eq!(&buf[0..4], &[1, 42, 0, 26]);
+    /// ```
+    pub fn encode(&self) -> Result<Vec<u8>, RadiusError> {
+        let length = HEADER_LENGTH
+            + self
+                .attributes
+                .iter()
+                .map(|attr| attr.encoded_length())
+                .sum::<usize>();
+
+        if length > MAX_PACKET_LENGTH {
+            return Err(RadiusError::PacketTooLong(length));
+        }
+
+        let mut buf: Vec<u8> = Vec::with_capacity(length);
+        buf.push(self.code as u8);
+        buf.push(self.identifier);
+        buf.extend_from_slice(&(length as u16).to_be_bytes());
+        buf.extend_from_slice(&self.authenticator);
+
+        for attr in &self.attributes {
+            attr.encode(&mut buf);
+        }
+
+        Ok(buf)
+    }
+
+    /// Encodes the packet as a request to a RADIUS server.
+    ///
+    /// For Accounting-Request, Disconnect-Request and CoA-Request packets the
+    /// Request Authenticator is calculated according to RFC 2866 3:
+    ///
+    ///   RequestAuth = MD5(Code+Identifier+Length+16 zero octets+Attributes+Secret)
+    ///
+    /// and stored within the packet, so the reply could be verified later.
+    /// For all other packets the authenticator is written as is.
+    ///
+    /// If the packet contains the Message-Authenticator attribute, its value
+    /// is calculated as HMAC-MD5 over the packet before the Request
+    /// Authenticator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::packet::{Code, Packet};
+    ///
+    /// let mut packet = Packet::new(Code::AccountingRequest, 1);
+    /// let buf = packet.encode_request(b"secret").unwrap();
+    ///
+    /// assert_eq!(&buf[4..20], packet.authenticator());
+    /// ```
+    pub fn encode_request(&mut self, secret: &[u8]) -> Result<Vec<u8>, RadiusError> {
+        if self.code.has_hashed_authenticator() {
+            self.authenticator = [0; AUTHENTICATOR_LENGTH];
+        }
+
+        let mut buf = self.encode()?;
+        self.sign_message_authenticator(&mut buf, secret);
+
+        if self.code.has_hashed_authenticator() {
+            self.authenticator = hash_authenticator(&buf, secret);
+            buf[4..HEADER_LENGTH].copy_from_slice(&self.authenticator);
+        }
+
+        Ok(buf)
+    }
+
+    /// Encodes a batch of requests the same way as `encode_request` does.
+    ///
+    /// Request Authenticators of all the packets that need it are calculated
+    /// at once with the multi-buffer MD5, which is noticeably faster than
+    /// encoding the packets one by one when many requests are flushed
+    /// together. Encoded packets are returned in the order of `packets`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::packet::{Code, Packet};
+    ///
+    /// let mut packets = vec![
+    ///     Packet::new(Code::AccountingRequest, 1),
+    ///     Packet::new(Code::AccessRequest, 2),
+    /// ];
+    /// let bufs = Packet::encode_requests(&mut packets, b"secret").unwrap();
+    ///
+    /// let mut single = Packet::new(Code::AccountingRequest, 1);
+    /// assert_eq!(bufs[0], single.encode_request(b"secret").unwrap());
+    /// assert_eq!(&bufs[1][4..20], packets[1].authenticator());
+    /// ```
+    pub fn encode_requests(
+        packets: &mut [Packet],
+        secret: &[u8],
+    ) -> Result<Vec<Vec<u8>>, RadiusError> {
+        let mut bufs: Vec<Vec<u8>> = Vec::with_capacity(packets.len());
+        for packet in packets.iter_mut() {
+            if packet.code.has_hashed_authenticator() {
+                packet.authenticator = [0; AUTHENTICATOR_LENGTH];
+            }
+
+            let mut buf = packet.encode()?;
+            packet.sign_message_authenticator(&mut buf, secret);
+            bufs.push(buf);
+        }
+
+        let hashed: Vec<usize> = (0..packets.len())
+            .filter(|i| packets[*i].code.has_hashed_authenticator())
+            .collect();
+        let messages: Vec<[&[u8]; 2]> = hashed.iter().map(|i| [&bufs[*i][..], secret]).collect();
+        let messages: Vec<&[&[u8]]> = messages.iter().map(|m| &m[..]).collect();
+        let authenticators = md5_batch(&messages);
+
+        for (i, authenticator) in hashed.into_iter().zip(authenticators) {
+            packets[i].authenticator = authenticator;
+            bufs[i][4..HEADER_LENGTH].copy_from_slice(&authenticator);
+        }
+
+        Ok(bufs)
+    }
+
+    /// Encodes the packet as a reply to the request with the given
+    /// `request_authenticator`.
+    ///
+    /// The Response Authenticator is calculated according to RFC 2865 3:
+    ///
+    ///   ResponseAuth = MD5(Code+ID+Length+RequestAuth+Attributes+Secret)
+    ///
+    /// and stored within the packet. If the packet contains the
+    /// Message-Authenticator attribute, its value is calculated with the
+    /// Request Authenticator before the Response Authenticator.
+    pub fn encode_response(
+        &mut self,
+        request_authenticator: &[u8; AUTHENTICATOR_LENGTH],
+        secret: &[u8],
+    ) -> Result<Vec<u8>, RadiusError> {
+        self.authenticator = *request_authenticator;
+        let mut buf = self.encode()?;
+        self.sign_message_authenticator(&mut buf, secret);
+
+        self.authenticator = hash_authenticator(&buf, secret);
+        buf[4..HEADER_LENGTH].copy_from_slice(&self.authenticator);
+        Ok(buf)
+    }
+
+    /// Decodes a reply to the request with the given `request_authenticator`
+    /// from the given `buf` and verifies its Response Authenticator and
+    /// Message-Authenticator.
+    ///
+    /// Returns `RadiusError::InvalidResponseAuthenticator` if the Response
+    /// Authenticator does not match the request or the shared `secret` and
+    /// `RadiusError::InvalidMessageAuthenticator` if the reply contains
+    /// Message-Authenticator with invalid value. If the
+    /// `require_message_authenticator` is set, replies without
+    /// Message-Authenticator are rejected with
+    /// `RadiusError::MissingMessageAuthenticator`.
+    ///
+    /// Vendor-Specific attributes are decoded according to the given vendor
+    /// `formats` (see `Attribute::decode_with_formats`).
+    pub fn decode_response(
+        buf: &[u8],
+        request_authenticator: &[u8; AUTHENTICATOR_LENGTH],
+        secret: &[u8],
+        require_message_authenticator: bool,
+        formats: &VendorFormats,
+    ) -> Result<Packet, RadiusError> {
+        let packet = Packet::decode_with_formats(buf, formats)?;
+        let length = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+
+        // both authenticators of a reply are calculated over the packet
+        // with the Request Authenticator in place of its own
+        let header = &buf[..4];
+        let attributes = &buf[HEADER_LENGTH..length];
+
+        let mut hasher = Md5::new();
+        hasher.update(header);
+        hasher.update(request_authenticator);
+        hasher.update(attributes);
+        hasher.update(secret);
+        if hasher.finalize() != packet.authenticator {
+            return Err(RadiusError::InvalidResponseAuthenticator(packet.identifier));
+        }
+
+        match message_authenticator_offset(&buf[..length]) {
+            Some(offset) => {
+                let offset = offset - HEADER_LENGTH;
+                let received = &attributes[offset..offset + AUTHENTICATOR_LENGTH];
+
+                let mut hasher = HmacMd5::new(secret);
+                hasher.update(header);
+                hasher.update(request_authenticator);
+                hasher.update(&attributes[..offset]);
+                hasher.update(&[0; AUTHENTICATOR_LENGTH]);
+                hasher.update(&attributes[offset + AUTHENTICATOR_LENGTH..]);
+                if hasher.finalize() != received {
+                    return Err(RadiusError::InvalidMessageAuthenticator(packet.identifier));
+                }
+            }
+            None if require_message_authenticator => {
+                return Err(RadiusError::MissingMessageAuthenticator(packet.identifier));
+            }
+            None => {}
+        }
+
+        Ok(packet)
+    }
+
+    // Calculates the value of the Message-Authenticator attribute within the
+    // given encoded packet if the packet has it.
+    //
+    //   Message-Authenticator = HMAC-MD5(Type+Identifier+Length+Authenticator+Attributes)
+    //
+    // where the value of the Message-Authenticator itself is zeroed.
+    fn sign_message_authenticator(&mut self, buf: &mut [u8], secret: &[u8]) {
+        let offset = match message_authenticator_offset(buf) {
+            Some(offset) => offset,
+            None => return,
+        };
+
+        let mut value = [0; AUTHENTICATOR_LENGTH];
+        buf[offset..offset + AUTHENTICATOR_LENGTH].fill(0);
+        hmac_md5(secret, buf, &mut value);
+        buf[offset..offset + AUTHENTICATOR_LENGTH].copy_from_slice(&value);
+
+        if let Some(attr) = self
+            .attributes
+            .iter_mut()
+            .find(|attr| attr.attr_type() == MESSAGE_AUTHENTICATOR_TYPE)
+        {
+            // value of the Message-Authenticator always fits into an attribute
+            attr.set_value(&value).unwrap();
+        }
+    }
+
+    /// Decodes a RADIUS packet from the given `buf`.
+    ///
+    /// Octets beyond the `Length` field of the packet are treated as padding
+    /// and ignored according to RFC 2865 3.
+    pub fn decode(buf: &[u8]) -> Result<Packet, RadiusError> {
+        Packet::decode_with_formats(buf, &VendorFormats::new())
+    }
+
+    /// Decodes a RADIUS packet from the given `buf` like `decode` does,
+    /// decoding Vendor-Specific attributes according to the given vendor
+    /// `formats`.
+    pub fn decode_with_formats(
+        buf: &[u8],
+        formats: &VendorFormats,
+    ) -> Result<Packet, RadiusError> {
+        if buf.len() < HEADER_LENGTH {
+            return Err(RadiusError::PacketTooShort(buf.len()));
+        }
+
+        let length = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        if !(HEADER_LENGTH..=MAX_PACKET_LENGTH).contains(&length) {
+            return Err(RadiusError::InvalidPacketLength(length));
+        }
+        if buf.len() < length {
+            return Err(RadiusError::PacketTooShort(buf.len()));
+        }
+
+        let code = Code::try_from(buf[0])?;
+        let mut authenticator = [0; AUTHENTICATOR_LENGTH];
+        authenticator.copy_from_slice(&buf[4..HEADER_LENGTH]);
+
+        Ok(Packet {
+            code,
+            identifier: buf[1],
+            authenticator,
+            attributes: Attribute::decode_with_formats(
+                &buf[HEADER_LENGTH..length],
+                formats,
+            )?,
+        })
+    }
+}
+
+// Returns the offset of the Message-Authenticator value within the given
+// encoded packet.
+fn message_authenticator_offset(buf: &[u8]) -> Option<usize> {
+    let mut pos = HEADER_LENGTH;
+
+    while pos + 2 <= buf.len() {
+        let length = buf[pos + 1] as usize;
+        if length < 2 || pos + length > buf.len() {
+            return None;
+        }
+
+        if buf[pos] == MESSAGE_AUTHENTICATOR_TYPE && length == AUTHENTICATOR_LENGTH + 2 {
+            return Some(pos + 2);
+        }
+
+        pos += length;
+    }
+
+    None
+}
+
+// Calculates MD5 over the given encoded packet followed by the shared
+// secret.
+fn hash_authenticator(packet: &[u8], secret: &[u8]) -> [u8; AUTHENTICATOR_LENGTH] {
+    let mut hasher = Md5::new();
+    hasher.update(packet);
+    hasher.update(secret);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod packet_tests {
+    use super::*;
+    use crate::md5::md5;
+    use crate::attribute::{Vendor, VENDOR_SPECIFIC_TYPE};
+
+    #[test]
+    fn code_test() {
+        assert_eq!(Code::try_from(3).unwrap(), Code::AccessReject);
+        assert_eq!(Code::AccessReject.to_string(), "Access-Reject");
+        assert_eq!(Code::try_from(44).unwrap().to_string(), "CoA-ACK");
+        assert!(Code::try_from(6).is_err());
+    }
+
+    #[test]
+    fn encode_decode_test() {
+        let mut packet = Packet::new(Code::AccountingRequest, 7);
+        packet.set_authenticator([0xab; AUTHENTICATOR_LENGTH]);
+
+        let mut status = Attribute::new(40, None);
+        status.set_value(&[0, 0, 0, 1]).unwrap();
+        packet.add_attribute(status);
+
+        let mut rat_type = Attribute::new(VENDOR_SPECIFIC_TYPE, Some(Vendor::new(10415, 21)));
+        rat_type.set_value(&[6]).unwrap();
+        packet.add_attribute(rat_type);
+
+        let buf = packet.encode().unwrap();
+        assert_eq!(buf.len(), HEADER_LENGTH + 6 + 9);
+        assert_eq!(&buf[0..4], &[4, 7, 0, 35]);
+
+        let decoded = Packet::decode(&buf).unwrap();
+        assert_eq!(decoded.code(), Code::AccountingRequest);
+        assert_eq!(decoded.identifier(), 7);
+        assert_eq!(decoded.authenticator(), &[0xab; AUTHENTICATOR_LENGTH]);
+        assert_eq!(decoded.attributes().len(), 2);
+        assert_eq!(decoded.attributes()[0].value(), &[0, 0, 0, 1]);
+        assert_eq!(decoded.attributes()[1].vendor().unwrap().vendor_type(), 21);
+        assert_eq!(decoded.attributes()[1].value(), &[6]);
+    }
+
+    #[test]
+    fn decode_ignores_padding_test() {
+        let mut buf = Packet::new(Code::AccessAccept, 1).encode().unwrap();
+        buf.extend_from_slice(&[0, 0, 0]);
+
+        let packet = Packet::decode(&buf).unwrap();
+        assert_eq!(packet.code(), Code::AccessAccept);
+        assert!(packet.attributes().is_empty());
+    }
+
+    #[test]
+    fn decode_invalid_packet_test() {
+        let buf = Packet::new(Code::AccessAccept, 1).encode().unwrap();
+
+        assert!(matches!(
+            Packet::decode(&buf[..19]),
+            Err(RadiusError::PacketTooShort(19))
+        ));
+
+        let mut invalid_code = buf.clone();
+        invalid_code[0] = 0;
+        assert!(matches!(
+            Packet::decode(&invalid_code),
+            Err(RadiusError::UnknownPacketCode(0))
+        ));
+
+        let mut invalid_length = buf.clone();
+        invalid_length[3] = 19;
+        assert!(matches!(
+            Packet::decode(&invalid_length),
+            Err(RadiusError::InvalidPacketLength(19))
+        ));
+
+        let mut truncated = buf;
+        truncated[3] = 24;
+        assert!(matches!(
+            Packet::decode(&truncated),
+            Err(RadiusError::PacketTooShort(20))
+        ));
+    }
+
+    #[test]
+    fn encode_too_long_test() {
+        let mut packet = Packet::new(Code::AccessRequest, 1);
+        for _ in 0..17 {
+            let mut attr = Attribute::new(25, None);
+            attr.set_value(&[0; 253]).unwrap();
+            packet.add_attribute(attr);
+        }
+
+        assert!(matches!(packet.encode(), Err(RadiusError::PacketTooLong(4355))));
+    }
+
+    #[test]
+    fn request_authenticator_test() {
+        let a = Packet::new(Code::AccessRequest, 1);
+        let b = Packet::new(Code::AccessRequest, 1);
+        assert_ne!(a.authenticator(), b.authenticator());
+        assert_eq!(
+            Packet::new(Code::AccountingRequest, 1).authenticator(),
+            &[0; AUTHENTICATOR_LENGTH]
+        );
+
+        // Access-Request authenticator is sent as is
+        let mut access = a.clone();
+        let buf = access.encode_request(b"secret").unwrap();
+        assert_eq!(&buf[4..20], a.authenticator());
+
+        // Accounting-Request authenticator is MD5 over the packet with
+        // zeroed authenticator and the secret
+        let mut status = Attribute::new(40, None);
+        status.set_value(&[0, 0, 0, 1]).unwrap();
+        let mut acct = Packet::new(Code::AccountingRequest, 2);
+        acct.add_attribute(status);
+
+        let buf = acct.encode_request(b"secret").unwrap();
+        let mut zeroed = buf.clone();
+        zeroed[4..20].copy_from_slice(&[0; AUTHENTICATOR_LENGTH]);
+        zeroed.extend_from_slice(b"secret");
+
+        let mut expected = [0; AUTHENTICATOR_LENGTH];
+        md5(&zeroed, &mut expected);
+        assert_eq!(&buf[4..20], &expected);
+        assert_eq!(acct.authenticator(), &expected);
+    }
+
+    #[test]
+    fn rfc2865_response_authenticator_test() {
+        // Access-Request and Access-Accept examples from RFC 2865 7.1
+        let request_authenticator = [
+            0x0f, 0x40, 0x3f, 0x94, 0x73, 0x97, 0x80, 0x57, 0xbd, 0x83, 0xd5, 0xcb, 0x98, 0xf4,
+            0x22, 0x7a,
+        ];
+        let accept = [
+            0x02, 0x00, 0x00, 0x26, 0x86, 0xfe, 0x22, 0x0e, 0x76, 0x24, 0xba, 0x2a, 0x10, 0x05,
+            0xf6, 0xbf, 0x9b, 0x55, 0xe0, 0xb2, 0x06, 0x06, 0x00, 0x00, 0x00, 0x01, 0x0f, 0x06,
+            0x00, 0x00, 0x00, 0x00, 0x0e, 0x06, 0xc0, 0xa8, 0x01, 0x03,
+        ];
+        let formats = VendorFormats::new();
+
+        let packet =
+            Packet::decode_response(&accept, &request_authenticator, b"xyzzy5461", false, &formats)
+                .unwrap();
+        assert_eq!(packet.code(), Code::AccessAccept);
+        assert_eq!(packet.attributes().len(), 3);
+
+        assert!(matches!(
+            Packet::decode_response(&accept, &request_authenticator, b"wrong", false, &formats),
+            Err(RadiusError::InvalidResponseAuthenticator(0))
+        ));
+
+        let mut tampered = accept;
+        tampered[37] = 0x04;
+        assert!(matches!(
+            Packet::decode_response(
+                &tampered,
+                &request_authenticator,
+                b"xyzzy5461",
+                false,
+                &formats
+            ),
+            Err(RadiusError::InvalidResponseAuthenticator(0))
+        ));
+
+        let mut reply = Packet::decode(&accept).unwrap();
+        let buf = reply.encode_response(&request_authenticator, b"xyzzy5461").unwrap();
+        assert_eq!(buf, accept);
+    }
+
+    #[test]
+    fn message_authenticator_test() {
+        let formats = VendorFormats::new();
+        let decode = |buf: &[u8], authenticator: &[u8; AUTHENTICATOR_LENGTH], strict: bool| {
+            Packet::decode_response(buf, authenticator, b"secret", strict, &formats)
+        };
+
+        let mut request = Packet::new(Code::AccessRequest, 3);
+        let mut user_name = Attribute::new(1, None);
+        user_name.set_value(b"user").unwrap();
+        request.add_attribute(user_name);
+        request.add_message_authenticator();
+        request.add_message_authenticator();
+
+        let buf = request.encode_request(b"secret").unwrap();
+        assert_eq!(request.attributes().len(), 2);
+        assert_eq!(&buf[20..22], &[MESSAGE_AUTHENTICATOR_TYPE, 18]);
+
+        let mut zeroed = buf.clone();
+        zeroed[22..38].fill(0);
+        let mut expected = [0; AUTHENTICATOR_LENGTH];
+        hmac_md5(b"secret", &zeroed, &mut expected);
+        assert_eq!(&buf[22..38], &expected);
+        assert_eq!(request.message_authenticator().unwrap().value(), &expected);
+
+        // reply with valid Message-Authenticator
+        let mut accept = Packet::new(Code::AccessAccept, 3);
+        accept.add_message_authenticator();
+        let reply = accept.encode_response(request.authenticator(), b"secret").unwrap();
+        assert!(decode(&reply, request.authenticator(), true).is_ok());
+
+        // reply with tampered Message-Authenticator but valid Response
+        // Authenticator
+        let mut tampered = reply.clone();
+        tampered[22] ^= 0xff;
+        tampered[4..20].copy_from_slice(request.authenticator());
+        let authenticator = hash_authenticator(&tampered, b"secret");
+        tampered[4..20].copy_from_slice(&authenticator);
+        assert!(matches!(
+            decode(&tampered, request.authenticator(), false),
+            Err(RadiusError::InvalidMessageAuthenticator(3))
+        ));
+
+        // reply without Message-Authenticator in strict mode
+        let reply = Packet::new(Code::AccessAccept, 3)
+            .encode_response(request.authenticator(), b"secret")
+            .unwrap();
+        assert!(decode(&reply, request.authenticator(), false).is_ok());
+        assert!(matches!(
+            decode(&reply, request.authenticator(), true),
+            Err(RadiusError::MissingMessageAuthenticator(3))
+        ));
+    }
+
+    #[test]
+    fn chap_password_test() {
+        let mut request = Packet::new(Code::AccessRequest, 1);
+        request.add_chap_password(b"password");
+        let chap = request.attributes()[0].value().to_vec();
+        assert_eq!(chap.len(), 17);
+        assert_eq!(chap, chap_password(chap[0], b"password", request.authenticator()));
+
+        // the CHAP-Challenge takes precedence over the Request Authenticator
+        let mut request = Packet::new(Code::AccessRequest, 2);
+        let mut challenge = Attribute::new(CHAP_CHALLENGE_TYPE, None);
+        challenge.set_value(b"0123456789abcdef").unwrap();
+        request.add_attribute(challenge);
+        request.add_chap_password(b"password");
+
+        let decoded = Packet::decode(&request.encode_request(b"secret").unwrap()).unwrap();
+        let chap = decoded.attributes()[1].value();
+        assert_eq!(decoded.attributes()[1].attr_type(), CHAP_PASSWORD_TYPE);
+        assert_eq!(chap, chap_password(chap[0], b"password", b"0123456789abcdef"));
+    }
+
+    #[test]
+    fn encode_requests_test() {
+        let mut packets: Vec<Packet> = Vec::new();
+        for id in 0..11 {
+            let code = if id % 3 == 0 {
+                Code::AccessRequest
+            } else {
+                Code::AccountingRequest
+            };
+            let mut packet = Packet::new(code, id);
+            let mut attr = Attribute::new(1, None);
+            attr.set_value(&vec![b'u'; id as usize * 10]).unwrap();
+            packet.add_attribute(attr);
+            if id == 4 {
+                packet.add_message_authenticator();
+            }
+            packets.push(packet);
+        }
+
+        let mut expected = packets.clone();
+        let bufs = Packet::encode_requests(&mut packets, b"secret").unwrap();
+
+        assert_eq!(bufs.len(), 11);
+        for ((buf, packet), expected) in bufs.iter().zip(&packets).zip(expected.iter_mut()) {
+            assert_eq!(buf, &expected.encode_request(b"secret").unwrap());
+            assert_eq!(packet.authenticator(), expected.authenticator());
+        }
+    }
+}
@@ -0,0 +1,364 @@
+/// Defines the auxilary functions to hide and recover values of the RADIUS
+/// attributes that carry passwords and to calculate CHAP responses.
+use super::error::RadiusError;
+use super::md5::Md5;
+use super::packet::AUTHENTICATOR_LENGTH;
+
+/// Size of a chunk of a hidden password.
+const CHUNK_LENGTH: usize = 16;
+
+/// Maximum length of the User-Password attribute value according to
+/// RFC 2865 5.2.
+pub const MAX_USER_PASSWORD_LENGTH: usize = 128;
+
+/// Length of the Salt field of the Tunnel-Password attribute according to
+/// RFC 2868 3.5.
+pub const SALT_LENGTH: usize = 2;
+
+/// Maximum length of a password carried by the Tunnel-Password attribute.
+///
+/// The hidden password follows the Tag and the Salt fields and is padded to
+/// a multiple of 16 octets together with the length octet, so at most `240`
+/// of the `253` octets of the attribute value could be used for it.
+pub const MAX_TUNNEL_PASSWORD_LENGTH: usize = 239;
+
+// Maximum length of the hidden part of the Tunnel-Password attribute.
+const MAX_HIDDEN_TUNNEL_PASSWORD_LENGTH: usize = MAX_TUNNEL_PASSWORD_LENGTH + 1;
+
+/// Length of the value of the CHAP-Password attribute according to RFC 2865
+/// 5.3, the CHAP Ident followed by the 16 octets of the CHAP response.
+pub const CHAP_PASSWORD_LENGTH: usize = 17;
+
+/// Hides the given `password` according to RFC 2865 5.2.
+///
+/// The password is padded with nulls to a multiple of 16 octets and every
+/// chunk is XORed with MD5 of the shared `secret` followed by the Request
+/// Authenticator for the first chunk or by the previous hidden chunk for all
+/// subsequent chunks:
+///
+///   b1 = MD5(S + RA)       c(1) = p1 xor b1
+///   b2 = MD5(S + c(1))     c(2) = p2 xor b2
+///          .                       .
+///   bi = MD5(S + c(i-1))   c(i) = pi xor bi
+///
+/// Returns `RadiusError::PasswordTooLong` if the password is longer than
+/// `128` octets.
+///
+/// # Examples
+///
+/// ```
+/// use radius::password::{hide_user_password, unhide_user_password};
+///
+/// let authenticator = [0x42; 16];
+/// let hidden = hide_user_password(b"password", b"secret", &authenticator).unwrap();
+///
+/// assert_eq!(hidden.len(), 16);
+/// assert_eq!(
+///     unhide_user_password(&hidden, b"secret", &authenticator).unwrap(),
+///     b"password"
+/// );
+/// ```
+pub fn hide_user_password(
+    password: &[u8],
+    secret: &[u8],
+    authenticator: &[u8; AUTHENTICATOR_LENGTH],
+) -> Result<Vec<u8>, RadiusError> {
+    if password.len() > MAX_USER_PASSWORD_LENGTH {
+        return Err(RadiusError::PasswordTooLong(password.len()));
+    }
+
+    let padded_length = password.len().max(1).div_ceil(CHUNK_LENGTH) * CHUNK_LENGTH;
+    let mut hidden: Vec<u8> = password.to_vec();
+    hidden.resize(padded_length, 0);
+
+    for pos in (0..padded_length).step_by(CHUNK_LENGTH) {
+        let mut hasher = Md5::new();
+        hasher.update(secret);
+        if pos == 0 {
+            hasher.update(authenticator);
+        } else {
+            hasher.update(&hidden[pos - CHUNK_LENGTH..pos]);
+        }
+        let b = hasher.finalize();
+
+        for (c, b) in hidden[pos..pos + CHUNK_LENGTH].iter_mut().zip(b.iter()) {
+            *c ^= b;
+        }
+    }
+
+    Ok(hidden)
+}
+
+/// Recovers the password hidden according to RFC 2865 5.2.
+///
+/// The trailing nulls added as padding are removed from the recovered
+/// password. Returns `RadiusError::InvalidHiddenPassword` if the length of
+/// the `hidden` value is not a multiple of 16 octets or is out of range
+/// `16..128`.
+pub fn unhide_user_password(
+    hidden: &[u8],
+    secret: &[u8],
+    authenticator: &[u8; AUTHENTICATOR_LENGTH],
+) -> Result<Vec<u8>, RadiusError> {
+    let chunks = hidden.chunks_exact(CHUNK_LENGTH);
+    if hidden.is_empty() || hidden.len() > MAX_USER_PASSWORD_LENGTH || !chunks.remainder().is_empty()
+    {
+        return Err(RadiusError::InvalidHiddenPassword(hidden.len()));
+    }
+
+    let mut password: Vec<u8> = Vec::with_capacity(hidden.len());
+    for (i, chunk) in chunks.enumerate() {
+        let mut hasher = Md5::new();
+        hasher.update(secret);
+        if i == 0 {
+            hasher.update(authenticator);
+        } else {
+            hasher.update(&hidden[(i - 1) * CHUNK_LENGTH..i * CHUNK_LENGTH]);
+        }
+        let b = hasher.finalize();
+
+        password.extend(chunk.iter().zip(b.iter()).map(|(c, b)| c ^ b));
+    }
+
+    while password.last() == Some(&0) {
+        password.pop();
+    }
+
+    Ok(password)
+}
+
+/// Hides the given `password` according to RFC 2868 3.5.
+///
+/// The password is prefixed with its length, padded with nulls to a multiple
+/// of 16 octets and hidden the same way as User-Password, except that the
+/// first chunk is XORed with MD5 of the shared `secret` followed by the
+/// Request Authenticator and the `salt`:
+///
+///   b(1) = MD5(S + R + A)    c(1) = p(1) xor b(1)
+///   b(2) = MD5(S + c(1))     c(2) = p(2) xor b(2)
+///
+/// The salt must be unique for every Tunnel-Password attribute sent with the
+/// same Request Authenticator and have the most significant bit set. Returns
+/// the Salt field followed by the hidden password, which is the value of
+/// the attribute without the Tag field, or `RadiusError::PasswordTooLong`
+/// if the password is longer than `239` octets.
+///
+/// # Examples
+///
+/// ```
+/// use radius::password::{hide_tunnel_password, unhide_tunnel_password};
+///
+/// let authenticator = [0x42; 16];
+/// let hidden = hide_tunnel_password(b"password", b"secret", &authenticator, [0x80, 1]).unwrap();
+///
+/// assert_eq!(hidden.len(), 2 + 16);
+/// assert_eq!(
+///     unhide_tunnel_password(&hidden, b"secret", &authenticator).unwrap(),
+///     b"password"
+/// );
+/// ```
+pub fn hide_tunnel_password(
+    password: &[u8],
+    secret: &[u8],
+    authenticator: &[u8; AUTHENTICATOR_LENGTH],
+    salt: [u8; SALT_LENGTH],
+) -> Result<Vec<u8>, RadiusError> {
+    if password.len() > MAX_TUNNEL_PASSWORD_LENGTH {
+        return Err(RadiusError::PasswordTooLong(password.len()));
+    }
+
+    let padded_length = (password.len() + 1).div_ceil(CHUNK_LENGTH) * CHUNK_LENGTH;
+    let mut hidden: Vec<u8> = Vec::with_capacity(SALT_LENGTH + padded_length);
+    hidden.extend_from_slice(&salt);
+    hidden.push(password.len() as u8);
+    hidden.extend_from_slice(password);
+    hidden.resize(SALT_LENGTH + padded_length, 0);
+
+    for pos in (SALT_LENGTH..hidden.len()).step_by(CHUNK_LENGTH) {
+        let mut hasher = Md5::new();
+        hasher.update(secret);
+        if pos == SALT_LENGTH {
+            hasher.update(authenticator);
+            hasher.update(&salt);
+        } else {
+            hasher.update(&hidden[pos - CHUNK_LENGTH..pos]);
+        }
+        let b = hasher.finalize();
+
+        for (c, b) in hidden[pos..pos + CHUNK_LENGTH].iter_mut().zip(b.iter()) {
+            *c ^= b;
+        }
+    }
+
+    Ok(hidden)
+}
+
+/// Recovers the password hidden according to RFC 2868 3.5.
+///
+/// The `hidden` value is the Salt field followed by the hidden password,
+/// i.e. the value of the Tunnel-Password attribute without the Tag field.
+/// Returns `RadiusError::InvalidHiddenPassword` if the length of the hidden
+/// password is not a multiple of 16 octets, is out of range `16..240` or
+/// does not cover the length of the recovered password.
+pub fn unhide_tunnel_password(
+    hidden: &[u8],
+    secret: &[u8],
+    authenticator: &[u8; AUTHENTICATOR_LENGTH],
+) -> Result<Vec<u8>, RadiusError> {
+    let invalid = || RadiusError::InvalidHiddenPassword(hidden.len());
+    if hidden.len() < SALT_LENGTH {
+        return Err(invalid());
+    }
+
+    let (salt, data) = hidden.split_at(SALT_LENGTH);
+    let chunks = data.chunks_exact(CHUNK_LENGTH);
+    if data.is_empty()
+        || data.len() > MAX_HIDDEN_TUNNEL_PASSWORD_LENGTH
+        || !chunks.remainder().is_empty()
+    {
+        return Err(invalid());
+    }
+
+    let mut password: Vec<u8> = Vec::with_capacity(data.len());
+    for (i, chunk) in chunks.enumerate() {
+        let mut hasher = Md5::new();
+        hasher.update(secret);
+        if i == 0 {
+            hasher.update(authenticator);
+            hasher.update(salt);
+        } else {
+            hasher.update(&data[(i - 1) * CHUNK_LENGTH..i * CHUNK_LENGTH]);
+        }
+        let b = hasher.finalize();
+
+        password.extend(chunk.iter().zip(b.iter()).map(|(c, b)| c ^ b));
+    }
+
+    // the first octet is the length of the password
+    let length = password[0] as usize;
+    if length >= password.len() {
+        return Err(invalid());
+    }
+
+    Ok(password[1..=length].to_vec())
+}
+
+/// Calculates the value of the CHAP-Password attribute according to RFC 2865
+/// 5.3 and RFC 1994 4.1.
+///
+/// The value is the CHAP `ident` followed by the response, which is MD5 of
+/// the ident, the cleartext `password` and the `challenge`. The challenge is
+/// the value of the CHAP-Challenge attribute of the packet or the Request
+/// Authenticator if the packet has no CHAP-Challenge.
+///
+/// # Examples
+///
+/// ```
+/// use radius::password::chap_password;
+///
+/// let value = chap_password(1, b"password", &[0x42; 16]);
+///
+/// assert_eq!(value.len(), 17);
+/// assert_eq!(value[0], 1);
+/// ```
+pub fn chap_password(ident: u8, password: &[u8], challenge: &[u8]) -> Vec<u8> {
+    let mut hasher = Md5::new();
+    hasher.update(&[ident]);
+    hasher.update(password);
+    hasher.update(challenge);
+
+    let mut value = Vec::with_capacity(CHAP_PASSWORD_LENGTH);
+    value.push(ident);
+    value.extend_from_slice(&hasher.finalize());
+    value
+}
+
+#[cfg(test)]
+mod password_tests {
+    use super::*;
+
+    // Access-Request example from RFC 2865 7.1
+    const AUTHENTICATOR: [u8; AUTHENTICATOR_LENGTH] = [
+        0x0f, 0x40, 0x3f, 0x94, 0x73, 0x97, 0x80, 0x57, 0xbd, 0x83, 0xd5, 0xcb, 0x98, 0xf4, 0x22,
+        0x7a,
+    ];
+    const HIDDEN: [u8; 16] = [
+        0x0d, 0xbe, 0x70, 0x8d, 0x93, 0xd4, 0x13, 0xce, 0x31, 0x96, 0xe4, 0x3f, 0x78, 0x2a, 0x0a,
+        0xee,
+    ];
+
+    #[test]
+    fn rfc2865_example_test() {
+        let hidden = hide_user_password(b"arctangent", b"xyzzy5461", &AUTHENTICATOR).unwrap();
+        assert_eq!(hidden, HIDDEN);
+
+        let password = unhide_user_password(&HIDDEN, b"xyzzy5461", &AUTHENTICATOR).unwrap();
+        assert_eq!(password, b"arctangent");
+    }
+
+    #[test]
+    fn long_password_test() {
+        let password = [b'p'; 100];
+        let hidden = hide_user_password(&password, b"secret", &AUTHENTICATOR).unwrap();
+
+        assert_eq!(hidden.len(), 112);
+        assert_eq!(
+            unhide_user_password(&hidden, b"secret", &AUTHENTICATOR).unwrap(),
+            password
+        );
+        assert_eq!(hide_user_password(b"", b"secret", &AUTHENTICATOR).unwrap().len(), 16);
+    }
+
+    #[test]
+    fn invalid_password_test() {
+        assert!(hide_user_password(&[b'p'; 129], b"secret", &AUTHENTICATOR).is_err());
+        assert!(unhide_user_password(&[0; 15], b"secret", &AUTHENTICATOR).is_err());
+        assert!(unhide_user_password(&[], b"secret", &AUTHENTICATOR).is_err());
+        assert!(unhide_user_password(&[0; 144], b"secret", &AUTHENTICATOR).is_err());
+    }
+
+    #[test]
+    fn tunnel_password_test() {
+        const HIDDEN: [u8; 18] = [
+            0x80, 0x01, 0xcd, 0x2e, 0xa4, 0x1b, 0x11, 0x70, 0xf1, 0x90, 0xa2, 0x68, 0x5b, 0x09,
+            0xe6, 0x83, 0x87, 0x23,
+        ];
+
+        let hidden =
+            hide_tunnel_password(b"arctangent", b"xyzzy5461", &AUTHENTICATOR, [0x80, 0x01]);
+        assert_eq!(hidden.unwrap(), HIDDEN);
+        let password = unhide_tunnel_password(&HIDDEN, b"xyzzy5461", &AUTHENTICATOR).unwrap();
+        assert_eq!(password, b"arctangent");
+
+        // the length octet makes passwords of 15 octets take one chunk and
+        // passwords of 16 octets two chunks
+        for (length, hidden_length) in [(0, 18), (15, 18), (16, 34), (239, 242)] {
+            let password = vec![b'p'; length];
+            let hidden =
+                hide_tunnel_password(&password, b"secret", &AUTHENTICATOR, [0x81, 0]).unwrap();
+            assert_eq!(hidden.len(), hidden_length);
+            assert_eq!(
+                unhide_tunnel_password(&hidden, b"secret", &AUTHENTICATOR).unwrap(),
+                password
+            );
+        }
+
+        assert!(hide_tunnel_password(&[b'p'; 240], b"secret", &AUTHENTICATOR, [0x80, 0]).is_err());
+        assert!(unhide_tunnel_password(&[0x80], b"secret", &AUTHENTICATOR).is_err());
+        assert!(unhide_tunnel_password(&[0x80, 0, 1], b"secret", &AUTHENTICATOR).is_err());
+    }
+
+    #[test]
+    fn chap_password_test() {
+        let value = chap_password(0x2a, b"arctangent", &AUTHENTICATOR);
+        assert_eq!(
+            value,
+            vec![
+                0x2a, 0x14, 0xe4, 0x7d, 0xfc, 0x63, 0xa7, 0xc6, 0x4c, 0xc8, 0xc3, 0xa3, 0x9a, 0xc0,
+                0xf4, 0x59, 0x7e
+            ]
+        );
+        assert_ne!(chap_password(0x2b, b"arctangent", &AUTHENTICATOR), value);
+        assert_ne!(chap_password(0x2a, b"arctangent", b"challenge"), value);
+    }
+}
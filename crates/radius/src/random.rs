@@ -0,0 +1,54 @@
+/// Defines the auxilary functions to generate random values for RADIUS
+/// packets like Request Authenticators.
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Counter that makes every call of `fill` produce distinct values even if
+// the clock did not move.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Fills the given `buf` with random octets.
+///
+/// The octets are produced by SipHash keyed with the random keys of the
+/// standard library `RandomState` (which are seeded by the operating system)
+/// over a global counter and the current time. This is enough to make
+/// Request Authenticators unpredictable as RFC 2865 3 requires without
+/// pulling in a dedicated random number generator.
+pub fn fill(buf: &mut [u8]) {
+    let state = RandomState::new();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+
+    for chunk in buf.chunks_mut(8) {
+        let mut hasher = state.build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u64(now);
+
+        let random = hasher.finish().to_le_bytes();
+        chunk.copy_from_slice(&random[..chunk.len()]);
+    }
+}
+
+#[cfg(test)]
+mod random_tests {
+    use super::*;
+
+    #[test]
+    fn fill_test() {
+        let mut a = [0; 16];
+        let mut b = [0; 16];
+        let mut c = [0; 3];
+
+        fill(&mut a);
+        fill(&mut b);
+        fill(&mut c);
+
+        assert_ne!(a, [0; 16]);
+        assert_ne!(a, b);
+        assert_ne!(&a[..8], &a[8..]);
+    }
+}
@@ -0,0 +1,53 @@
+/// Defines the temporary directories the tests write RADIUS dictionaries
+/// into.
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Temporary directory with the files of a test. The directory is removed
+/// with all its content when the `TempDir` is dropped, so it is not leaked
+/// when an assertion of the test fails.
+pub(crate) struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates the directory unique for the given test `name` and writes the
+    /// given `files` into it. Names of the files could contain directories
+    /// which are created as well.
+    pub(crate) fn new(name: &str, files: &[(&str, &str)]) -> TempDir {
+        let path = env::temp_dir().join(format!("whirl-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+
+        let dir = TempDir { path };
+        for (file, content) in files {
+            let file = dir.path.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+        dir
+    }
+
+    /// Returns the path of the directory.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the path of the directory as the list of dictionaries paths.
+    pub(crate) fn paths(&self) -> &[PathBuf] {
+        std::slice::from_ref(&self.path)
+    }
+
+    /// Returns the path of the given file within the directory.
+    pub(crate) fn join<P: AsRef<Path>>(&self, file: P) -> PathBuf {
+        self.path.join(file)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,1155 @@
+/// Defines the types and auxilary functions to represent and work with
+/// structured values of 3GPP RADIUS attributes described in TS 29.061
+/// 16.4.7.2.
+///
+/// Structured values are built from and converted to lists of named
+/// fields, so they could be given as tables within scenarios, for example
+/// `{type='TAI+ECGI', mcc='262', mnc='01', tac=1, eci=42}` for
+/// `3GPP-User-Location-Info` or `{offset='+02:00', dst=1}` for
+/// `3GPP-MS-TimeZone`.
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Value of a field of a structured value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    Integer(u64),
+    String(String),
+}
+
+impl Field {
+    // Returns the field as an integer, strings are parsed as decimal or `0x`
+    // prefixed hexadecimal integers.
+    fn integer(&self) -> Option<u64> {
+        match self {
+            Field::Integer(n) => Some(*n),
+            Field::String(s) => match s.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16).ok(),
+                None => s.parse::<u64>().ok(),
+            },
+        }
+    }
+
+    // Returns the field as a string, integers are formatted with at least
+    // `width` digits.
+    fn string(&self, width: usize) -> String {
+        match self {
+            Field::Integer(n) => format!("{:0width$}", n, width = width),
+            Field::String(s) => s.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Field::Integer(n) => write!(f, "{}", n),
+            Field::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// List of named fields of a structured value.
+pub type Fields = Vec<(String, Field)>;
+
+// Looks up the field with the given `name`.
+fn field<'a>(fields: &'a [(String, Field)], name: &str) -> Option<&'a Field> {
+    fields.iter().find(|(n, _)| n == name).map(|(_, f)| f)
+}
+
+// Looks up the required integer field with the given `name` that should not
+// exceed `max`.
+fn integer(fields: &[(String, Field)], name: &str, max: u64) -> Option<u64> {
+    field(fields, name)?.integer().filter(|n| *n <= max)
+}
+
+/// Public Land Mobile Network identifier encoded as defined in TS 24.008
+/// 10.5.1.3.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plmn {
+    mcc: String,
+    mnc: String,
+}
+
+impl Plmn {
+    /// Creates new `Plmn` from the Mobile Country Code of 3 digits and the
+    /// Mobile Network Code of 2 or 3 digits.
+    pub fn new(mcc: &str, mnc: &str) -> Option<Plmn> {
+        let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if mcc.len() != 3 || !(2..=3).contains(&mnc.len()) || !digits(mcc) || !digits(mnc) {
+            return None;
+        }
+
+        Some(Plmn {
+            mcc: mcc.to_string(),
+            mnc: mnc.to_string(),
+        })
+    }
+
+    /// Returns the Mobile Country Code.
+    #[inline]
+    pub fn mcc(&self) -> &str {
+        &self.mcc
+    }
+
+    /// Returns the Mobile Network Code.
+    #[inline]
+    pub fn mnc(&self) -> &str {
+        &self.mnc
+    }
+
+    fn from_fields(fields: &[(String, Field)]) -> Option<Plmn> {
+        let mcc = field(fields, "mcc")?.string(3);
+        let mnc = field(fields, "mnc")?.string(2);
+        Plmn::new(&mcc, &mnc)
+    }
+
+    fn fields(&self, fields: &mut Fields) {
+        fields.push(("mcc".to_string(), Field::String(self.mcc.clone())));
+        fields.push(("mnc".to_string(), Field::String(self.mnc.clone())));
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        let mcc: Vec<u8> = self.mcc.bytes().map(|b| b - b'0').collect();
+        let mnc: Vec<u8> = self.mnc.bytes().map(|b| b - b'0').collect();
+
+        // the third digit of 2 digits MNC is filled with 1111
+        buf.push(mcc[1] << 4 | mcc[0]);
+        buf.push(mnc.get(2).copied().unwrap_or(0xf) << 4 | mcc[2]);
+        buf.push(mnc[1] << 4 | mnc[0]);
+    }
+
+    fn decode(buf: &[u8]) -> Option<Plmn> {
+        let digit = |d: u8| {
+            if d <= 9 {
+                Some((b'0' + d) as char)
+            } else {
+                None
+            }
+        };
+
+        let mcc: String = [buf[0] & 0xf, buf[0] >> 4, buf[1] & 0xf]
+            .iter()
+            .map(|d| digit(*d))
+            .collect::<Option<String>>()?;
+        let mut mnc: String = [buf[2] & 0xf, buf[2] >> 4]
+            .iter()
+            .map(|d| digit(*d))
+            .collect::<Option<String>>()?;
+        if buf[1] >> 4 != 0xf {
+            mnc.push(digit(buf[1] >> 4)?);
+        }
+
+        Some(Plmn { mcc, mnc })
+    }
+}
+
+/// Value of the `3GPP-User-Location-Info` attribute, the location of the
+/// user identified by the Geographic Location Type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserLocationInfo {
+    /// Cell Global Identification.
+    Cgi { plmn: Plmn, lac: u16, ci: u16 },
+    /// Service Area Identity.
+    Sai { plmn: Plmn, lac: u16, sac: u16 },
+    /// Routing Area Identification.
+    Rai { plmn: Plmn, lac: u16, rac: u8 },
+    /// Tracking Area Identity.
+    Tai { plmn: Plmn, tac: u16 },
+    /// E-UTRAN Cell Global Identifier.
+    Ecgi { plmn: Plmn, eci: u32 },
+    /// Tracking Area Identity and E-UTRAN Cell Global Identifier.
+    TaiEcgi { plmn: Plmn, tac: u16, eci: u32 },
+    /// NR Cell Global Identifier.
+    Ncgi { plmn: Plmn, nci: u64 },
+    /// 5GS Tracking Area Identity.
+    Tai5gs { plmn: Plmn, tac: u32 },
+    /// 5GS Tracking Area Identity and NR Cell Global Identifier.
+    Tai5gsNcgi { plmn: Plmn, tac: u32, nci: u64 },
+    /// Location of a type that is not supported or a combined location with
+    /// different PLMNs of its parts, kept as is.
+    Other { location_type: u8, data: Vec<u8> },
+}
+
+impl UserLocationInfo {
+    /// Builds the location from the given `fields`. The `type` field selects
+    /// the Geographic Location Type (`CGI`, `SAI`, `RAI`, `TAI`, `ECGI`,
+    /// `TAI+ECGI`, `NCGI`, `5GS-TAI` or `5GS-TAI+NCGI`), the `mcc` and `mnc`
+    /// fields identify the PLMN and the rest of the fields depend on the
+    /// type: `lac`, `ci`, `sac`, `rac`, `tac`, `eci` and `nci`.
+    pub fn from_fields(fields: &[(String, Field)]) -> Option<UserLocationInfo> {
+        let location_type = field(fields, "type")?.string(0);
+        if let Ok(location_type) = location_type.parse::<u8>() {
+            let data = match field(fields, "data")? {
+                Field::String(hex) => decode_hex(hex.strip_prefix("0x")?)?,
+                Field::Integer(_) => return None,
+            };
+            return Some(UserLocationInfo::Other {
+                location_type,
+                data,
+            });
+        }
+
+        let plmn = Plmn::from_fields(fields)?;
+        let u16_field = |name| integer(fields, name, u16::MAX as u64).map(|n| n as u16);
+        let tac5gs = || integer(fields, "tac", 0xffffff).map(|n| n as u32);
+        let eci = || integer(fields, "eci", 0xfffffff).map(|n| n as u32);
+        let nci = || integer(fields, "nci", 0xfffffffff);
+
+        let location = match location_type.to_ascii_uppercase().as_str() {
+            "CGI" => UserLocationInfo::Cgi {
+                plmn,
+                lac: u16_field("lac")?,
+                ci: u16_field("ci")?,
+            },
+            "SAI" => UserLocationInfo::Sai {
+                plmn,
+                lac: u16_field("lac")?,
+                sac: u16_field("sac")?,
+            },
+            "RAI" => UserLocationInfo::Rai {
+                plmn,
+                lac: u16_field("lac")?,
+                rac: integer(fields, "rac", u8::MAX as u64)? as u8,
+            },
+            "TAI" => UserLocationInfo::Tai {
+                plmn,
+                tac: u16_field("tac")?,
+            },
+            "ECGI" => UserLocationInfo::Ecgi { plmn, eci: eci()? },
+            "TAI+ECGI" => UserLocationInfo::TaiEcgi {
+                plmn,
+                tac: u16_field("tac")?,
+                eci: eci()?,
+            },
+            "NCGI" => UserLocationInfo::Ncgi { plmn, nci: nci()? },
+            "5GS-TAI" => UserLocationInfo::Tai5gs {
+                plmn,
+                tac: tac5gs()?,
+            },
+            "5GS-TAI+NCGI" => UserLocationInfo::Tai5gsNcgi {
+                plmn,
+                tac: tac5gs()?,
+                nci: nci()?,
+            },
+            _ => return None,
+        };
+
+        Some(location)
+    }
+
+    /// Returns the fields of the location in the form accepted by
+    /// `from_fields`.
+    pub fn fields(&self) -> Fields {
+        let mut fields = Fields::new();
+        let mut push = |name: &str, field: Field| fields.push((name.to_string(), field));
+
+        let (plmn, location_type) = match self {
+            UserLocationInfo::Cgi { plmn, .. } => (plmn, "CGI"),
+            UserLocationInfo::Sai { plmn, .. } => (plmn, "SAI"),
+            UserLocationInfo::Rai { plmn, .. } => (plmn, "RAI"),
+            UserLocationInfo::Tai { plmn, .. } => (plmn, "TAI"),
+            UserLocationInfo::Ecgi { plmn, .. } => (plmn, "ECGI"),
+            UserLocationInfo::TaiEcgi { plmn, .. } => (plmn, "TAI+ECGI"),
+            UserLocationInfo::Ncgi { plmn, .. } => (plmn, "NCGI"),
+            UserLocationInfo::Tai5gs { plmn, .. } => (plmn, "5GS-TAI"),
+            UserLocationInfo::Tai5gsNcgi { plmn, .. } => (plmn, "5GS-TAI+NCGI"),
+            UserLocationInfo::Other {
+                location_type,
+                data,
+            } => {
+                push("type", Field::Integer(*location_type as u64));
+                push("data", Field::String(encode_hex(data)));
+                return fields;
+            }
+        };
+
+        push("type", Field::String(location_type.to_string()));
+        let mut plmn_fields = Fields::new();
+        plmn.fields(&mut plmn_fields);
+        for (name, field) in plmn_fields {
+            push(&name, field);
+        }
+
+        let integers: Vec<(&str, u64)> = match self {
+            UserLocationInfo::Cgi { lac, ci, .. } => vec![("lac", *lac as u64), ("ci", *ci as u64)],
+            UserLocationInfo::Sai { lac, sac, .. } => {
+                vec![("lac", *lac as u64), ("sac", *sac as u64)]
+            }
+            UserLocationInfo::Rai { lac, rac, .. } => {
+                vec![("lac", *lac as u64), ("rac", *rac as u64)]
+            }
+            UserLocationInfo::Tai { tac, .. } => vec![("tac", *tac as u64)],
+            UserLocationInfo::Ecgi { eci, .. } => vec![("eci", *eci as u64)],
+            UserLocationInfo::TaiEcgi { tac, eci, .. } => {
+                vec![("tac", *tac as u64), ("eci", *eci as u64)]
+            }
+            UserLocationInfo::Ncgi { nci, .. } => vec![("nci", *nci)],
+            UserLocationInfo::Tai5gs { tac, .. } => vec![("tac", *tac as u64)],
+            UserLocationInfo::Tai5gsNcgi { tac, nci, .. } => {
+                vec![("tac", *tac as u64), ("nci", *nci)]
+            }
+            UserLocationInfo::Other { .. } => vec![],
+        };
+        for (name, n) in integers {
+            push(name, Field::Integer(n));
+        }
+
+        fields
+    }
+
+    /// Returns the wire representation of the location.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        match self {
+            UserLocationInfo::Cgi { plmn, lac, ci } => {
+                buf.push(0);
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&lac.to_be_bytes());
+                buf.extend_from_slice(&ci.to_be_bytes());
+            }
+            UserLocationInfo::Sai { plmn, lac, sac } => {
+                buf.push(1);
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&lac.to_be_bytes());
+                buf.extend_from_slice(&sac.to_be_bytes());
+            }
+            UserLocationInfo::Rai { plmn, lac, rac } => {
+                // RAC takes the first octet of the field, the second one is
+                // filled with 1s
+                buf.push(2);
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&lac.to_be_bytes());
+                buf.extend_from_slice(&[*rac, 0xff]);
+            }
+            UserLocationInfo::Tai { plmn, tac } => {
+                buf.push(128);
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&tac.to_be_bytes());
+            }
+            UserLocationInfo::Ecgi { plmn, eci } => {
+                buf.push(129);
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&eci.to_be_bytes());
+            }
+            UserLocationInfo::TaiEcgi { plmn, tac, eci } => {
+                buf.push(130);
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&tac.to_be_bytes());
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&eci.to_be_bytes());
+            }
+            UserLocationInfo::Ncgi { plmn, nci } => {
+                buf.push(135);
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&nci.to_be_bytes()[3..]);
+            }
+            UserLocationInfo::Tai5gs { plmn, tac } => {
+                buf.push(136);
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&tac.to_be_bytes()[1..]);
+            }
+            UserLocationInfo::Tai5gsNcgi { plmn, tac, nci } => {
+                buf.push(137);
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&tac.to_be_bytes()[1..]);
+                plmn.encode(&mut buf);
+                buf.extend_from_slice(&nci.to_be_bytes()[3..]);
+            }
+            UserLocationInfo::Other {
+                location_type,
+                data,
+            } => {
+                buf.push(*location_type);
+                buf.extend_from_slice(data);
+            }
+        }
+
+        buf
+    }
+
+    /// Decodes the wire representation of the location.
+    pub fn decode(buf: &[u8]) -> Option<UserLocationInfo> {
+        let (location_type, data) = buf.split_first()?;
+        let be = |bytes: &[u8]| bytes.iter().fold(0u64, |n, b| n << 8 | *b as u64);
+
+        let expected = match location_type {
+            0 | 1 | 2 | 129 => 7,
+            128 => 5,
+            130 => 12,
+            135 => 8,
+            136 => 6,
+            137 => 14,
+            _ => {
+                return Some(UserLocationInfo::Other {
+                    location_type: *location_type,
+                    data: data.to_vec(),
+                });
+            }
+        };
+        if data.len() != expected {
+            return None;
+        }
+
+        let plmn = Plmn::decode(&data[..3])?;
+
+        // the combined locations carry the PLMN of both their parts, the
+        // typed values have one PLMN only, so values with different PLMNs
+        // are kept as is
+        let second_plmn = match location_type {
+            130 => Some(&data[5..8]),
+            137 => Some(&data[6..9]),
+            _ => None,
+        };
+        if let Some(second_plmn) = second_plmn {
+            if Plmn::decode(second_plmn)? != plmn {
+                return Some(UserLocationInfo::Other {
+                    location_type: *location_type,
+                    data: data.to_vec(),
+                });
+            }
+        }
+
+        let location = match location_type {
+            0 => UserLocationInfo::Cgi {
+                plmn,
+                lac: be(&data[3..5]) as u16,
+                ci: be(&data[5..7]) as u16,
+            },
+            1 => UserLocationInfo::Sai {
+                plmn,
+                lac: be(&data[3..5]) as u16,
+                sac: be(&data[5..7]) as u16,
+            },
+            2 => UserLocationInfo::Rai {
+                plmn,
+                lac: be(&data[3..5]) as u16,
+                rac: data[5],
+            },
+            128 => UserLocationInfo::Tai {
+                plmn,
+                tac: be(&data[3..5]) as u16,
+            },
+            129 => UserLocationInfo::Ecgi {
+                plmn,
+                eci: be(&data[3..7]) as u32 & 0xfffffff,
+            },
+            130 => UserLocationInfo::TaiEcgi {
+                plmn,
+                tac: be(&data[3..5]) as u16,
+                eci: be(&data[8..12]) as u32 & 0xfffffff,
+            },
+            135 => UserLocationInfo::Ncgi {
+                plmn,
+                nci: be(&data[3..8]) & 0xfffffffff,
+            },
+            136 => UserLocationInfo::Tai5gs {
+                plmn,
+                tac: be(&data[3..6]) as u32,
+            },
+            _ => UserLocationInfo::Tai5gsNcgi {
+                plmn,
+                tac: be(&data[3..6]) as u32,
+                nci: be(&data[9..14]) & 0xfffffffff,
+            },
+        };
+
+        Some(location)
+    }
+}
+
+/// Value of the `3GPP-MS-TimeZone` attribute, the offset between universal
+/// time and local time of the user as defined in TS 24.008 10.5.3.8 and
+/// the Daylight Saving Time adjustment in hours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MsTimeZone {
+    /// Offset from universal time in minutes, a multiple of 15 minutes.
+    pub offset: i16,
+    /// Daylight Saving Time adjustment, `0`, `1` or `2` hours.
+    pub dst: u8,
+}
+
+impl MsTimeZone {
+    /// Builds the time zone from the `offset` field given as `+HH:MM` or
+    /// `-HH:MM` and the optional `dst` field.
+    pub fn from_fields(fields: &[(String, Field)]) -> Option<MsTimeZone> {
+        let offset = match field(fields, "offset")? {
+            Field::String(offset) => parse_offset(offset)?,
+            Field::Integer(_) => return None,
+        };
+        let dst = match field(fields, "dst") {
+            Some(dst) => dst.integer().filter(|dst| *dst <= 2)? as u8,
+            None => 0,
+        };
+
+        // the offset is encoded as 2 digits of quarters of an hour
+        if offset % 15 != 0 || offset.abs() / 15 > 79 {
+            return None;
+        }
+
+        Some(MsTimeZone { offset, dst })
+    }
+
+    /// Returns the fields of the time zone in the form accepted by
+    /// `from_fields`.
+    pub fn fields(&self) -> Fields {
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        let offset = self.offset.abs();
+
+        vec![
+            (
+                "offset".to_string(),
+                Field::String(format!("{}{:02}:{:02}", sign, offset / 60, offset % 60)),
+            ),
+            ("dst".to_string(), Field::Integer(self.dst as u64)),
+        ]
+    }
+
+    /// Returns the wire representation of the time zone.
+    pub fn encode(&self) -> Vec<u8> {
+        // semi-octets of the quarters are swapped and the sign is the bit 4
+        // of the tens
+        let quarters = (self.offset.abs() / 15) as u8;
+        let sign = if self.offset < 0 { 0x08 } else { 0 };
+
+        vec![((quarters % 10) << 4) | (quarters / 10) | sign, self.dst & 0x03]
+    }
+
+    /// Decodes the wire representation of the time zone.
+    pub fn decode(buf: &[u8]) -> Option<MsTimeZone> {
+        if buf.len() != 2 || buf[0] >> 4 > 9 {
+            return None;
+        }
+
+        let quarters = ((buf[0] & 0x07) * 10 + (buf[0] >> 4)) as i16;
+        let sign = if buf[0] & 0x08 != 0 { -1 } else { 1 };
+
+        Some(MsTimeZone {
+            offset: sign * quarters * 15,
+            dst: buf[1] & 0x03,
+        })
+    }
+}
+
+// Parses the offset from universal time given as `+HH:MM` or `-HH:MM` to
+// minutes.
+fn parse_offset(offset: &str) -> Option<i16> {
+    let (sign, offset) = match offset.strip_prefix('-') {
+        Some(offset) => (-1, offset),
+        None => (1, offset.strip_prefix('+').unwrap_or(offset)),
+    };
+
+    let (hours, minutes) = offset.split_once(':')?;
+    let hours = hours.parse::<i16>().ok().filter(|h| *h < 24)?;
+    let minutes = minutes.parse::<i16>().ok().filter(|m| *m < 60)?;
+
+    Some(sign * (hours * 60 + minutes))
+}
+
+/// Direction of the traffic a packet filter applies to as defined in TS
+/// 24.008 10.5.6.12.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    PreRel7,
+    Downlink,
+    Uplink,
+    Bidirectional,
+}
+
+impl Direction {
+    const NAMES: [(Direction, &'static str); 4] = [
+        (Direction::PreRel7, "pre-rel7"),
+        (Direction::Downlink, "downlink"),
+        (Direction::Uplink, "uplink"),
+        (Direction::Bidirectional, "bidirectional"),
+    ];
+
+    fn from_name(name: &str) -> Option<Direction> {
+        Direction::NAMES
+            .iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+            .map(|(d, _)| *d)
+    }
+
+    fn name(&self) -> &'static str {
+        Direction::NAMES.iter().find(|(d, _)| d == self).unwrap().1
+    }
+}
+
+/// Component of a packet filter as defined in TS 24.008 10.5.6.12.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterComponent {
+    RemoteIpv4(Ipv4Addr, Ipv4Addr),
+    LocalIpv4(Ipv4Addr, Ipv4Addr),
+    RemoteIpv6(Ipv6Addr, u8),
+    LocalIpv6(Ipv6Addr, u8),
+    Protocol(u8),
+    LocalPort(u16),
+    LocalPortRange(u16, u16),
+    RemotePort(u16),
+    RemotePortRange(u16, u16),
+    Spi(u32),
+    TypeOfService(u8, u8),
+    FlowLabel(u32),
+}
+
+impl FilterComponent {
+    // Builds the component from the field with the given `name`. Returns
+    // `None` if the field is not a component and `Some(None)` if the value
+    // of the component is invalid.
+    fn from_field(name: &str, field: &Field) -> Option<Option<FilterComponent>> {
+        let port = |local: bool| {
+            let component = match field.string(0).split_once('-') {
+                Some((from, to)) => {
+                    let (from, to) = (
+                        from.trim().parse::<u16>().ok()?,
+                        to.trim().parse::<u16>().ok()?,
+                    );
+                    if local {
+                        FilterComponent::LocalPortRange(from, to)
+                    } else {
+                        FilterComponent::RemotePortRange(from, to)
+                    }
+                }
+                None => {
+                    let port = field.integer().and_then(|p| u16::try_from(p).ok())?;
+                    if local {
+                        FilterComponent::LocalPort(port)
+                    } else {
+                        FilterComponent::RemotePort(port)
+                    }
+                }
+            };
+            Some(component)
+        };
+        let address = |local: bool| {
+            let address = field.string(0);
+            let (addr, prefix) = match address.split_once('/') {
+                Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().ok()?)),
+                None => (address.as_str(), None),
+            };
+
+            if let Ok(addr) = addr.parse::<Ipv4Addr>() {
+                let prefix = prefix.unwrap_or(32);
+                if prefix > 32 {
+                    return None;
+                }
+                let mask = Ipv4Addr::from(u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0));
+                return Some(if local {
+                    FilterComponent::LocalIpv4(addr, mask)
+                } else {
+                    FilterComponent::RemoteIpv4(addr, mask)
+                });
+            }
+
+            let addr = addr.parse::<Ipv6Addr>().ok()?;
+            let prefix = prefix.unwrap_or(128);
+            if prefix > 128 {
+                return None;
+            }
+            Some(if local {
+                FilterComponent::LocalIpv6(addr, prefix)
+            } else {
+                FilterComponent::RemoteIpv6(addr, prefix)
+            })
+        };
+
+        let component = match name {
+            "remote_address" => address(false),
+            "local_address" => address(true),
+            "protocol" => field
+                .integer()
+                .and_then(|p| u8::try_from(p).ok())
+                .map(FilterComponent::Protocol),
+            "local_port" => port(true),
+            "remote_port" => port(false),
+            "spi" => field
+                .integer()
+                .and_then(|spi| u32::try_from(spi).ok())
+                .map(FilterComponent::Spi),
+            "tos" => {
+                let tos = field.string(0);
+                let (tos, mask) = tos.split_once('/').unwrap_or((tos.as_str(), "0xff"));
+                let byte = |s: &str| {
+                    Field::String(s.trim().to_string())
+                        .integer()
+                        .and_then(|n| u8::try_from(n).ok())
+                };
+                byte(tos)
+                    .zip(byte(mask))
+                    .map(|(tos, mask)| FilterComponent::TypeOfService(tos, mask))
+            }
+            "flow_label" => field
+                .integer()
+                .filter(|label| *label <= 0xfffff)
+                .map(|label| FilterComponent::FlowLabel(label as u32)),
+            _ => return None,
+        };
+
+        Some(component)
+    }
+
+    fn field(&self) -> (&'static str, Field) {
+        let address = |addr: String, prefix: u32, max: u32| {
+            if prefix == max {
+                Field::String(addr)
+            } else {
+                Field::String(format!("{}/{}", addr, prefix))
+            }
+        };
+
+        match self {
+            FilterComponent::RemoteIpv4(addr, mask) => (
+                "remote_address",
+                address(addr.to_string(), u32::from(*mask).count_ones(), 32),
+            ),
+            FilterComponent::LocalIpv4(addr, mask) => (
+                "local_address",
+                address(addr.to_string(), u32::from(*mask).count_ones(), 32),
+            ),
+            FilterComponent::RemoteIpv6(addr, prefix) => (
+                "remote_address",
+                address(addr.to_string(), *prefix as u32, 128),
+            ),
+            FilterComponent::LocalIpv6(addr, prefix) => (
+                "local_address",
+                address(addr.to_string(), *prefix as u32, 128),
+            ),
+            FilterComponent::Protocol(protocol) => ("protocol", Field::Integer(*protocol as u64)),
+            FilterComponent::LocalPort(port) => ("local_port", Field::Integer(*port as u64)),
+            FilterComponent::LocalPortRange(from, to) => {
+                ("local_port", Field::String(format!("{}-{}", from, to)))
+            }
+            FilterComponent::RemotePort(port) => ("remote_port", Field::Integer(*port as u64)),
+            FilterComponent::RemotePortRange(from, to) => {
+                ("remote_port", Field::String(format!("{}-{}", from, to)))
+            }
+            FilterComponent::Spi(spi) => ("spi", Field::Integer(*spi as u64)),
+            FilterComponent::TypeOfService(tos, mask) => (
+                "tos",
+                Field::String(format!("0x{:02x}/0x{:02x}", tos, mask)),
+            ),
+            FilterComponent::FlowLabel(label) => ("flow_label", Field::Integer(*label as u64)),
+        }
+    }
+
+    // Returns the packet filter component type identifier.
+    fn type_id(&self) -> u8 {
+        match self {
+            FilterComponent::RemoteIpv4(..) => 0x10,
+            FilterComponent::LocalIpv4(..) => 0x11,
+            FilterComponent::RemoteIpv6(..) => 0x21,
+            FilterComponent::LocalIpv6(..) => 0x23,
+            FilterComponent::Protocol(_) => 0x30,
+            FilterComponent::LocalPort(_) => 0x40,
+            FilterComponent::LocalPortRange(..) => 0x41,
+            FilterComponent::RemotePort(_) => 0x50,
+            FilterComponent::RemotePortRange(..) => 0x51,
+            FilterComponent::Spi(_) => 0x60,
+            FilterComponent::TypeOfService(..) => 0x70,
+            FilterComponent::FlowLabel(_) => 0x80,
+        }
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(self.type_id());
+        match self {
+            FilterComponent::RemoteIpv4(addr, mask) => {
+                buf.extend_from_slice(&addr.octets());
+                buf.extend_from_slice(&mask.octets());
+            }
+            FilterComponent::LocalIpv4(addr, mask) => {
+                buf.extend_from_slice(&addr.octets());
+                buf.extend_from_slice(&mask.octets());
+            }
+            FilterComponent::RemoteIpv6(addr, prefix) => {
+                buf.extend_from_slice(&addr.octets());
+                buf.push(*prefix);
+            }
+            FilterComponent::LocalIpv6(addr, prefix) => {
+                buf.extend_from_slice(&addr.octets());
+                buf.push(*prefix);
+            }
+            FilterComponent::Protocol(protocol) => buf.push(*protocol),
+            FilterComponent::LocalPort(port) => buf.extend_from_slice(&port.to_be_bytes()),
+            FilterComponent::LocalPortRange(from, to) => {
+                buf.extend_from_slice(&from.to_be_bytes());
+                buf.extend_from_slice(&to.to_be_bytes());
+            }
+            FilterComponent::RemotePort(port) => buf.extend_from_slice(&port.to_be_bytes()),
+            FilterComponent::RemotePortRange(from, to) => {
+                buf.extend_from_slice(&from.to_be_bytes());
+                buf.extend_from_slice(&to.to_be_bytes());
+            }
+            FilterComponent::Spi(spi) => buf.extend_from_slice(&spi.to_be_bytes()),
+            FilterComponent::TypeOfService(tos, mask) => buf.extend_from_slice(&[*tos, *mask]),
+            FilterComponent::FlowLabel(label) => buf.extend_from_slice(&label.to_be_bytes()[1..]),
+        }
+    }
+
+    // Decodes the component at the beginning of the given `buf` and returns
+    // it with the number of consumed octets.
+    fn decode(buf: &[u8]) -> Option<(FilterComponent, usize)> {
+        let (component_type, data) = buf.split_first()?;
+        let size = match component_type {
+            0x10 | 0x11 => 8,
+            0x21 | 0x23 => 17,
+            0x30 => 1,
+            0x40 | 0x50 => 2,
+            0x41 | 0x51 | 0x60 => 4,
+            0x70 => 2,
+            0x80 => 3,
+            _ => return None,
+        };
+        let data = data.get(..size)?;
+
+        let ipv4 = |b: &[u8]| Ipv4Addr::new(b[0], b[1], b[2], b[3]);
+        let ipv6 = |b: &[u8]| Ipv6Addr::from(<[u8; 16]>::try_from(b).unwrap());
+        let u16_at = |i: usize| u16::from_be_bytes([data[i], data[i + 1]]);
+
+        let component = match component_type {
+            0x10 => FilterComponent::RemoteIpv4(ipv4(&data[..4]), ipv4(&data[4..])),
+            0x11 => FilterComponent::LocalIpv4(ipv4(&data[..4]), ipv4(&data[4..])),
+            0x21 => FilterComponent::RemoteIpv6(ipv6(&data[..16]), data[16]),
+            0x23 => FilterComponent::LocalIpv6(ipv6(&data[..16]), data[16]),
+            0x30 => FilterComponent::Protocol(data[0]),
+            0x40 => FilterComponent::LocalPort(u16_at(0)),
+            0x41 => FilterComponent::LocalPortRange(u16_at(0), u16_at(2)),
+            0x50 => FilterComponent::RemotePort(u16_at(0)),
+            0x51 => FilterComponent::RemotePortRange(u16_at(0), u16_at(2)),
+            0x60 => FilterComponent::Spi(u32::from_be_bytes(data.try_into().unwrap())),
+            0x70 => FilterComponent::TypeOfService(data[0], data[1]),
+            _ => FilterComponent::FlowLabel(
+                u32::from_be_bytes([0, data[0], data[1], data[2]]) & 0xfffff,
+            ),
+        };
+
+        Some((component, size + 1))
+    }
+}
+
+/// Value of the `3GPP-Packet-Filter` attribute, a packet filter of a
+/// Traffic Flow Template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PacketFilter {
+    /// Packet filter identifier.
+    pub id: u8,
+    /// Packet filter evaluation precedence.
+    pub precedence: u8,
+    /// Direction of the traffic the filter applies to.
+    pub direction: Direction,
+    /// Components of the filter in the order they are encoded.
+    pub components: Vec<FilterComponent>,
+}
+
+impl PacketFilter {
+    /// Builds the packet filter from the `id`, `precedence` and `direction`
+    /// (`pre-rel7`, `downlink`, `uplink` or `bidirectional`) fields and the
+    /// fields of the components: `remote_address` and `local_address` given
+    /// as IPv4 or IPv6 addresses with optional prefix length, `protocol`,
+    /// `local_port` and `remote_port` given as a port or a range of ports
+    /// like `1000-2000`, `spi`, `tos` given as type of service with optional
+    /// mask like `0x10/0xfc` and `flow_label`. Components are ordered by
+    /// their type identifiers.
+    pub fn from_fields(fields: &[(String, Field)]) -> Option<PacketFilter> {
+        let direction = match field(fields, "direction") {
+            Some(direction) => Direction::from_name(&direction.string(0))?,
+            None => Direction::Bidirectional,
+        };
+
+        let mut components = Vec::new();
+        for (name, value) in fields {
+            match FilterComponent::from_field(name, value) {
+                Some(component) => components.push(component?),
+                None if ["id", "precedence", "direction"].contains(&name.as_str()) => {}
+                None => return None,
+            }
+        }
+        components.sort_by_key(FilterComponent::type_id);
+
+        Some(PacketFilter {
+            id: integer(fields, "id", 0x0f)? as u8,
+            precedence: integer(fields, "precedence", u8::MAX as u64)? as u8,
+            direction,
+            components,
+        })
+    }
+
+    /// Returns the fields of the packet filter in the form accepted by
+    /// `from_fields`.
+    pub fn fields(&self) -> Fields {
+        let mut fields = vec![
+            ("id".to_string(), Field::Integer(self.id as u64)),
+            (
+                "precedence".to_string(),
+                Field::Integer(self.precedence as u64),
+            ),
+            (
+                "direction".to_string(),
+                Field::String(self.direction.name().to_string()),
+            ),
+        ];
+
+        for component in self.components.iter() {
+            let (name, field) = component.field();
+            fields.push((name.to_string(), field));
+        }
+
+        fields
+    }
+
+    /// Returns the wire representation of the packet filter.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut contents = Vec::new();
+        for component in self.components.iter() {
+            component.encode(&mut contents);
+        }
+
+        let direction = match self.direction {
+            Direction::PreRel7 => 0,
+            Direction::Downlink => 1,
+            Direction::Uplink => 2,
+            Direction::Bidirectional => 3,
+        };
+
+        let mut buf = vec![self.id, self.precedence, contents.len() as u8, direction];
+        buf.extend_from_slice(&contents);
+        buf
+    }
+
+    /// Decodes the wire representation of the packet filter.
+    pub fn decode(buf: &[u8]) -> Option<PacketFilter> {
+        if buf.len() < 4 || buf.len() != 4 + buf[2] as usize {
+            return None;
+        }
+
+        let direction = Direction::NAMES[(buf[3] & 0x03) as usize].0;
+        let mut components = Vec::new();
+        let mut contents = &buf[4..];
+        while !contents.is_empty() {
+            let (component, size) = FilterComponent::decode(contents)?;
+            components.push(component);
+            contents = &contents[size..];
+        }
+
+        Some(PacketFilter {
+            id: buf[0] & 0x0f,
+            precedence: buf[1],
+            direction,
+            components,
+        })
+    }
+}
+
+// Parses the `key=value` pairs separated by whitespaces to the fields.
+pub(crate) fn parse_fields(s: &str) -> Option<Fields> {
+    s.split_whitespace()
+        .map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            Some((name.to_string(), Field::String(value.to_string())))
+        })
+        .collect()
+}
+
+// Formats the fields as `key=value` pairs separated by spaces.
+pub(crate) fn format_fields(fields: &[(String, Field)]) -> String {
+    fields
+        .iter()
+        .map(|(name, field)| format!("{}={}", name, field))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Encodes the given `data` as `0x` prefixed hex string.
+fn encode_hex(data: &[u8]) -> String {
+    let mut hex = String::from("0x");
+    for b in data {
+        hex.push_str(&format!("{:02x}", b));
+    }
+    hex
+}
+
+// Decodes the given hex string without the `0x` prefix. Returns `None` if the
+// string has an odd length or contains non-hex digits.
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let chunks = hex.as_bytes().chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+
+    chunks
+        .map(|chunk| u8::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tgpp_tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, Field)]) -> Fields {
+        pairs
+            .iter()
+            .map(|(name, field)| (name.to_string(), field.clone()))
+            .collect()
+    }
+
+    fn s(s: &str) -> Field {
+        Field::String(s.to_string())
+    }
+
+    #[test]
+    fn user_location_info_test() {
+        let uli = UserLocationInfo::from_fields(&fields(&[
+            ("type", s("TAI+ECGI")),
+            ("mcc", s("262")),
+            ("mnc", s("01")),
+            ("tac", Field::Integer(1)),
+            ("eci", Field::Integer(42)),
+        ]))
+        .unwrap();
+
+        let encoded = vec![130, 0x62, 0xf2, 0x10, 0, 1, 0x62, 0xf2, 0x10, 0, 0, 0, 42];
+        assert_eq!(uli.encode(), encoded);
+        assert_eq!(UserLocationInfo::decode(&encoded).unwrap(), uli);
+        assert_eq!(
+            uli.fields(),
+            fields(&[
+                ("type", s("TAI+ECGI")),
+                ("mcc", s("262")),
+                ("mnc", s("01")),
+                ("tac", Field::Integer(1)),
+                ("eci", Field::Integer(42)),
+            ])
+        );
+
+        // 3 digits MNC and integer fields given as strings
+        let uli = UserLocationInfo::from_fields(&fields(&[
+            ("type", s("cgi")),
+            ("mcc", Field::Integer(310)),
+            ("mnc", s("410")),
+            ("lac", s("0x1234")),
+            ("ci", Field::Integer(5)),
+        ]))
+        .unwrap();
+        assert_eq!(uli.encode(), vec![0, 0x13, 0x00, 0x14, 0x12, 0x34, 0, 5]);
+        assert_eq!(UserLocationInfo::decode(&uli.encode()).unwrap(), uli);
+
+        let uli = UserLocationInfo::decode(&[200, 1, 2]).unwrap();
+        assert_eq!(
+            uli.fields(),
+            fields(&[("type", Field::Integer(200)), ("data", s("0x0102"))])
+        );
+        assert_eq!(UserLocationInfo::from_fields(&uli.fields()).unwrap(), uli);
+
+        assert!(
+            UserLocationInfo::from_fields(&fields(&[("type", s("TAI")), ("mcc", s("262"))]))
+                .is_none()
+        );
+        assert!(UserLocationInfo::from_fields(&fields(&[
+            ("type", s("ECGI")),
+            ("mcc", s("262")),
+            ("mnc", s("01")),
+            ("eci", Field::Integer(0x10000000)),
+        ]))
+        .is_none());
+        assert!(UserLocationInfo::decode(&[128, 0x62, 0xf2, 0x10, 0]).is_none());
+
+        // TAI and ECGI of different PLMNs
+        let encoded = vec![130, 0x62, 0xf2, 0x10, 0, 1, 0x62, 0xf2, 0x20, 0, 0, 0, 42];
+        let uli = UserLocationInfo::decode(&encoded).unwrap();
+        assert_eq!(
+            uli,
+            UserLocationInfo::Other {
+                location_type: 130,
+                data: encoded[1..].to_vec(),
+            }
+        );
+        assert_eq!(uli.encode(), encoded);
+        assert_eq!(UserLocationInfo::from_fields(&uli.fields()).unwrap(), uli);
+        assert!(UserLocationInfo::decode(&[
+            130, 0x62, 0xf2, 0x10, 0, 1, 0x62, 0xfa, 0x20, 0, 0, 0, 42
+        ])
+        .is_none());
+    }
+
+    #[test]
+    fn ms_timezone_test() {
+        let tz = MsTimeZone::from_fields(&fields(&[
+            ("offset", s("+02:00")),
+            ("dst", Field::Integer(1)),
+        ]))
+        .unwrap();
+        assert_eq!(
+            tz,
+            MsTimeZone {
+                offset: 120,
+                dst: 1
+            }
+        );
+        assert_eq!(tz.encode(), vec![0x80, 0x01]);
+        assert_eq!(MsTimeZone::decode(&[0x80, 0x01]).unwrap(), tz);
+
+        let tz = MsTimeZone::from_fields(&fields(&[("offset", s("-05:45"))])).unwrap();
+        assert_eq!(tz.encode(), vec![0x3a, 0x00]);
+        assert_eq!(MsTimeZone::decode(&tz.encode()).unwrap(), tz);
+        assert_eq!(
+            tz.fields(),
+            fields(&[("offset", s("-05:45")), ("dst", Field::Integer(0))])
+        );
+
+        assert!(MsTimeZone::from_fields(&fields(&[("offset", s("+02:10"))])).is_none());
+        assert!(MsTimeZone::from_fields(&fields(&[
+            ("offset", s("+02:00")),
+            ("dst", Field::Integer(3))
+        ]))
+        .is_none());
+        assert!(MsTimeZone::decode(&[0x80]).is_none());
+    }
+
+    #[test]
+    fn packet_filter_test() {
+        let filter = PacketFilter::from_fields(&fields(&[
+            ("id", Field::Integer(1)),
+            ("precedence", Field::Integer(255)),
+            ("direction", s("uplink")),
+            ("remote_address", s("10.0.0.0/8")),
+            ("protocol", Field::Integer(17)),
+            ("remote_port", s("1000-2000")),
+            ("local_port", Field::Integer(53)),
+            ("tos", s("0x10/0xfc")),
+        ]))
+        .unwrap();
+
+        let encoded = vec![
+            1, 255, 22, 2, 0x10, 10, 0, 0, 0, 255, 0, 0, 0, 0x30, 17, 0x40, 0, 53, 0x51, 0x03,
+            0xe8, 0x07, 0xd0, 0x70, 0x10, 0xfc,
+        ];
+        assert_eq!(filter.encode(), encoded);
+        assert_eq!(PacketFilter::decode(&encoded).unwrap(), filter);
+        assert_eq!(PacketFilter::from_fields(&filter.fields()).unwrap(), filter);
+
+        let filter = PacketFilter::from_fields(&fields(&[
+            ("id", Field::Integer(2)),
+            ("precedence", Field::Integer(0)),
+            ("local_address", s("2001:db8::/32")),
+            ("flow_label", Field::Integer(0xabcde)),
+        ]))
+        .unwrap();
+        assert_eq!(filter.direction, Direction::Bidirectional);
+        assert_eq!(PacketFilter::decode(&filter.encode()).unwrap(), filter);
+        assert_eq!(
+            filter.fields()[3],
+            ("local_address".to_string(), s("2001:db8::/32"))
+        );
+
+        assert!(PacketFilter::from_fields(&fields(&[("id", Field::Integer(1))])).is_none());
+        assert!(PacketFilter::from_fields(&fields(&[
+            ("id", Field::Integer(1)),
+            ("precedence", Field::Integer(0)),
+            ("unknown", Field::Integer(0)),
+        ]))
+        .is_none());
+        assert!(PacketFilter::decode(&[1, 0, 2, 3, 0x30]).is_none());
+    }
+}
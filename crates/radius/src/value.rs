@@ -0,0 +1,883 @@
+/// Defines the types and auxilary functions to represent and work with
+/// typed values of RADIUS attributes.
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use super::error::RadiusError;
+use super::tgpp::{self, decode_hex, Fields, MsTimeZone, PacketFilter, UserLocationInfo};
+
+/// Size of the `Reserved` and `Prefix-Length` fields of IPv4 and IPv6
+/// prefixes.
+const PREFIX_HEADER_LENGTH: usize = 2;
+
+/// Size of an interface identifier.
+const IFID_LENGTH: usize = 8;
+
+/// Number of seconds in a day.
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// Data type of a RADIUS attribute as declared by the `type` key of an
+/// attribute within RADIUS dictionaries.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DataType {
+    /// UTF-8 text.
+    String,
+    /// 32 bit unsigned integer in network byte order.
+    Integer,
+    /// 64 bit unsigned integer in network byte order.
+    Integer64,
+    /// IPv4 address.
+    Ipv4,
+    /// IPv6 address.
+    Ipv6,
+    /// Raw binary data.
+    Octets,
+    /// 8 bit unsigned integer.
+    Byte,
+    /// 16 bit unsigned integer in network byte order.
+    Short,
+    /// 32 bit signed integer in network byte order.
+    Signed,
+    /// Seconds since the Unix epoch as 32 bit unsigned integer (RFC 8044
+    /// 3.5).
+    Date,
+    /// Duration in seconds as 32 bit unsigned integer.
+    TimeDelta,
+    /// IPv4 prefix of RFC 8044 3.11.
+    Ipv4Prefix,
+    /// IPv6 prefix of RFC 8044 3.10.
+    Ipv6Prefix,
+    /// Interface identifier of an IPv6 address (RFC 8044 3.7).
+    Ifid,
+    /// IPv4 or IPv6 address distinguished by the length of the value.
+    ComboIp,
+    /// Raw value of the Vendor-Specific attribute.
+    Vsa,
+    /// Sequence of values of the given fixed size type.
+    Array(Box<DataType>),
+    /// 3GPP User Location Info (TS 29.061 16.4.7.2).
+    UserLocationInfo,
+    /// 3GPP MS Time Zone (TS 29.061 16.4.7.2).
+    MsTimeZone,
+    /// 3GPP Packet Filter (TS 29.061 16.4.7.2).
+    PacketFilter,
+    /// Reference to a type defined within the `types` section of a RADIUS
+    /// dictionary.
+    Named(String),
+}
+
+impl DataType {
+    /// Returns the size of an encoded value of the type if the type has fixed
+    /// size.
+    pub fn fixed_size(&self) -> Option<usize> {
+        match self {
+            DataType::Integer => Some(4),
+            DataType::Integer64 => Some(8),
+            DataType::Ipv4 => Some(4),
+            DataType::Ipv6 => Some(16),
+            DataType::Byte => Some(1),
+            DataType::Short => Some(2),
+            DataType::Signed | DataType::Date | DataType::TimeDelta => Some(4),
+            DataType::Ipv4Prefix => Some(PREFIX_HEADER_LENGTH + 4),
+            DataType::Ifid => Some(8),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for DataType {
+    type Err = RadiusError;
+
+    /// Parses the `type` of a RADIUS attribute from a dictionary.
+    ///
+    /// Everything that is not a base type is treated as reference to a type
+    /// from the `types` section of a dictionary.
+    fn from_str(s: &str) -> Result<DataType, RadiusError> {
+        let s = s.trim();
+
+        if let Some(element) = s.strip_prefix("array ") {
+            let element = element.parse::<DataType>()?;
+            return match element.fixed_size() {
+                Some(_) => Ok(DataType::Array(Box::new(element))),
+                None => Err(RadiusError::InvalidDataType(s.to_string())),
+            };
+        }
+
+        match s {
+            "" => Err(RadiusError::InvalidDataType(s.to_string())),
+            "string" => Ok(DataType::String),
+            "integer" => Ok(DataType::Integer),
+            "integer64" => Ok(DataType::Integer64),
+            "ipv4" => Ok(DataType::Ipv4),
+            "ipv6" => Ok(DataType::Ipv6),
+            "octets" => Ok(DataType::Octets),
+            "byte" => Ok(DataType::Byte),
+            "short" => Ok(DataType::Short),
+            "signed" => Ok(DataType::Signed),
+            "date" => Ok(DataType::Date),
+            "time_delta" => Ok(DataType::TimeDelta),
+            "ipv4prefix" => Ok(DataType::Ipv4Prefix),
+            "ipv6prefix" => Ok(DataType::Ipv6Prefix),
+            "ifid" => Ok(DataType::Ifid),
+            "combo-ip" => Ok(DataType::ComboIp),
+            "vsa" => Ok(DataType::Vsa),
+            "user-location-info" => Ok(DataType::UserLocationInfo),
+            "ms-timezone" => Ok(DataType::MsTimeZone),
+            "packet-filter" => Ok(DataType::PacketFilter),
+            _ => Ok(DataType::Named(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataType::String => write!(f, "string"),
+            DataType::Integer => write!(f, "integer"),
+            DataType::Integer64 => write!(f, "integer64"),
+            DataType::Ipv4 => write!(f, "ipv4"),
+            DataType::Ipv6 => write!(f, "ipv6"),
+            DataType::Octets => write!(f, "octets"),
+            DataType::Byte => write!(f, "byte"),
+            DataType::Short => write!(f, "short"),
+            DataType::Signed => write!(f, "signed"),
+            DataType::Date => write!(f, "date"),
+            DataType::TimeDelta => write!(f, "time_delta"),
+            DataType::Ipv4Prefix => write!(f, "ipv4prefix"),
+            DataType::Ipv6Prefix => write!(f, "ipv6prefix"),
+            DataType::Ifid => write!(f, "ifid"),
+            DataType::ComboIp => write!(f, "combo-ip"),
+            DataType::Vsa => write!(f, "vsa"),
+            DataType::Array(element) => write!(f, "array {}", element),
+            DataType::UserLocationInfo => write!(f, "user-location-info"),
+            DataType::MsTimeZone => write!(f, "ms-timezone"),
+            DataType::PacketFilter => write!(f, "packet-filter"),
+            DataType::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Typed value of a RADIUS attribute.
+///
+/// Values of `combo-ip` attributes are represented as `Ipv4` or `Ipv6`
+/// values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeValue {
+    String(String),
+    Integer(u32),
+    Integer64(u64),
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    Octets(Vec<u8>),
+    Byte(u8),
+    Short(u16),
+    Signed(i32),
+    Date(u32),
+    TimeDelta(u32),
+    Ipv4Prefix(Ipv4Addr, u8),
+    Ipv6Prefix(Ipv6Addr, u8),
+    Ifid([u8; IFID_LENGTH]),
+    Array(Vec<AttributeValue>),
+    UserLocationInfo(UserLocationInfo),
+    MsTimeZone(MsTimeZone),
+    PacketFilter(PacketFilter),
+}
+
+impl AttributeValue {
+    /// Parses the textual representation of a value of the given
+    /// `data_type`.
+    ///
+    /// Integers could be given in decimal or in hexadecimal with the `0x`
+    /// prefix. Octets given with the `0x` prefix are decoded from hex,
+    /// otherwise the bytes of the string are used as is. Elements of an array
+    /// are separated by commas. Structured 3GPP values are given as `key=value`
+    /// fields separated by spaces (see `from_fields`) or as hex encoded octets
+    /// with the `0x` prefix.
+    ///
+    /// Dates are given as RFC 3339 timestamps like `2021-06-01T12:00:00Z` or
+    /// as the number of seconds since the Unix epoch, prefixes as
+    /// `address/length` and interface identifiers as four groups of hex
+    /// digits like `0211:22ff:fe33:4455`.
+    ///
+    /// `DataType::Named` should be resolved to its base type before parsing,
+    /// unresolved named types are treated as `integer`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net::Ipv4Addr;
+    /// use radius::value::{AttributeValue, DataType};
+    ///
+    /// let value = AttributeValue::parse(&DataType::Ipv4, "127.0.0.1").unwrap();
+    ///
+    /// assert_eq!(value, AttributeValue::Ipv4(Ipv4Addr::new(127, 0, 0, 1)));
+    /// assert_eq!(value.encode(), vec![127, 0, 0, 1]);
+    /// ```
+    pub fn parse(data_type: &DataType, s: &str) -> Result<AttributeValue, RadiusError> {
+        let invalid = || RadiusError::InvalidAttributeValue(data_type.clone(), s.to_string());
+
+        match data_type {
+            DataType::String => Ok(AttributeValue::String(s.to_string())),
+            DataType::Date if s.contains('-') => parse_date(s)
+                .and_then(|seconds| u32::try_from(seconds).ok())
+                .map(AttributeValue::Date)
+                .ok_or_else(invalid),
+            DataType::Integer
+            | DataType::Integer64
+            | DataType::Byte
+            | DataType::Short
+            | DataType::Signed
+            | DataType::Date
+            | DataType::TimeDelta
+            | DataType::Named(_) => {
+                let n = parse_integer(s).ok_or_else(invalid)?;
+                Self::from_integer(data_type, n).map_err(|_| invalid())
+            }
+            DataType::Ipv4 => s
+                .trim()
+                .parse::<Ipv4Addr>()
+                .map(AttributeValue::Ipv4)
+                .map_err(|_| invalid()),
+            DataType::Ipv6 => s
+                .trim()
+                .parse::<Ipv6Addr>()
+                .map(AttributeValue::Ipv6)
+                .map_err(|_| invalid()),
+            DataType::ComboIp => Self::parse(&DataType::Ipv4, s)
+                .or_else(|_| Self::parse(&DataType::Ipv6, s))
+                .map_err(|_| invalid()),
+            DataType::Ipv4Prefix | DataType::Ipv6Prefix => {
+                let (address, length) = match s.trim().split_once('/') {
+                    Some((address, length)) => {
+                        (address, Some(length.parse::<u8>().map_err(|_| invalid())?))
+                    }
+                    None => (s.trim(), None),
+                };
+                let value = match data_type {
+                    DataType::Ipv4Prefix => {
+                        let address = address.parse::<Ipv4Addr>().map_err(|_| invalid())?;
+                        AttributeValue::Ipv4Prefix(address, length.unwrap_or(32))
+                    }
+                    _ => {
+                        let address = address.parse::<Ipv6Addr>().map_err(|_| invalid())?;
+                        AttributeValue::Ipv6Prefix(address, length.unwrap_or(128))
+                    }
+                };
+                // the encoded prefix is validated the same way as a received one
+                Self::decode(data_type, &value.encode()).map_err(|_| invalid())
+            }
+            DataType::Ifid => {
+                let groups = s
+                    .trim()
+                    .split(':')
+                    .map(|group| match group.len() {
+                        1..=4 => u16::from_str_radix(group, 16).ok(),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<u16>>>()
+                    .filter(|groups| groups.len() == IFID_LENGTH / 2)
+                    .ok_or_else(invalid)?;
+                let mut ifid = [0; IFID_LENGTH];
+                for (octets, group) in ifid.chunks_exact_mut(2).zip(groups) {
+                    octets.copy_from_slice(&group.to_be_bytes());
+                }
+                Ok(AttributeValue::Ifid(ifid))
+            }
+            DataType::Octets | DataType::Vsa => match s.strip_prefix("0x") {
+                Some(hex) => decode_hex(hex).map(AttributeValue::Octets).ok_or_else(invalid),
+                None => Ok(AttributeValue::Octets(s.as_bytes().to_vec())),
+            },
+            DataType::Array(element) => s
+                .split(',')
+                .map(|e| Self::parse(element, e))
+                .collect::<Result<Vec<AttributeValue>, RadiusError>>()
+                .map(AttributeValue::Array),
+            DataType::UserLocationInfo | DataType::MsTimeZone | DataType::PacketFilter => {
+                match s.trim().strip_prefix("0x") {
+                    Some(hex) => Self::decode(data_type, &decode_hex(hex).ok_or_else(invalid)?)
+                        .map_err(|_| invalid()),
+                    None => Self::from_fields(data_type, &tgpp::parse_fields(s).ok_or_else(invalid)?),
+                }
+            }
+        }
+    }
+
+    /// Builds a structured 3GPP value of the given `data_type` from the given
+    /// named `fields`.
+    ///
+    /// Returns `RadiusError::InvalidAttributeValue` if the fields do not
+    /// describe a valid value or the type is not structured.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::tgpp::Field;
+    /// use radius::value::{AttributeValue, DataType};
+    ///
+    /// let fields = vec![
+    ///     ("offset".to_string(), Field::String("+02:00".to_string())),
+    ///     ("dst".to_string(), Field::Integer(1)),
+    /// ];
+    /// let value = AttributeValue::from_fields(&DataType::MsTimeZone, &fields).unwrap();
+    ///
+    /// assert_eq!(value.encode(), vec![0x80, 0x01]);
+    /// assert_eq!(value.fields(), Some(fields));
+    /// ```
+    pub fn from_fields(data_type: &DataType, fields: &Fields) -> Result<AttributeValue, RadiusError> {
+        let value = match data_type {
+            DataType::UserLocationInfo => {
+                UserLocationInfo::from_fields(fields).map(AttributeValue::UserLocationInfo)
+            }
+            DataType::MsTimeZone => MsTimeZone::from_fields(fields).map(AttributeValue::MsTimeZone),
+            DataType::PacketFilter => {
+                PacketFilter::from_fields(fields).map(AttributeValue::PacketFilter)
+            }
+            _ => None,
+        };
+
+        value.ok_or_else(|| {
+            RadiusError::InvalidAttributeValue(data_type.clone(), tgpp::format_fields(fields))
+        })
+    }
+
+    /// Returns the named fields of a structured 3GPP value.
+    pub fn fields(&self) -> Option<Fields> {
+        match self {
+            AttributeValue::UserLocationInfo(uli) => Some(uli.fields()),
+            AttributeValue::MsTimeZone(tz) => Some(tz.fields()),
+            AttributeValue::PacketFilter(filter) => Some(filter.fields()),
+            _ => None,
+        }
+    }
+
+    /// Converts the given integer to a value of the given `data_type`.
+    ///
+    /// Returns `RadiusError::InvalidAttributeValue` if the integer does not
+    /// fit into the type or the type is not numeric. Integers given for
+    /// `string` attributes are converted to their decimal representation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::value::{AttributeValue, DataType};
+    ///
+    /// assert_eq!(
+    ///     AttributeValue::from_integer(&DataType::Byte, 6).unwrap().encode(),
+    ///     vec![6]
+    /// );
+    /// assert!(AttributeValue::from_integer(&DataType::Byte, 256).is_err());
+    /// ```
+    pub fn from_integer(data_type: &DataType, n: i128) -> Result<AttributeValue, RadiusError> {
+        let invalid = || RadiusError::InvalidAttributeValue(data_type.clone(), n.to_string());
+
+        match data_type {
+            DataType::String => Ok(AttributeValue::String(n.to_string())),
+            DataType::Integer | DataType::Named(_) => u32::try_from(n)
+                .map(AttributeValue::Integer)
+                .map_err(|_| invalid()),
+            DataType::Integer64 => u64::try_from(n)
+                .map(AttributeValue::Integer64)
+                .map_err(|_| invalid()),
+            DataType::Byte => u8::try_from(n)
+                .map(AttributeValue::Byte)
+                .map_err(|_| invalid()),
+            DataType::Short => u16::try_from(n)
+                .map(AttributeValue::Short)
+                .map_err(|_| invalid()),
+            DataType::Signed => i32::try_from(n)
+                .map(AttributeValue::Signed)
+                .map_err(|_| invalid()),
+            DataType::Date => u32::try_from(n)
+                .map(AttributeValue::Date)
+                .map_err(|_| invalid()),
+            DataType::TimeDelta => u32::try_from(n)
+                .map(AttributeValue::TimeDelta)
+                .map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Returns the wire representation of the value.
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            AttributeValue::String(s) => s.as_bytes().to_vec(),
+            AttributeValue::Integer(n) => n.to_be_bytes().to_vec(),
+            AttributeValue::Integer64(n) => n.to_be_bytes().to_vec(),
+            AttributeValue::Ipv4(addr) => addr.octets().to_vec(),
+            AttributeValue::Ipv6(addr) => addr.octets().to_vec(),
+            AttributeValue::Octets(data) => data.clone(),
+            AttributeValue::Byte(n) => vec![*n],
+            AttributeValue::Short(n) => n.to_be_bytes().to_vec(),
+            AttributeValue::Signed(n) => n.to_be_bytes().to_vec(),
+            AttributeValue::Date(n) | AttributeValue::TimeDelta(n) => n.to_be_bytes().to_vec(),
+            AttributeValue::Ipv4Prefix(addr, length) => {
+                [&[0, *length][..], &addr.octets()].concat()
+            }
+            AttributeValue::Ipv6Prefix(addr, length) => {
+                // only the octets covered by the prefix length are sent
+                let octets = (*length as usize).div_ceil(8).min(16);
+                [&[0, *length][..], &addr.octets()[..octets]].concat()
+            }
+            AttributeValue::Ifid(ifid) => ifid.to_vec(),
+            AttributeValue::Array(values) => values.iter().flat_map(|v| v.encode()).collect(),
+            AttributeValue::UserLocationInfo(uli) => uli.encode(),
+            AttributeValue::MsTimeZone(tz) => tz.encode(),
+            AttributeValue::PacketFilter(filter) => filter.encode(),
+        }
+    }
+
+    /// Decodes the wire representation of a value of the given `data_type`.
+    ///
+    /// Returns `RadiusError::InvalidAttributeValueLength` if the length of the
+    /// given `buf` does not match the type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use radius::value::{AttributeValue, DataType};
+    ///
+    /// let value = AttributeValue::decode(&DataType::Integer, &[0, 0, 0, 2]).unwrap();
+    ///
+    /// assert_eq!(value, AttributeValue::Integer(2));
+    /// ```
+    pub fn decode(data_type: &DataType, buf: &[u8]) -> Result<AttributeValue, RadiusError> {
+        let invalid = || RadiusError::InvalidAttributeValueLength(data_type.clone(), buf.len());
+
+        if let Some(size) = data_type.fixed_size() {
+            if buf.len() != size {
+                return Err(invalid());
+            }
+        }
+
+        match data_type {
+            DataType::String => Ok(AttributeValue::String(
+                String::from_utf8_lossy(buf).into_owned(),
+            )),
+            DataType::Integer => Ok(AttributeValue::Integer(u32::from_be_bytes(
+                buf.try_into().unwrap(),
+            ))),
+            DataType::Integer64 => Ok(AttributeValue::Integer64(u64::from_be_bytes(
+                buf.try_into().unwrap(),
+            ))),
+            DataType::Ipv4 => {
+                let octets: [u8; 4] = buf.try_into().unwrap();
+                Ok(AttributeValue::Ipv4(Ipv4Addr::from(octets)))
+            }
+            DataType::Ipv6 => {
+                let octets: [u8; 16] = buf.try_into().unwrap();
+                Ok(AttributeValue::Ipv6(Ipv6Addr::from(octets)))
+            }
+            DataType::Octets | DataType::Vsa => Ok(AttributeValue::Octets(buf.to_vec())),
+            DataType::Byte => Ok(AttributeValue::Byte(buf[0])),
+            DataType::Short => Ok(AttributeValue::Short(u16::from_be_bytes(
+                buf.try_into().unwrap(),
+            ))),
+            DataType::Signed => Ok(AttributeValue::Signed(i32::from_be_bytes(
+                buf.try_into().unwrap(),
+            ))),
+            DataType::Date => Ok(AttributeValue::Date(u32::from_be_bytes(
+                buf.try_into().unwrap(),
+            ))),
+            DataType::TimeDelta => Ok(AttributeValue::TimeDelta(u32::from_be_bytes(
+                buf.try_into().unwrap(),
+            ))),
+            DataType::Ipv4Prefix => {
+                let octets: [u8; 4] = buf[PREFIX_HEADER_LENGTH..].try_into().unwrap();
+                let addr = u32::from_be_bytes(octets);
+                match (buf[0], buf[1]) {
+                    // bits beyond the prefix length must be zero
+                    (0, length @ 0..=32) if addr & !prefix_mask(length as u32, 32) as u32 == 0 => {
+                        Ok(AttributeValue::Ipv4Prefix(Ipv4Addr::from(octets), length))
+                    }
+                    _ => Err(invalid()),
+                }
+            }
+            DataType::Ipv6Prefix => {
+                if buf.len() < PREFIX_HEADER_LENGTH || buf.len() > PREFIX_HEADER_LENGTH + 16 {
+                    return Err(invalid());
+                }
+
+                // the prefix could be shorter than an address, RFC 3162 2.3
+                // allowed to send all of the octets as well
+                let mut octets = [0; 16];
+                octets[..buf.len() - PREFIX_HEADER_LENGTH]
+                    .copy_from_slice(&buf[PREFIX_HEADER_LENGTH..]);
+                let addr = u128::from_be_bytes(octets);
+                match (buf[0], buf[1]) {
+                    (0, length @ 0..=128) if addr & !prefix_mask(length as u32, 128) == 0 => {
+                        Ok(AttributeValue::Ipv6Prefix(Ipv6Addr::from(octets), length))
+                    }
+                    _ => Err(invalid()),
+                }
+            }
+            DataType::Ifid => Ok(AttributeValue::Ifid(buf.try_into().unwrap())),
+            DataType::ComboIp => match buf.len() {
+                4 => Self::decode(&DataType::Ipv4, buf),
+                16 => Self::decode(&DataType::Ipv6, buf),
+                _ => Err(invalid()),
+            },
+            DataType::Named(_) => Self::decode(&DataType::Integer, buf),
+            DataType::Array(element) => {
+                // array elements always have fixed size, see DataType::from_str
+                let chunks = buf.chunks_exact(element.fixed_size().unwrap());
+                if !chunks.remainder().is_empty() {
+                    return Err(invalid());
+                }
+
+                chunks
+                    .map(|chunk| Self::decode(element, chunk))
+                    .collect::<Result<Vec<AttributeValue>, RadiusError>>()
+                    .map(AttributeValue::Array)
+            }
+            DataType::UserLocationInfo => UserLocationInfo::decode(buf)
+                .map(AttributeValue::UserLocationInfo)
+                .ok_or_else(invalid),
+            DataType::MsTimeZone => MsTimeZone::decode(buf)
+                .map(AttributeValue::MsTimeZone)
+                .ok_or_else(invalid),
+            DataType::PacketFilter => PacketFilter::decode(buf)
+                .map(AttributeValue::PacketFilter)
+                .ok_or_else(invalid),
+        }
+    }
+}
+
+impl fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AttributeValue::String(s) => write!(f, "{}", s),
+            AttributeValue::Integer(n) => write!(f, "{}", n),
+            AttributeValue::Integer64(n) => write!(f, "{}", n),
+            AttributeValue::Ipv4(addr) => write!(f, "{}", addr),
+            AttributeValue::Ipv6(addr) => write!(f, "{}", addr),
+            AttributeValue::Octets(data) => {
+                write!(f, "0x")?;
+                for b in data {
+                    write!(f, "{:02x}", b)?;
+                }
+                Ok(())
+            }
+            AttributeValue::Byte(n) => write!(f, "{}", n),
+            AttributeValue::Short(n) => write!(f, "{}", n),
+            AttributeValue::Signed(n) => write!(f, "{}", n),
+            AttributeValue::Date(n) => write!(f, "{}", format_date(*n as i64)),
+            AttributeValue::TimeDelta(n) => write!(f, "{}", n),
+            AttributeValue::Ipv4Prefix(addr, length) => write!(f, "{}/{}", addr, length),
+            AttributeValue::Ipv6Prefix(addr, length) => write!(f, "{}/{}", addr, length),
+            AttributeValue::Ifid(ifid) => {
+                for (i, group) in ifid.chunks_exact(2).enumerate() {
+                    if i > 0 {
+                        write!(f, ":")?;
+                    }
+                    write!(f, "{:02x}{:02x}", group[0], group[1])?;
+                }
+                Ok(())
+            }
+            AttributeValue::Array(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                Ok(())
+            }
+            value => write!(f, "{}", tgpp::format_fields(&value.fields().unwrap_or_default())),
+        }
+    }
+}
+
+// Parses decimal or `0x` prefixed hexadecimal integer.
+fn parse_integer(s: &str) -> Option<i128> {
+    let s = s.trim();
+    match s.strip_prefix("0x") {
+        Some(hex) => i128::from_str_radix(hex, 16).ok(),
+        None => s.parse::<i128>().ok(),
+    }
+}
+
+// Returns the mask of the given prefix `length` within an address of the
+// given `width` in bits.
+fn prefix_mask(length: u32, width: u32) -> u128 {
+    match length {
+        0 => 0,
+        _ => (u128::MAX << (128 - length)) >> (128 - width),
+    }
+}
+
+// Parses RFC 3339 timestamp like `2021-06-01T12:00:00Z` or
+// `2021-06-01T14:00:00+02:00` to the number of seconds since the Unix epoch.
+// Fractions of a second are ignored. Only years that could be held by the
+// 32 bit `date` attributes are accepted.
+fn parse_date(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (date, time) = s.split_once(['T', 't', ' '])?;
+
+    let mut date = date.splitn(3, '-').map(|n| n.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    if !(1970..=2106).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+    {
+        return None;
+    }
+
+    // the time is followed by `Z` or the offset from UTC
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(pos) => time.split_at(pos),
+        None => return None,
+    };
+    let offset = match offset {
+        "Z" | "z" => 0,
+        offset => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = offset[1..].split_once(':')?;
+            let (hours, minutes) = (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?);
+            if !(0..=23).contains(&hours) || !(0..=59).contains(&minutes) {
+                return None;
+            }
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+
+    let time = time.split('.').next()?;
+    let mut time = time.splitn(3, ':').map(|n| n.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if !(0..=23).contains(&hour) || !(0..=59).contains(&minute) || !(0..=60).contains(&second) {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    Some(days * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second - offset)
+}
+
+// Returns the number of days in the given month of the proleptic Gregorian
+// calendar.
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Formats the given number of seconds since the Unix epoch as RFC 3339
+// timestamp in UTC.
+fn format_date(seconds: i64) -> String {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    let seconds = seconds.rem_euclid(SECONDS_PER_DAY);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+// Returns the number of days since the Unix epoch of the given date of the
+// proleptic Gregorian calendar, see
+// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+// Returns the date of the proleptic Gregorian calendar of the given number
+// of days since the Unix epoch, the reverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod value_tests {
+    use super::*;
+
+    #[test]
+    fn data_type_from_str_test() {
+        assert_eq!("string".parse::<DataType>().unwrap(), DataType::String);
+        assert_eq!("integer64".parse::<DataType>().unwrap(), DataType::Integer64);
+        assert_eq!(
+            "array ipv6".parse::<DataType>().unwrap(),
+            DataType::Array(Box::new(DataType::Ipv6))
+        );
+        assert_eq!(
+            "service-type".parse::<DataType>().unwrap(),
+            DataType::Named("service-type".to_string())
+        );
+        assert!("array string".parse::<DataType>().is_err());
+        assert_eq!(DataType::Array(Box::new(DataType::Ipv6)).to_string(), "array ipv6");
+
+        for name in [
+            "short", "signed", "date", "time_delta", "ipv4prefix", "ipv6prefix", "ifid", "combo-ip",
+        ] {
+            assert_eq!(name.parse::<DataType>().unwrap().to_string(), name);
+        }
+        assert!("array ipv4prefix".parse::<DataType>().is_ok());
+        assert!("array ipv6prefix".parse::<DataType>().is_err());
+    }
+
+    #[test]
+    fn format_test() {
+        let format = |data_type: &DataType, s: &str| {
+            AttributeValue::parse(data_type, s).unwrap().to_string()
+        };
+
+        assert_eq!(format(&DataType::Date, "2021-06-01T14:00:00+02:00"), "2021-06-01T12:00:00Z");
+        assert_eq!(format(&DataType::Date, "0"), "1970-01-01T00:00:00Z");
+        assert_eq!(format(&DataType::Date, "4294967295"), "2106-02-07T06:28:15Z");
+        assert_eq!(format(&DataType::Date, "2000-02-29T23:59:59Z"), "2000-02-29T23:59:59Z");
+        assert_eq!(format(&DataType::Date, "2024-02-29T00:00:00Z"), "2024-02-29T00:00:00Z");
+        assert_eq!(format(&DataType::Ifid, "211:22ff:fe33:4455"), "0211:22ff:fe33:4455");
+        assert_eq!(format(&DataType::Ipv6Prefix, "2001:db8::/32"), "2001:db8::/32");
+        assert_eq!(format(&DataType::Signed, "-2"), "-2");
+
+        // RFC 3162 allowed to send the whole address with the prefix
+        let mut prefix = vec![0, 32, 0x20, 0x01, 0x0d, 0xb8];
+        prefix.resize(18, 0);
+        assert_eq!(
+            AttributeValue::decode(&DataType::Ipv6Prefix, &prefix).unwrap().to_string(),
+            "2001:db8::/32"
+        );
+    }
+
+    #[test]
+    fn parse_encode_decode_test() {
+        let cases: Vec<(DataType, &str, Vec<u8>)> = vec![
+            (DataType::String, "user@nas-id", b"user@nas-id".to_vec()),
+            (DataType::Integer, "2", vec![0, 0, 0, 2]),
+            (DataType::Integer, "0x48f90001", vec![0x48, 0xf9, 0, 1]),
+            (DataType::Integer64, "4294967296", vec![0, 0, 0, 1, 0, 0, 0, 0]),
+            (DataType::Ipv4, "127.0.0.2", vec![127, 0, 0, 2]),
+            (
+                DataType::Ipv6,
+                "2001:db8::1",
+                vec![0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            ),
+            (DataType::Octets, "0x00ff10", vec![0, 0xff, 0x10]),
+            (DataType::Byte, "6", vec![6]),
+            (
+                DataType::Array(Box::new(DataType::Ipv4)),
+                "10.0.0.1,10.0.0.2",
+                vec![10, 0, 0, 1, 10, 0, 0, 2],
+            ),
+            (DataType::Short, "1812", vec![0x07, 0x14]),
+            (DataType::Signed, "-2", vec![0xff, 0xff, 0xff, 0xfe]),
+            (DataType::Date, "1622548800", vec![0x60, 0xb6, 0x21, 0x40]),
+            (DataType::Date, "2021-06-01T12:00:00Z", vec![0x60, 0xb6, 0x21, 0x40]),
+            (DataType::Date, "2021-06-01T14:00:00.5+02:00", vec![0x60, 0xb6, 0x21, 0x40]),
+            (DataType::TimeDelta, "3600", vec![0, 0, 0x0e, 0x10]),
+            (DataType::Ipv4Prefix, "10.0.0.0/8", vec![0, 8, 10, 0, 0, 0]),
+            (DataType::Ipv4Prefix, "192.0.2.1", vec![0, 32, 192, 0, 2, 1]),
+            (DataType::Ipv6Prefix, "2001:db8::/32", vec![0, 32, 0x20, 0x01, 0x0d, 0xb8]),
+            (DataType::Ipv6Prefix, "2001:db8::/29", vec![0, 29, 0x20, 0x01, 0x0d, 0xb8]),
+            (DataType::Ipv6Prefix, "::/0", vec![0, 0]),
+            (
+                DataType::Ifid,
+                "0211:22ff:fe33:4455",
+                vec![0x02, 0x11, 0x22, 0xff, 0xfe, 0x33, 0x44, 0x55],
+            ),
+            (DataType::ComboIp, "192.0.2.1", vec![192, 0, 2, 1]),
+            (
+                DataType::ComboIp,
+                "2001:db8::1",
+                vec![0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            ),
+            (
+                DataType::Array(Box::new(DataType::Short)),
+                "1,2",
+                vec![0, 1, 0, 2],
+            ),
+        ];
+
+        for (data_type, input, encoded) in cases {
+            let value = AttributeValue::parse(&data_type, input).unwrap();
+            assert_eq!(value.encode(), encoded);
+            assert_eq!(AttributeValue::decode(&data_type, &encoded).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn invalid_values_test() {
+        assert!(AttributeValue::parse(&DataType::Integer, "4294967296").is_err());
+        assert!(AttributeValue::parse(&DataType::Integer, "-1").is_err());
+        assert!(AttributeValue::parse(&DataType::Integer, "abc").is_err());
+        assert!(AttributeValue::parse(&DataType::Byte, "256").is_err());
+        assert!(AttributeValue::parse(&DataType::Ipv4, "127.0.0.256").is_err());
+        assert!(AttributeValue::parse(&DataType::Ipv6, "127.0.0.1").is_err());
+        assert!(AttributeValue::parse(&DataType::Octets, "0xabc").is_err());
+        assert!(AttributeValue::from_integer(&DataType::Ipv4, 1).is_err());
+        assert!(AttributeValue::parse(&DataType::Short, "65536").is_err());
+        assert!(AttributeValue::parse(&DataType::Signed, "2147483648").is_err());
+        assert!(AttributeValue::parse(&DataType::Date, "1969-12-31T23:59:59Z").is_err());
+        assert!(AttributeValue::parse(&DataType::Date, "2021-13-01T00:00:00Z").is_err());
+        assert!(AttributeValue::parse(&DataType::Date, "2021-06-01T12:00:00").is_err());
+        for date in [
+            "2024-02-30T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "2100-02-29T00:00:00Z",
+            "2021-04-31T00:00:00Z",
+            "2107-01-01T00:00:00Z",
+            "99999999999999999-01-01T00:00:00Z",
+            "2021-06-01T12:00:00+99999999999999:00",
+        ] {
+            assert!(AttributeValue::parse(&DataType::Date, date).is_err(), "{}", date);
+        }
+        assert!(AttributeValue::parse(&DataType::Ipv4Prefix, "10.0.0.1/8").is_err());
+        assert!(AttributeValue::parse(&DataType::Ipv4Prefix, "10.0.0.0/33").is_err());
+        assert!(AttributeValue::parse(&DataType::Ipv6Prefix, "2001:db8::/129").is_err());
+        assert!(AttributeValue::parse(&DataType::Ifid, "0211:22ff:fe33").is_err());
+        assert!(AttributeValue::parse(&DataType::Ifid, "0211:22ff:fe33:44550").is_err());
+        assert!(AttributeValue::parse(&DataType::ComboIp, "localhost").is_err());
+
+        assert!(AttributeValue::decode(&DataType::Integer, &[0, 1]).is_err());
+        assert!(AttributeValue::decode(&DataType::Array(Box::new(DataType::Ipv6)), &[0; 17]).is_err());
+        assert!(AttributeValue::decode(&DataType::Ipv4Prefix, &[1, 8, 10, 0, 0, 0]).is_err());
+        assert!(AttributeValue::decode(&DataType::Ipv6Prefix, &[0]).is_err());
+        assert!(AttributeValue::decode(&DataType::Ipv6Prefix, &[0; 19]).is_err());
+        assert!(AttributeValue::decode(&DataType::ComboIp, &[0; 6]).is_err());
+    }
+
+    #[test]
+    fn structured_values_test() {
+        let value =
+            AttributeValue::parse(&DataType::UserLocationInfo, "type=TAI mcc=262 mnc=01 tac=1").unwrap();
+        assert_eq!(value.encode(), vec![128, 0x62, 0xf2, 0x10, 0, 1]);
+        assert_eq!(value.to_string(), "type=TAI mcc=262 mnc=01 tac=1");
+        assert_eq!(
+            AttributeValue::decode(&DataType::UserLocationInfo, &value.encode()).unwrap(),
+            value
+        );
+        assert_eq!(
+            AttributeValue::parse(&DataType::UserLocationInfo, "0x8062f2100001").unwrap(),
+            value
+        );
+
+        let value = AttributeValue::parse(&DataType::MsTimeZone, "offset=+02:00 dst=1").unwrap();
+        assert_eq!(value.encode(), vec![0x80, 0x01]);
+        assert_eq!("packet-filter".parse::<DataType>().unwrap(), DataType::PacketFilter);
+
+        assert!(AttributeValue::parse(&DataType::MsTimeZone, "offset=+02:00 dst").is_err());
+        assert!(AttributeValue::parse(&DataType::PacketFilter, "id=1").is_err());
+        assert!(AttributeValue::decode(&DataType::MsTimeZone, &[0x80]).is_err());
+        assert!(AttributeValue::from_fields(&DataType::Integer, &vec![]).is_err());
+    }
+}
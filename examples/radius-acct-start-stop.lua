@@ -6,6 +6,11 @@
 --  * sleep for 5 seconds
 --  * RADIUS Accounting-Stop
 --
+-- Sessions of all the subscribers are running concurrently, a session that
+-- waits for a reply or sleeps yields to the others. Global variables are
+-- shared by all the sessions, so the per-session data should be kept in
+-- locals and the session `state`.
+--
 
 -- Import libwl lib to acccess whirl API
 local libwl = require('libwl')
@@ -15,9 +20,18 @@ local libwl = require('libwl')
 --
 -- Following configuration items:
 --
---  * workers - Number of threads to use
+--  * workers - Number of threads to use, every worker runs the scenario
+--    within its own Lua context, so globals are not shared between them
 --  * radius_servers - List of authorization and accounting RADIUS servers
 --  * imsi_range = Range of IMSI(s) that will be used on per-user basis
+--  * max_errors - Stop the run after this number of aborted sessions
+--  * radius - RADIUS protocol options:
+--     * message_authenticator - add Message-Authenticator to Access-Requests,
+--       Status-Server packets (`code = 'Status-Server'`) always have it
+--     * require_message_authenticator - reject replies to Access-Request and
+--       Status-Server without valid Message-Authenticator
+--     * timeout - seconds to wait for a reply before retransmission (3)
+--     * retries - number of retransmissions of a request (2)
 --
 workload = {
    workers = 4,
@@ -25,7 +39,14 @@ workload = {
       auth = '127.0.0.1:1812',
       acct = '127.0.0.1:1813'
    },
-   imsi_range = '262019876543200-262019876543201'
+   imsi_range = '262019876543200-262019876543201',
+   max_errors = 100,
+   radius = {
+      message_authenticator = true,
+      require_message_authenticator = true,
+      timeout = 3,
+      retries = 2
+   }
 }
 
 -- Entry point
@@ -33,20 +54,25 @@ function run(state)
    -- Build RADIUS Access-Requst packet
    state = radius_auth(state)
 
-   -- Send RADIUS Access-Request packet
-   state = radius_send(workload['radius_servers']['auth'], state['packet'], 'secret')
+   -- Send RADIUS Access-Request packet, the result passed to the callbacks
+   -- of the packet is returned
+   local result = radius_send(workload['radius_servers']['auth'], state['packet'], 'secret')
+   state['Framed-IP-Address'] = result['Framed-IP-Address']
+
+   -- Build RADIUS Accounting-Start packet
+   state = radius_acct_start(state)
 
-   -- -- Build RADIUS Accounting-Start packet
-   -- state = radius_acct_start(state)
+   -- Send RADIUS Accounting-Request Start packet
+   radius_send(workload['radius_servers']['acct'], state['packet'], 'secret')
 
-   -- -- Send RADIUS Accounting-Request Start packet
-   -- state = libwl.radius_send(workload['radius_servers']['acct'], state['packet'], 'secret')
+   -- Sessions of other subscribers are running while this one sleeps
+   libwl.sleep(5)
 
-   -- -- Build RADIUS Accounting-Request Stop packet
-   -- state = radius_acct_start(state)
+   -- Build RADIUS Accounting-Request Stop packet
+   state = radius_acct_stop(state)
 
-   -- -- Send RADIUS Accounting-Request Stop packet
-   -- state = libwl.radius_send(workload['radius_servers']['acct'], state['packet'], 'secret')
+   -- Send RADIUS Accounting-Request Stop packet
+   radius_send(workload['radius_servers']['acct'], state['packet'], 'secret')
 
    return
 end
@@ -54,34 +80,60 @@ end
 -- Build RADIUS Access-Request packet
 function radius_auth(state)
    -- Initialize empty RADIUS packet
-   packet = {}
+   local packet = {}
 
    -- Fill RADIUS packet with attributes
    packet['3GPP-IMSI'] = state['3GPP-IMSI']
-   packet['3GPP-RAT-Type'] = 6
+   packet['3GPP-RAT-Type'] = 'EUTRAN'
    packet['User-Name'] = 'username' .. '@' .. 'nas-id'
+   packet['User-Password'] = 'password'
+   -- CHAP could be used instead, CHAP-Password is calculated from the
+   -- cleartext password and the Request Authenticator or CHAP-Challenge
+   -- packet['CHAP-Password'] = {chap='password'}
    packet['NAS-IP-Address'] = '127.0.0.1'
    packet['3GPP-SGSN-MCC-MNC'] = '40101'
-   packet['Service-Type'] = 2
+   packet['Service-Type'] = 'Framed-User'
    packet['Called-Station-Id'] = '00-10-A4-23-19-C0'
    packet['Calling-Station-Id'] = '00-10-A4-23-19-C0'
    packet['3GPP-IMEISV'] = '35-209900-176148-87'
    packet['3GPP-SGSN-Address'] = '127.0.0.2'
 
-   -- RADIUS response callback
+   -- Structured 3GPP attributes are given as tables of their fields
+   packet['3GPP-User-Location-Info'] = {type='TAI+ECGI', mcc='262', mnc='01', tac=1, eci=42}
+   packet['3GPP-MS-TimeZone'] = {offset='+02:00', dst=1}
+
+   -- RADIUS response callback, the state contains:
+   --
+   --  * code - code of the reply, like 'Access-Accept'
+   --  * response - attributes of the reply by their names
+   --  * latency - seconds passed since the request was sent
+   --
    packet['on_response'] = function(state)
       -- Try to get Framed-IP-Address from response
-      ip = state['response']['Framed-IP-Address']
+      local ip = state['response']['Framed-IP-Address']
 
       -- Check if we have Framed-IP-Address within response put
       -- into state
       if ip ~= nil then
          state['Framed-IP-Address'] = ip
       else
-         libwl.stop_with_error()
+         -- abort the session of this subscriber, the reason is counted
+         -- in the results of the run
+         libwl.stop_with_error('no Framed-IP-Address')
       end
    end
 
+   -- Called on Access-Reject instead of on_response
+   packet['on_reject'] = function(state)
+      libwl.stop_with_error('rejected')
+   end
+
+   -- Called if there is no reply after all the retransmissions
+   packet['on_timeout'] = function(state)
+      -- the whole run could be stopped with the 'run' scope
+      libwl.stop_with_error('server is down', 'run')
+   end
+
    -- put packet into the state machine
    state['packet'] = packet
 
@@ -92,11 +144,11 @@ end
 -- Build RADIUS Accounting-Start packet
 function radius_acct_start(state)
    -- Initialize empty RADIUS packet
-   packet = {}
+   local packet = {}
 
    -- Fill RADIUS packet with attributes
-   packet['Acct-Status-Type'] = 1
-   packet['Framed-IP-Protocol'] = 2
+   packet['Acct-Status-Type'] = 'Start'
+   packet['Framed-Protocol'] = 'PPP'
    packet['3GPP-IMSI'] = state['3GPP-IMSI']
    packet['Framed-IP-Address'] = state['Framed-IP-Address']
    packet['Acct-Session-Id'] = 'session-' .. '-' .. state['3GPP-IMSI']
@@ -111,11 +163,11 @@ end
 -- Build RADIUS Accounting-Stop packet
 function radius_acct_stop(state)
    -- Initialize empty RADIUS packet
-   packet = {}
+   local packet = {}
 
    -- Fill RADIUS packet with attributes
-   packet['Acct-Status-Type'] = 2
-   packet['Framed-IP-Protocol'] = 2
+   packet['Acct-Status-Type'] = 'Stop'
+   packet['Framed-Protocol'] = 'PPP'
    packet['3GPP-IMSI'] = state['3GPP-IMSI']
    packet['Framed-IP-Address'] = state['Framed-IP-Address']
    packet['Acct-Session-Id'] = 'session-' .. '-' .. state['3GPP-IMSI']
@@ -4,20 +4,73 @@ extern crate radius;
 use std::env;
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::Arc;
 
-use radius::dictionary::{load_dictionaries, DictionarySet};
+use radius::dictionary::{
+    export_dictionaries, lint_dictionaries, load_dictionaries, load_dictionaries_as, Dictionary,
+    DictionaryFormat, DictionarySet, ExportFormat,
+};
+use radius::error::RadiusError;
 
 use getopts::{HasArg, Occur, Options};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn print_usage(opts: Options) {
-    let brief = format!("Usage: whirl -s SCRIPT [options]");
+    let brief = format!(
+        "Usage: whirl -s SCRIPT [options]\n       \
+         whirl dict lint [options]\n       \
+         whirl dict export freeradius|wireshark DIR [options]"
+    );
     print!("{}", opts.usage(&brief));
 }
 
+// Checks the RADIUS dictionaries and prints all the found problems. Returns
+// the exit code of whirl.
+fn lint(dicts_set: DictionarySet, dicts_dirs: &[PathBuf]) -> i32 {
+    match lint_dictionaries(dicts_set, dicts_dirs) {
+        Ok(problems) if problems.is_empty() => 0,
+        Ok(problems) => {
+            for problem in problems.iter() {
+                eprintln!("Error: {:?}", problem);
+            }
+            eprintln!("Error: {} problem(s) found in RADIUS dictionaries", problems.len());
+            1
+        }
+        Err(err) => {
+            eprintln!("Error: Can't load RADIUS dictionaries - {:?}", err);
+            1
+        }
+    }
+}
+
+// Exports the RADIUS dictionaries into the directory `dir`. Returns the exit
+// code of whirl.
+fn export(dict: Result<Dictionary, RadiusError>, format: ExportFormat, dir: &str) -> i32 {
+    let dict = match dict {
+        Ok(dict) => dict,
+        Err(err) => {
+            eprintln!("Error: Can't load RADIUS dictionaries - {:?}", err);
+            return 1;
+        }
+    };
+
+    match export_dictionaries(&dict, format, Path::new(dir)) {
+        Ok(files) => {
+            for file in files {
+                println!("{}", file.display());
+            }
+            0
+        }
+        Err(err) => {
+            eprintln!("Error: Can't export RADIUS dictionaries - {:?}", err);
+            1
+        }
+    }
+}
+
 fn main() {
     let argv: Vec<String> = env::args().collect();
     let mut opts = Options::new();
@@ -25,9 +78,25 @@ fn main() {
     opts.opt(
         "d",
         "dictionaries",
-        "path to the directory with RADIUS and Diameter dictionaries",
+        "path to the directory with RADIUS and Diameter dictionaries, could be repeated",
         "DIR",
         HasArg::Yes,
+        Occur::Multi,
+    );
+    opts.opt(
+        "r",
+        "radius-dictionary",
+        "name of the RADIUS dictionary to load, all dictionaries are loaded if not given",
+        "NAME",
+        HasArg::Yes,
+        Occur::Multi,
+    );
+    opts.opt(
+        "",
+        "dictionary-format",
+        "format of RADIUS dictionaries (yaml or freeradius), detected by file names if not given",
+        "FORMAT",
+        HasArg::Yes,
         Occur::Optional,
     );
     opts.opt(
@@ -49,7 +118,7 @@ fn main() {
     opts.opt(
         "t",
         "threads",
-        "number of threads to use",
+        "number of threads to use, the workers of the workload by default",
         "THREADS",
         HasArg::Yes,
         Occur::Optional,
@@ -75,6 +144,43 @@ fn main() {
         process::exit(0);
     }
 
+    let dicts_dirs: Vec<PathBuf> = matches.opt_strs("d").into_iter().map(PathBuf::from).collect();
+    let dicts_set = match matches.opt_strs("r") {
+        names if names.is_empty() => DictionarySet::All,
+        names => DictionarySet::Set(names),
+    };
+
+    let dicts_format = match matches.opt_str("dictionary-format").as_deref() {
+        None => None,
+        Some("yaml") => Some(DictionaryFormat::Yaml),
+        Some("freeradius") => Some(DictionaryFormat::FreeRadius),
+        Some(format) => {
+            eprintln!("Error: unknown RADIUS dictionary format '{}'", format);
+            process::exit(1);
+        }
+    };
+
+    let load = |dicts_set: DictionarySet| match dicts_format {
+        None => load_dictionaries(dicts_set, &dicts_dirs),
+        Some(format) => load_dictionaries_as(dicts_set, &dicts_dirs, format),
+    };
+
+    let command: Vec<&str> = matches.free.iter().map(|s| s.as_str()).collect();
+    match command.as_slice() {
+        [] => {}
+        ["dict", "lint"] => process::exit(lint(dicts_set, &dicts_dirs)),
+        ["dict", "export", "freeradius", dir] => {
+            process::exit(export(load(dicts_set), ExportFormat::FreeRadius, dir))
+        }
+        ["dict", "export", "wireshark", dir] => {
+            process::exit(export(load(dicts_set), ExportFormat::Wireshark, dir))
+        }
+        _ => {
+            eprintln!("Error: unknown command '{}'", command.join(" "));
+            process::exit(1);
+        }
+    }
+
     let mut io_engine = libwl::ev::IOEngine::WIO;
     let engine = matches.opt_str("e");
     match engine {
@@ -90,14 +196,12 @@ fn main() {
         }
     }
 
-    let mut threads = matches.opt_str("t");
-    if threads == None {
-        threads = Some("4".to_string());
-    }
-    let threads = match threads.unwrap().parse::<u8>() {
-        Ok(t) => t,
-        Err(_e) => {
-            eprintln!("Error: -t/--threads should have numeric value");
+    // the number of `workers` of the workload is used if not given
+    let threads = match matches.opt_str("t").map(|t| t.parse::<u8>()) {
+        None => None,
+        Some(Ok(t)) if t > 0 => Some(t),
+        Some(_) => {
+            eprintln!("Error: -t/--threads should have positive numeric value");
             process::exit(1);
         }
     };
@@ -120,13 +224,8 @@ fn main() {
     let mut script = String::new();
     script_file.unwrap().read_to_string(&mut script).unwrap();
 
-    let dicts_dir = matches.opt_str("d").and_then(|dir| {
-        let mut p = PathBuf::new();
-        p.push(dir);
-        Some(p)
-    });
-    let radius_dictionaries = match load_dictionaries(DictionarySet::All, dicts_dir) {
-        Ok(d) => { d }
+    let radius_dictionaries = match load(dicts_set) {
+        Ok(d) => Arc::new(d),
         Err(err) => {
             eprintln!("Error: Can't load RADIUS dictionaries - {:?}", err);
             process::exit(1);
@@ -135,12 +234,22 @@ fn main() {
 
     // load the scenario script, build event loop configuration
     // and start execution
-    let sceneario = libwl::load(script.as_ref()).unwrap();
+    let sceneario = match libwl::load(script.as_ref()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error: Can't load scenario configuration - {}", err);
+            process::exit(1);
+        }
+    };
     let mut ev = libwl::ev::Ev::new();
+    if let Some(threads) = threads {
+        ev.set_threads(threads);
+    }
 
-    ev.set_threads(threads)
+    let code = ev
         .set_io_engine(io_engine)
+        .set_dictionary(radius_dictionaries)
         .run(&sceneario);
 
-    process::exit(0);
+    process::exit(code);
 }